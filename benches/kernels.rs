@@ -0,0 +1,89 @@
+//! Benchmarks of the individual hot paths of the renderer:
+//! intersection, transformation, shading and random number
+//! generation. Run with `cargo bench`.
+
+use std::hint::black_box;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+
+use rt::*;
+
+fn unit_square() -> Poly {
+    Poly::new([
+        Vec2::new(-0.5, -0.5),
+        Vec2::new(-0.5, 0.5),
+        Vec2::new(0.5, 0.5),
+        Vec2::new(0.5, -0.5),
+    ])
+}
+
+fn bench_intersect(c: &mut Criterion) {
+    let xform = Xform::translation(&Vec3::new(0.0, 0.0, 3.0));
+    let hit = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.1, 0.1, 1.0));
+    let miss = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 1.0));
+
+    let mut s = Sphere::default();
+    s.complete(&xform);
+    c.bench_function("sphere intersect hit", |b| {
+        b.iter(|| s.intersect(black_box(&xform), black_box(&hit)))
+    });
+    c.bench_function("sphere intersect miss", |b| {
+        b.iter(|| s.intersect(black_box(&xform), black_box(&miss)))
+    });
+
+    let mut p = unit_square();
+    p.complete(&xform);
+    c.bench_function("poly intersect hit", |b| {
+        b.iter(|| p.intersect(black_box(&xform), black_box(&hit)))
+    });
+    c.bench_function("poly intersect miss", |b| {
+        b.iter(|| p.intersect(black_box(&xform), black_box(&miss)))
+    });
+    let v = Vec2::new(0.1, 0.2);
+    c.bench_function("poly contains", |b| b.iter(|| p.contains(black_box(&v))));
+}
+
+fn bench_xform(c: &mut Criterion) {
+    let mut x = Xform::rotation_y(0.3);
+    x *= &Xform::scaling(&Vec3::new(1.0, 2.0, 3.0));
+    let y = Xform::translation(&Vec3::new(1.0, 2.0, 3.0));
+    let p = Vec3::new(1.0, -1.0, 0.5);
+    c.bench_function("xform compose", |b| {
+        b.iter(|| {
+            let mut z = *black_box(&x);
+            z *= black_box(&y);
+            z
+        })
+    });
+    c.bench_function("xform apply point", |b| {
+        b.iter(|| black_box(&x).apply_point(black_box(&p)))
+    });
+}
+
+fn bench_shade(c: &mut Criterion) {
+    seed(None);
+    let m = Model::generate(Thing::generate());
+    let kblue = Color::new(0.3, 0.3, 1.0);
+    let kblack = Color::new(0.0, 0.0, 0.0);
+    let t = SolidTexture::new(kblack, kblue * 0.2, kblue * 0.75, 100.0);
+    let at = Vec2::new(0.0, 0.0);
+    let gc = Vec3::new(0.0, 0.0, 8.0);
+    let normal = Vec3::new(0.0, 0.0, -1.0);
+    c.bench_function("solid texture value", |b| {
+        b.iter(|| t.value(black_box(&at), black_box(&gc), black_box(&normal), &m, 0))
+    });
+}
+
+fn bench_frandom(c: &mut Criterion) {
+    seed(None);
+    c.bench_function("frandom", |b| b.iter(frandom));
+}
+
+criterion_group!(
+    benches,
+    bench_intersect,
+    bench_xform,
+    bench_shade,
+    bench_frandom
+);
+criterion_main!(benches);
@@ -0,0 +1,39 @@
+//! Whole-image render benchmarks. Run with `cargo bench`.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+
+use rt::*;
+
+/// Output that throws its pixels away.
+struct NullOutput;
+
+impl Output for NullOutput {
+    fn put_pixel(&mut self, _x: usize, _y: usize, _c: Color) {}
+    fn flush_row(&mut self) {}
+}
+
+fn bench_render(c: &mut Criterion) {
+    // The scene joggles its objects with the PRNG, so seed
+    // it to get the same scene on every run.
+    seed(None);
+    let m = Model::generate(Thing::generate());
+
+    let mut g = c.benchmark_group("render");
+    for &(w, h) in &[(80, 60), (160, 120), (320, 240)] {
+        g.bench_with_input(
+            BenchmarkId::new("sequential", format!("{}x{}", w, h)),
+            &(w, h),
+            |b, &(w, h)| b.iter(|| render(NullOutput, &m, w, h, true, None)),
+        );
+    }
+    g.bench_function("antialias 4 80x60", |b| {
+        b.iter(|| {
+            seed(None);
+            render(NullOutput, &m, 80, 60, true, Some(4))
+        })
+    });
+    g.finish();
+}
+
+criterion_group!(benches, bench_render);
+criterion_main!(benches);
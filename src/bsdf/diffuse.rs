@@ -0,0 +1,113 @@
+use crate::*;
+
+// Cosine-weighted sample on the side of `wo`, for the
+// diffuse models.
+fn sample_diffuse<B: Bsdf + ?Sized>(bsdf: &B, wo: &Vec3, u: &Vec3) -> Option<BsdfSample> {
+    let mut wi = cosine_hemisphere(u[Y], u[Z]);
+    if wo[Z] < 0.0 {
+        wi[Z] = -wi[Z];
+    }
+    let pdf = bsdf.pdf(wo, &wi);
+    if pdf <= 0.0 {
+        return None;
+    }
+    Some(BsdfSample {
+        wi,
+        f: bsdf.eval(wo, &wi),
+        pdf,
+        specular: false,
+    })
+}
+
+// Density of a cosine-weighted sample.
+fn pdf_diffuse(wo: &Vec3, wi: &Vec3) -> f64 {
+    if same_hemisphere(wo, wi) {
+        wi[Z].abs() / PI
+    } else {
+        0.0
+    }
+}
+
+/// Lambert's ideal diffuse reflector, equally bright from
+/// every direction.
+#[derive(Clone, Copy)]
+pub struct Lambert {
+    /// Fraction of light reflected.
+    pub albedo: Color,
+}
+
+impl Bsdf for Lambert {
+    fn eval(&self, wo: &Vec3, wi: &Vec3) -> Color {
+        if same_hemisphere(wo, wi) {
+            self.albedo * (1.0 / PI)
+        } else {
+            Color::new(0.0, 0.0, 0.0)
+        }
+    }
+
+    fn pdf(&self, wo: &Vec3, wi: &Vec3) -> f64 {
+        pdf_diffuse(wo, wi)
+    }
+
+    fn sample(&self, wo: &Vec3, u: &Vec3) -> Option<BsdfSample> {
+        sample_diffuse(self, wo, u)
+    }
+}
+
+/// The Oren-Nayar rough diffuse reflector: a surface of
+/// Lambertian V-shaped facets, which looks flatter than a
+/// Lambertian one and brighter back toward the light.
+#[derive(Clone, Copy)]
+pub struct OrenNayar {
+    pub albedo: Color,
+    a: f64,
+    b: f64,
+}
+
+impl OrenNayar {
+    /// Reflector whose facet slopes have standard deviation
+    /// `sigma` radians. A `sigma` of 0 is Lambertian.
+    pub fn new(albedo: Color, sigma: f64) -> Self {
+        let s2 = sigma * sigma;
+        Self {
+            albedo,
+            a: 1.0 - s2 / (2.0 * (s2 + 0.33)),
+            b: 0.45 * s2 / (s2 + 0.09),
+        }
+    }
+}
+
+impl Bsdf for OrenNayar {
+    fn eval(&self, wo: &Vec3, wi: &Vec3) -> Color {
+        if !same_hemisphere(wo, wi) {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+        let sin_theta = |w: &Vec3| (1.0 - w[Z] * w[Z]).max(0.0).sqrt();
+        let (sin_i, sin_o) = (sin_theta(wi), sin_theta(wo));
+
+        // Cosine of the difference in azimuth.
+        let mut max_cos = 0.0;
+        if sin_i > TINY && sin_o > TINY {
+            let d = (wi[X] * wo[X] + wi[Y] * wo[Y]) / (sin_i * sin_o);
+            max_cos = d.max(0.0);
+        }
+
+        // Sine and tangent of the larger and smaller polar
+        // angles respectively.
+        let (cos_i, cos_o) = (wi[Z].abs(), wo[Z].abs());
+        let (sin_alpha, tan_beta) = if cos_i > cos_o {
+            (sin_o, sin_i / cos_i)
+        } else {
+            (sin_i, sin_o / cos_o)
+        };
+        self.albedo * ((self.a + self.b * max_cos * sin_alpha * tan_beta) / PI)
+    }
+
+    fn pdf(&self, wo: &Vec3, wi: &Vec3) -> f64 {
+        pdf_diffuse(wo, wi)
+    }
+
+    fn sample(&self, wo: &Vec3, u: &Vec3) -> Option<BsdfSample> {
+        sample_diffuse(self, wo, u)
+    }
+}
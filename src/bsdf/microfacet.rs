@@ -0,0 +1,372 @@
+use crate::*;
+
+/// Shape of the distribution of microfacet normals.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Distribution {
+    /// Trowbridge-Reitz, with long tails that give bright
+    /// halos around highlights.
+    Ggx,
+    /// Beckmann's Gaussian slopes.
+    Beckmann,
+}
+
+/// A rough surface made of tiny mirror facets, whose
+/// normals scatter around the surface normal.
+#[derive(Clone, Copy, Debug)]
+pub struct Microfacets {
+    pub distribution: Distribution,
+    /// Width of the distribution of slopes.
+    pub alpha: f64,
+}
+
+impl Microfacets {
+    /// Facets of the given perceptual roughness from 0,
+    /// perfectly smooth, to 1, very rough. The slope width
+    /// is the square of the roughness.
+    pub fn new(distribution: Distribution, roughness: f64) -> Self {
+        let r = roughness.clamp(0.0, 1.0);
+        Self {
+            distribution,
+            alpha: r * r,
+        }
+    }
+
+    /// Whether the facets are so nearly flat that the
+    /// surface is best taken as a perfect mirror.
+    pub fn is_smooth(&self) -> bool {
+        self.alpha < 1.0e-3
+    }
+
+    /// Density of facet normals `wh`, per unit area of
+    /// surface and solid angle.
+    pub fn d(&self, wh: &Vec3) -> f64 {
+        let cos2 = wh[Z] * wh[Z];
+        if cos2 < TINY {
+            return 0.0;
+        }
+        let tan2 = (1.0 - cos2) / cos2;
+        let a2 = self.alpha * self.alpha;
+        match self.distribution {
+            Distribution::Ggx => {
+                let e = 1.0 + tan2 / a2;
+                1.0 / (PI * a2 * cos2 * cos2 * e * e)
+            }
+            Distribution::Beckmann => (-tan2 / a2).exp() / (PI * a2 * cos2 * cos2),
+        }
+    }
+
+    // Smith's auxiliary function: the ratio of hidden to
+    // visible facet area seen from `w`.
+    fn lambda(&self, w: &Vec3) -> f64 {
+        let cos2 = w[Z] * w[Z];
+        if cos2 < TINY {
+            return f64::INFINITY;
+        }
+        let tan2 = (1.0 - cos2) / cos2;
+        match self.distribution {
+            Distribution::Ggx => ((1.0 + self.alpha * self.alpha * tan2).sqrt() - 1.0) / 2.0,
+            Distribution::Beckmann => {
+                let a = 1.0 / (self.alpha * tan2.sqrt());
+                if a >= 1.6 {
+                    0.0
+                } else {
+                    (1.0 - 1.259 * a + 0.396 * a * a) / (3.535 * a + 2.181 * a * a)
+                }
+            }
+        }
+    }
+
+    /// Fraction of facets seen from both `wo` and `wi`.
+    pub fn g(&self, wo: &Vec3, wi: &Vec3) -> f64 {
+        1.0 / (1.0 + self.lambda(wo) + self.lambda(wi))
+    }
+
+    /// Draw a facet normal on the +z side with density
+    /// `d(wh) cos θh`, from two uniform numbers.
+    pub fn sample_wh(&self, u0: f64, u1: f64) -> Vec3 {
+        let a2 = self.alpha * self.alpha;
+        let tan2 = match self.distribution {
+            Distribution::Ggx => a2 * u0 / (1.0 - u0).max(TINY),
+            Distribution::Beckmann => -a2 * (1.0 - u0).max(TINY).ln(),
+        };
+        let cos = 1.0 / (1.0 + tan2).sqrt();
+        let sin = (1.0 - cos * cos).max(0.0).sqrt();
+        let phi = 2.0 * PI * u1;
+        Vec3::new(sin * phi.cos(), sin * phi.sin(), cos)
+    }
+
+    /// Density with which `sample_wh()` draws `wh`.
+    pub fn pdf_wh(&self, wh: &Vec3) -> f64 {
+        self.d(wh) * wh[Z].abs()
+    }
+}
+
+/// Common metals, for `Conductor::metal()`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Metal {
+    Aluminum,
+    Copper,
+    Gold,
+    Silver,
+}
+
+impl Metal {
+    /// Complex index of refraction `eta + ik` of the metal,
+    /// at red, green and blue wavelengths.
+    pub fn ior(self) -> (Color, Color) {
+        let (eta, k) = match self {
+            Metal::Aluminum => ([1.657, 0.880, 0.521], [9.224, 6.269, 4.837]),
+            Metal::Copper => ([0.200, 0.924, 1.102], [3.912, 2.452, 2.142]),
+            Metal::Gold => ([0.143, 0.374, 1.442], [3.983, 2.385, 1.603]),
+            Metal::Silver => ([0.155, 0.117, 0.138], [4.828, 3.122, 2.147]),
+        };
+        (
+            Color::new(eta[0], eta[1], eta[2]),
+            Color::new(k[0], k[1], k[2]),
+        )
+    }
+}
+
+// Color of the same value in every channel.
+fn gray(v: f64) -> Color {
+    Color::new(v, v, v)
+}
+
+/// A metal: microfacets reflecting by the Fresnel equations
+/// for a complex index of refraction, as in the
+/// Cook-Torrance model.
+#[derive(Clone, Copy)]
+pub struct Conductor {
+    /// Real part of the index of refraction, per channel.
+    pub eta: Color,
+    /// Imaginary part of the index of refraction: the
+    /// absorption, per channel.
+    pub k: Color,
+    /// Multiplier on the reflected light, for artistic
+    /// control.
+    pub tint: Color,
+    pub facets: Microfacets,
+}
+
+impl Conductor {
+    pub fn new(eta: Color, k: Color, facets: Microfacets) -> Self {
+        Self {
+            eta,
+            k,
+            tint: gray(1.0),
+            facets,
+        }
+    }
+
+    pub fn metal(metal: Metal, facets: Microfacets) -> Self {
+        let (eta, k) = metal.ior();
+        Self::new(eta, k, facets)
+    }
+
+    // Fresnel reflectance for light at cosine `cos` to a
+    // facet.
+    fn fresnel(&self, cos: f64) -> Color {
+        fresnel_conductor(cos, &self.eta, &self.k).colorize(&self.tint)
+    }
+}
+
+impl Bsdf for Conductor {
+    fn eval(&self, wo: &Vec3, wi: &Vec3) -> Color {
+        if self.facets.is_smooth() || !same_hemisphere(wo, wi) {
+            return gray(0.0);
+        }
+        let wh = *wo + *wi;
+        if wh.mag2() == 0.0 {
+            return gray(0.0);
+        }
+        let wh = wh.unit();
+        let d = self.facets.d(&wh) * self.facets.g(wo, wi) / (4.0 * (wo[Z] * wi[Z]).abs());
+        self.fresnel(*wi * wh) * d
+    }
+
+    fn pdf(&self, wo: &Vec3, wi: &Vec3) -> f64 {
+        if self.facets.is_smooth() || !same_hemisphere(wo, wi) {
+            return 0.0;
+        }
+        let wh = (*wo + *wi).unit();
+        self.facets.pdf_wh(&wh) / (4.0 * (*wo * wh).abs())
+    }
+
+    fn sample(&self, wo: &Vec3, u: &Vec3) -> Option<BsdfSample> {
+        if wo[Z] == 0.0 {
+            return None;
+        }
+        if self.facets.is_smooth() {
+            let wi = Vec3::new(-wo[X], -wo[Y], wo[Z]);
+            return Some(BsdfSample {
+                wi,
+                f: self.fresnel(wi[Z]) * (1.0 / wi[Z].abs()),
+                pdf: 1.0,
+                specular: true,
+            });
+        }
+        let mut wh = self.facets.sample_wh(u[Y], u[Z]);
+        if wo[Z] < 0.0 {
+            wh = -wh;
+        }
+        let wi = reflect(wo, &wh);
+        if !same_hemisphere(wo, &wi) {
+            return None;
+        }
+        Some(BsdfSample {
+            wi,
+            f: self.eval(wo, &wi),
+            pdf: self.pdf(wo, &wi),
+            specular: false,
+        })
+    }
+
+    fn specular(&self, wo: &Vec3) -> Vec<(Vec3, Color)> {
+        if !self.facets.is_smooth() {
+            return Vec::new();
+        }
+        vec![(Vec3::new(-wo[X], -wo[Y], wo[Z]), self.fresnel(wo[Z]))]
+    }
+}
+
+/// Glass, water and the like: microfacets that reflect and
+/// refract by the Fresnel equations, after Walter et al.
+/// Transmitted light is scaled by the square of the change
+/// in index of refraction, as radiance is.
+#[derive(Clone, Copy)]
+pub struct Dielectric {
+    /// Index of refraction of the inside, below the
+    /// surface, relative to the outside.
+    pub eta: f64,
+    /// Multiplier on the reflected and transmitted light,
+    /// for artistic control.
+    pub tint: Color,
+    pub facets: Microfacets,
+}
+
+impl Dielectric {
+    pub fn new(eta: f64, facets: Microfacets) -> Self {
+        Self {
+            eta,
+            tint: gray(1.0),
+            facets,
+        }
+    }
+
+    // The relative index of refraction across the surface
+    // for `wo` and `wi`, and the generalized half vector
+    // between them on the +z side, if any.
+    fn half_vector(&self, wo: &Vec3, wi: &Vec3) -> Option<(f64, Vec3)> {
+        let reflecting = same_hemisphere(wo, wi);
+        let etap = match (reflecting, wo[Z] > 0.0) {
+            (true, _) => 1.0,
+            (false, true) => self.eta,
+            (false, false) => 1.0 / self.eta,
+        };
+        let wm = *wi * etap + *wo;
+        if wo[Z] == 0.0 || wi[Z] == 0.0 || wm.mag2() == 0.0 {
+            return None;
+        }
+        let wm = wm.unit();
+        let wm = if wm[Z] < 0.0 { -wm } else { wm };
+        // Discard facets seen from behind.
+        if (wm * *wi) * wi[Z] < 0.0 || (wm * *wo) * wo[Z] < 0.0 {
+            return None;
+        }
+        Some((etap, wm))
+    }
+}
+
+impl Bsdf for Dielectric {
+    fn eval(&self, wo: &Vec3, wi: &Vec3) -> Color {
+        if self.facets.is_smooth() {
+            return gray(0.0);
+        }
+        let Some((etap, wm)) = self.half_vector(wo, wi) else {
+            return gray(0.0);
+        };
+        let f = fresnel_dielectric(*wo * wm, self.eta);
+        let dg = self.facets.d(&wm) * self.facets.g(wo, wi);
+        let v = if etap == 1.0 {
+            dg * f / (4.0 * wi[Z] * wo[Z]).abs()
+        } else {
+            let denom = *wi * wm + (*wo * wm) / etap;
+            let denom = denom * denom * wi[Z] * wo[Z];
+            dg * (1.0 - f) * ((*wi * wm) * (*wo * wm) / denom).abs() / (etap * etap)
+        };
+        self.tint * v
+    }
+
+    fn pdf(&self, wo: &Vec3, wi: &Vec3) -> f64 {
+        if self.facets.is_smooth() {
+            return 0.0;
+        }
+        let Some((etap, wm)) = self.half_vector(wo, wi) else {
+            return 0.0;
+        };
+        let r = fresnel_dielectric(*wo * wm, self.eta);
+        if etap == 1.0 {
+            self.facets.pdf_wh(&wm) / (4.0 * (*wo * wm).abs()) * r
+        } else {
+            let denom = *wi * wm + (*wo * wm) / etap;
+            let dwm_dwi = (*wi * wm).abs() / (denom * denom);
+            self.facets.pdf_wh(&wm) * dwm_dwi * (1.0 - r)
+        }
+    }
+
+    fn sample(&self, wo: &Vec3, u: &Vec3) -> Option<BsdfSample> {
+        if wo[Z] == 0.0 {
+            return None;
+        }
+        if self.facets.is_smooth() {
+            // Reflect or refract in proportion to the
+            // Fresnel reflectance.
+            let paths = self.specular(wo);
+            let r = fresnel_dielectric(wo[Z], self.eta);
+            let (k, pdf) = if u[X] < r || paths.len() == 1 {
+                (0, r)
+            } else {
+                (1, 1.0 - r)
+            };
+            let (wi, c) = paths[k];
+            let pdf = if paths.len() == 1 { 1.0 } else { pdf };
+            return Some(BsdfSample {
+                wi,
+                f: c * (1.0 / wi[Z].abs()),
+                pdf,
+                specular: true,
+            });
+        }
+
+        let wm = self.facets.sample_wh(u[Y], u[Z]);
+        let r = fresnel_dielectric(*wo * wm, self.eta);
+        let wi = if u[X] < r {
+            reflect(wo, &wm)
+        } else {
+            refract(wo, &wm, self.eta)?.0
+        };
+        let pdf = self.pdf(wo, &wi);
+        if pdf <= 0.0 {
+            return None;
+        }
+        Some(BsdfSample {
+            wi,
+            f: self.eval(wo, &wi),
+            pdf,
+            specular: false,
+        })
+    }
+
+    fn specular(&self, wo: &Vec3) -> Vec<(Vec3, Color)> {
+        if !self.facets.is_smooth() {
+            return Vec::new();
+        }
+        let n = Vec3::new(0.0, 0.0, 1.0);
+        let r = fresnel_dielectric(wo[Z], self.eta);
+        let mut paths = vec![(Vec3::new(-wo[X], -wo[Y], wo[Z]), self.tint * r)];
+        if let Some((wt, etap)) = refract(wo, &n, self.eta) {
+            paths.push((wt, self.tint * ((1.0 - r) / (etap * etap))));
+        }
+        paths
+    }
+}
@@ -0,0 +1,290 @@
+//! Bidirectional scattering distribution functions: how
+//! much of the light arriving at a surface from one
+//! direction leaves it in another. Directions are given in
+//! a local shading `Frame` with the normal along +z, and
+//! both point away from the surface.
+
+pub mod diffuse;
+pub use self::diffuse::*;
+
+pub mod microfacet;
+pub use self::microfacet::*;
+
+pub mod principled;
+pub use self::principled::*;
+
+use crate::*;
+
+/// An incident direction drawn from a BSDF.
+#[derive(Clone, Copy)]
+pub struct BsdfSample {
+    /// Direction the light arrives from, in the local frame.
+    pub wi: Vec3,
+    /// Value of the BSDF for the pair of directions. For a
+    /// perfectly specular sample this is the fraction of
+    /// light scattered, divided by the cosine of `wi`.
+    pub f: Color,
+    /// Probability density of `wi` per unit solid angle, or
+    /// for a perfectly specular sample the probability of
+    /// choosing it.
+    pub pdf: f64,
+    /// Whether the sample is a perfectly specular direction.
+    pub specular: bool,
+}
+
+impl BsdfSample {
+    /// Weight of the light arriving along the sample for a
+    /// Monte Carlo estimate of the light leaving: the BSDF
+    /// times the cosine over the density.
+    pub fn weight(&self) -> Color {
+        self.f * (self.wi[Z].abs() / self.pdf)
+    }
+}
+
+pub trait Bsdf: Send + Sync {
+    /// Value of the BSDF for light arriving from `wi` and
+    /// leaving toward `wo`, leaving out any perfectly
+    /// specular part.
+    fn eval(&self, wo: &Vec3, wi: &Vec3) -> Color;
+
+    /// Probability density with which `sample()` draws `wi`
+    /// for `wo`, leaving out any perfectly specular part.
+    fn pdf(&self, wo: &Vec3, wi: &Vec3) -> f64;
+
+    /// Draw an incident direction for light leaving toward
+    /// `wo`, with probability roughly in proportion to its
+    /// contribution, from three numbers uniform on 0 to 1:
+    /// the first picks among lobes and the others place the
+    /// direction.
+    fn sample(&self, wo: &Vec3, u: &Vec3) -> Option<BsdfSample>;
+
+    /// Every perfectly specular incident direction for `wo`,
+    /// with the fraction of light it carries, for a tracer
+    /// that follows them all rather than sampling. By
+    /// default there are none.
+    fn specular(&self, _wo: &Vec3) -> Vec<(Vec3, Color)> {
+        Vec::new()
+    }
+}
+
+/// An orthonormal shading frame: a unit normal and two
+/// unit tangents.
+#[derive(Clone, Copy)]
+pub struct Frame {
+    pub s: Vec3,
+    pub t: Vec3,
+    pub n: Normal3,
+}
+
+impl Frame {
+    /// Frame around the unit normal `n`, with `s` along the
+    /// part of `dpdu` tangent to the surface, or along any
+    /// tangent if there is none.
+    pub fn new(n: &Normal3, dpdu: &Vec3) -> Self {
+        let n = *n;
+        let mut s = *dpdu - n * (n * *dpdu);
+        if s.mag2() < TINY * TINY {
+            // Any axis not along the normal will do.
+            let a = if n[X].abs() < 0.9 {
+                Vec3::new(1.0, 0.0, 0.0)
+            } else {
+                Vec3::new(0.0, 1.0, 0.0)
+            };
+            s = a - n * (n * a);
+        }
+        let s = s.unit();
+        Self {
+            s,
+            t: n.cross_product(&s),
+            n,
+        }
+    }
+
+    /// A world-space direction in the frame.
+    pub fn to_local(&self, v: &Vec3) -> Vec3 {
+        Vec3::new(*v * self.s, *v * self.t, *v * self.n)
+    }
+
+    /// A direction in the frame in world space.
+    pub fn to_world(&self, v: &Vec3) -> Vec3 {
+        self.s * v[X] + self.t * v[Y] + self.n * v[Z]
+    }
+}
+
+/// Whether two local directions are on the same side of
+/// the surface.
+pub fn same_hemisphere(a: &Vec3, b: &Vec3) -> bool {
+    a[Z] * b[Z] > 0.0
+}
+
+/// Mirror `wo` about `n`.
+pub fn reflect(wo: &Vec3, n: &Vec3) -> Vec3 {
+    -*wo + *n * (2.0 * (*wo * *n))
+}
+
+/// Refract `wo` through a surface with normal `n` and
+/// relative index of refraction `eta` (inside over
+/// outside), where the side of `n` that `wo` is on is
+/// outside. Gives the refracted direction and the relative
+/// index along it, or None on total internal reflection.
+pub fn refract(wo: &Vec3, n: &Vec3, eta: f64) -> Option<(Vec3, f64)> {
+    let (mut n, mut eta) = (*n, eta);
+    let mut cos_i = n * *wo;
+    if cos_i < 0.0 {
+        // Leaving the inside.
+        eta = 1.0 / eta;
+        cos_i = -cos_i;
+        n = -n;
+    }
+    let sin2_t = (1.0 - cos_i * cos_i).max(0.0) / (eta * eta);
+    if sin2_t >= 1.0 {
+        return None;
+    }
+    let cos_t = (1.0 - sin2_t).sqrt();
+    Some((-*wo * (1.0 / eta) + n * (cos_i / eta - cos_t), eta))
+}
+
+/// Fresnel reflectance of a dielectric boundary for light
+/// at cosine `cos_i` to the normal, with relative index of
+/// refraction `eta` (inside over outside). A negative
+/// cosine is light from the inside.
+pub fn fresnel_dielectric(cos_i: f64, eta: f64) -> f64 {
+    let (mut cos_i, mut eta) = (cos_i.clamp(-1.0, 1.0), eta);
+    if cos_i < 0.0 {
+        eta = 1.0 / eta;
+        cos_i = -cos_i;
+    }
+    let sin2_t = (1.0 - cos_i * cos_i) / (eta * eta);
+    if sin2_t >= 1.0 {
+        return 1.0;
+    }
+    let cos_t = (1.0 - sin2_t).sqrt();
+    let r_parl = (eta * cos_i - cos_t) / (eta * cos_i + cos_t);
+    let r_perp = (cos_i - eta * cos_t) / (cos_i + eta * cos_t);
+    0.5 * (r_parl * r_parl + r_perp * r_perp)
+}
+
+/// Fresnel reflectance of a conductor with complex index of
+/// refraction `eta + ik` per channel, for light at cosine
+/// `cos_i` to the normal.
+pub fn fresnel_conductor(cos_i: f64, eta: &Color, k: &Color) -> Color {
+    let cos_i = cos_i.abs().min(1.0);
+    let cos2 = cos_i * cos_i;
+    let sin2 = 1.0 - cos2;
+    let channel = |eta: f64, k: f64| {
+        let (eta2, k2) = (eta * eta, k * k);
+        let t0 = eta2 - k2 - sin2;
+        let a2b2 = (t0 * t0 + 4.0 * eta2 * k2).sqrt();
+        let t1 = a2b2 + cos2;
+        let a = (0.5 * (a2b2 + t0)).max(0.0).sqrt();
+        let t2 = 2.0 * cos_i * a;
+        let rs = (t1 - t2) / (t1 + t2);
+        let t3 = cos2 * a2b2 + sin2 * sin2;
+        let t4 = t2 * sin2;
+        let rp = rs * (t3 - t4) / (t3 + t4);
+        0.5 * (rp + rs)
+    };
+    Color::new(
+        channel(eta[R], k[R]),
+        channel(eta[G], k[G]),
+        channel(eta[B], k[B]),
+    )
+}
+
+/// Schlick's approximation to Fresnel reflectance, rising
+/// from `f0` at normal incidence to 1 at grazing.
+pub fn fresnel_schlick(cos_i: f64, f0: &Color) -> Color {
+    let m = (1.0 - cos_i.abs()).clamp(0.0, 1.0);
+    let m5 = m * m * m * m * m;
+    *f0 * (1.0 - m5) + Color::new(m5, m5, m5)
+}
+
+/// Direction on the +z hemisphere with density in
+/// proportion to its cosine, from two uniform numbers.
+pub fn cosine_hemisphere(u0: f64, u1: f64) -> Vec3 {
+    let r = u0.sqrt();
+    let phi = 2.0 * PI * u1;
+    Vec3::new(r * phi.cos(), r * phi.sin(), (1.0 - u0).max(0.0).sqrt())
+}
+
+#[test]
+fn test_bsdf_energy() {
+    // Monte Carlo estimates of the fraction of light each
+    // BSDF scatters from a direction, which should be at
+    // most what an ideal surface of its kind scatters.
+    let wo = Vec3::new(0.3, 0.1, 0.8).unit();
+    let white = Color::new(1.0, 1.0, 1.0);
+    let ggx = |r: f64| Microfacets::new(Distribution::Ggx, r);
+    let beckmann = |r: f64| Microfacets::new(Distribution::Beckmann, r);
+    // Transmitted radiance is scaled down by the square of
+    // the index of refraction.
+    let r = fresnel_dielectric(wo[Z], 1.5);
+    let glass = r + (1.0 - r) / (1.5 * 1.5);
+    let bsdfs: Vec<(Box<dyn Bsdf>, f64, f64)> = vec![
+        (Box::new(Lambert { albedo: white }), 0.97, 1.03),
+        (Box::new(OrenNayar::new(white, 0.5)), 0.8, 1.03),
+        (
+            Box::new(Conductor::new(
+                Color::new(0.2, 0.2, 0.2),
+                Color::new(3.9, 3.9, 3.9),
+                ggx(0.3),
+            )),
+            0.8,
+            1.03,
+        ),
+        (
+            Box::new(Dielectric::new(1.5, beckmann(0.3))),
+            glass - 0.03,
+            glass + 0.01,
+        ),
+        (
+            Box::new(Principled::new(Color::new(0.5, 0.5, 0.5), 0.0, 0.5, 0.5)),
+            0.3,
+            1.03,
+        ),
+    ];
+    let n = 64;
+    for (bsdf, lo, hi) in bsdfs {
+        let mut total = 0.0;
+        for i in 0..n {
+            for j in 0..n {
+                let pick = ((i * n + j) as f64 * 0.618034).fract();
+                let u = Vec3::new(
+                    pick,
+                    (i as f64 + 0.5) / n as f64,
+                    (j as f64 + 0.5) / n as f64,
+                );
+                if let Some(s) = bsdf.sample(&wo, &u) {
+                    if s.pdf > 0.0 {
+                        total += s.weight()[G];
+                    }
+                    // Sampled densities agree with pdf().
+                    if !s.specular && s.pdf > 1.0e-3 {
+                        let pdf = bsdf.pdf(&wo, &s.wi);
+                        assert!(
+                            (pdf - s.pdf).abs() < 1.0e-6 * s.pdf.max(1.0),
+                            "{} vs {}",
+                            pdf,
+                            s.pdf
+                        );
+                        let f = bsdf.eval(&wo, &s.wi);
+                        assert!((f[G] - s.f[G]).abs() < 1.0e-6 * s.f[G].max(1.0));
+                    }
+                }
+            }
+        }
+        let albedo = total / (n * n) as f64;
+        assert!(lo <= albedo && albedo <= hi, "albedo {}", albedo);
+    }
+
+    // A smooth dielectric reflects and transmits, and the
+    // two add to one.
+    let glass = Dielectric::new(1.5, ggx(0.0));
+    let paths = glass.specular(&wo);
+    assert_eq!(2, paths.len());
+    let (wr, r) = paths[0];
+    let (wt, t) = paths[1];
+    assert!((wr - Vec3::new(-wo[X], -wo[Y], wo[Z])).mag() < TINY);
+    assert!(wt[Z] < 0.0);
+    assert!((r[G] + t[G] * 1.5 * 1.5 - 1.0).abs() < TINY);
+}
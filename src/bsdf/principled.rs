@@ -0,0 +1,127 @@
+use crate::*;
+
+/// A metallic/roughness material in the manner of Disney's
+/// principled BSDF: a Lambertian base under a GGX specular
+/// layer with Schlick's Fresnel term. Metals have no
+/// diffuse base, and reflect in the base color.
+#[derive(Clone, Copy)]
+pub struct Principled {
+    pub base_color: Color,
+    /// From 0, a dielectric, to 1, a metal.
+    pub metallic: f64,
+    /// Specular reflectance of a dielectric at normal
+    /// incidence, where 0.5 is 4%, about that of most
+    /// plastics and glass.
+    pub specular: f64,
+    pub facets: Microfacets,
+}
+
+impl Principled {
+    pub fn new(base_color: Color, metallic: f64, roughness: f64, specular: f64) -> Self {
+        Self {
+            base_color,
+            metallic: metallic.clamp(0.0, 1.0),
+            specular,
+            facets: Microfacets::new(Distribution::Ggx, roughness),
+        }
+    }
+
+    // Specular reflectance at normal incidence.
+    fn f0(&self) -> Color {
+        let s = 0.08 * self.specular;
+        Color::new(s, s, s) * (1.0 - self.metallic) + self.base_color * self.metallic
+    }
+
+    // Probability of sampling the specular layer rather than
+    // the base.
+    fn specular_weight(&self) -> f64 {
+        0.5 + 0.5 * self.metallic
+    }
+
+    // The diffuse base.
+    fn base(&self) -> Lambert {
+        Lambert {
+            albedo: self.base_color * (1.0 - self.metallic),
+        }
+    }
+
+    // Specular layer value and density for a pair of
+    // directions on the same side.
+    fn layer(&self, wo: &Vec3, wi: &Vec3) -> (Color, f64) {
+        if self.facets.is_smooth() || !same_hemisphere(wo, wi) {
+            return (Color::new(0.0, 0.0, 0.0), 0.0);
+        }
+        let wh = (*wo + *wi).unit();
+        let d = self.facets.d(&wh);
+        let f = fresnel_schlick(*wi * wh, &self.f0());
+        let v = d * self.facets.g(wo, wi) / (4.0 * (wo[Z] * wi[Z]).abs());
+        (f * v, self.facets.pdf_wh(&wh) / (4.0 * (*wo * wh).abs()))
+    }
+}
+
+impl Bsdf for Principled {
+    fn eval(&self, wo: &Vec3, wi: &Vec3) -> Color {
+        self.base().eval(wo, wi) + self.layer(wo, wi).0
+    }
+
+    fn pdf(&self, wo: &Vec3, wi: &Vec3) -> f64 {
+        let ps = self.specular_weight();
+        (1.0 - ps) * self.base().pdf(wo, wi) + ps * self.layer(wo, wi).1
+    }
+
+    fn sample(&self, wo: &Vec3, u: &Vec3) -> Option<BsdfSample> {
+        if wo[Z] == 0.0 {
+            return None;
+        }
+        let ps = self.specular_weight();
+        if u[X] < ps {
+            if self.facets.is_smooth() {
+                let wi = Vec3::new(-wo[X], -wo[Y], wo[Z]);
+                let f = fresnel_schlick(wo[Z], &self.f0());
+                return Some(BsdfSample {
+                    wi,
+                    f: f * (1.0 / wi[Z].abs()),
+                    pdf: ps,
+                    specular: true,
+                });
+            }
+            let mut wh = self.facets.sample_wh(u[Y], u[Z]);
+            if wo[Z] < 0.0 {
+                wh = -wh;
+            }
+            let wi = reflect(wo, &wh);
+            if !same_hemisphere(wo, &wi) {
+                return None;
+            }
+            return Some(BsdfSample {
+                wi,
+                f: self.eval(wo, &wi),
+                pdf: self.pdf(wo, &wi),
+                specular: false,
+            });
+        }
+        if self.metallic >= 1.0 {
+            return None;
+        }
+        let mut wi = cosine_hemisphere(u[Y], u[Z]);
+        if wo[Z] < 0.0 {
+            wi[Z] = -wi[Z];
+        }
+        Some(BsdfSample {
+            wi,
+            f: self.eval(wo, &wi),
+            pdf: self.pdf(wo, &wi),
+            specular: false,
+        })
+    }
+
+    fn specular(&self, wo: &Vec3) -> Vec<(Vec3, Color)> {
+        if !self.facets.is_smooth() {
+            return Vec::new();
+        }
+        vec![(
+            Vec3::new(-wo[X], -wo[Y], wo[Z]),
+            fresnel_schlick(wo[Z], &self.f0()),
+        )]
+    }
+}
@@ -8,10 +8,22 @@
 
 use std::sync::atomic::{AtomicU64, Ordering::SeqCst};
 
+/// Initial PRNG state.
+const SEED: u64 = 0x123456789abcdef0u64;
+
+/// PRNG state.
+static STATE: AtomicU64 = AtomicU64::new(SEED);
+
+/// Reset the PRNG to a known state, so that a sequence of
+/// operations can be repeated exactly. `None` restores the
+/// state the PRNG starts with.
+pub fn seed(s: Option<u64>) {
+    STATE.store(s.unwrap_or(SEED), SeqCst);
+}
+
 /// Produce a pseudo-random integer. Will likely be slow in
 /// the presence of contention.
 pub fn random() -> u64 {
-    static STATE: AtomicU64 = AtomicU64::new(0x123456789abcdef0u64);
     let mut current = STATE.load(SeqCst);
     loop {
         let new = current
@@ -1,30 +1,42 @@
 //! Library for raytracing. This contains most of the
 //! functionality of a raytracer.
 
+pub mod bsdf;
 pub mod color;
 pub mod frandom;
 pub mod infra;
 pub mod lights;
 pub mod model;
+pub mod noise;
 pub mod outputs;
 pub mod point;
+pub mod quat;
 pub mod ray;
 pub mod render;
+pub mod scene;
+pub mod sdf;
 pub mod shapes;
+pub mod solve;
 pub mod textures;
 pub mod thing;
 pub mod xform;
 
+pub use bsdf::*;
 pub use color::*;
 pub use frandom::*;
 pub use infra::*;
 pub use lights::*;
 pub use model::*;
+pub use noise::*;
 pub use outputs::*;
 pub use point::*;
+pub use quat::*;
 pub use ray::*;
 pub use render::*;
+pub use scene::*;
+pub use sdf::*;
 pub use shapes::*;
+pub use solve::*;
 pub use textures::*;
 pub use thing::*;
 pub use xform::*;
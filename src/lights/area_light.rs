@@ -0,0 +1,269 @@
+use super::{relative, samples_at};
+use crate::*;
+
+/// A parallelogram of light with one corner at `corner` and
+/// edges `u` and `v`, usually at right angles for a
+/// rectangle. It gives off light of the same radiance in
+/// every direction on the side that `u × v` points to.
+pub struct RectLight {
+    pub corner: Point3,
+    pub u: Vec3,
+    pub v: Vec3,
+    pub radiance: Color,
+    /// Number of points sampled on the light for each
+    /// surface it lights.
+    pub samples: usize,
+    pub falloff: Falloff,
+}
+
+impl RectLight {
+    pub fn new(corner: Point3, u: Vec3, v: Vec3, radiance: Color) -> Self {
+        Self {
+            corner,
+            u,
+            v,
+            radiance,
+            samples: 16,
+            falloff: Falloff::InverseSquare,
+        }
+    }
+
+    pub fn with_samples(mut self, samples: usize) -> Self {
+        self.samples = samples;
+        self
+    }
+
+    pub fn with_falloff(mut self, falloff: Falloff) -> Self {
+        self.falloff = falloff;
+        self
+    }
+}
+
+impl Light for RectLight {
+    fn sample(&self, m: &Model, p: &Point3, depth: usize) -> Vec<LightSample> {
+        let n = samples_at(self.samples, depth);
+        let normal = self.u.cross_product(&self.v);
+        let area = normal.mag();
+        let normal = normal.unit();
+        let points = stratified(n)
+            .into_iter()
+            .map(|(a, b)| self.corner + self.u * a + self.v * b);
+        surface_samples(m, p, points, &normal, area, &self.radiance, self.falloff)
+    }
+}
+
+/// A disk of light centered at `center`, facing `normal`.
+/// It gives off light of the same radiance in every
+/// direction on that side.
+pub struct DiskLight {
+    pub center: Point3,
+    pub normal: Normal3,
+    pub radius: f64,
+    pub radiance: Color,
+    /// Number of points sampled on the light for each
+    /// surface it lights.
+    pub samples: usize,
+    pub falloff: Falloff,
+}
+
+impl DiskLight {
+    pub fn new(center: Point3, normal: Normal3, radius: f64, radiance: Color) -> Self {
+        Self {
+            center,
+            normal: normal.unit(),
+            radius,
+            radiance,
+            samples: 16,
+            falloff: Falloff::InverseSquare,
+        }
+    }
+
+    pub fn with_samples(mut self, samples: usize) -> Self {
+        self.samples = samples;
+        self
+    }
+
+    pub fn with_falloff(mut self, falloff: Falloff) -> Self {
+        self.falloff = falloff;
+        self
+    }
+}
+
+impl Light for DiskLight {
+    /// Points are stratified in the squared radius and the
+    /// angle, which keeps them stratified by area.
+    fn sample(&self, m: &Model, p: &Point3, depth: usize) -> Vec<LightSample> {
+        let n = samples_at(self.samples, depth);
+        let frame = Frame::new(&self.normal, &Vec3::default());
+        let points = stratified(n).into_iter().map(|(a, b)| {
+            let r = self.radius * a.sqrt();
+            let phi = 2.0 * PI * b;
+            self.center + frame.to_world(&Vec3::new(r * phi.cos(), r * phi.sin(), 0.0))
+        });
+        let area = PI * self.radius * self.radius;
+        surface_samples(
+            m,
+            p,
+            points,
+            &self.normal,
+            area,
+            &self.radiance,
+            self.falloff,
+        )
+    }
+}
+
+/// A ball of light, giving off light of the same radiance
+/// in every direction from every point of its surface.
+pub struct SphereLight {
+    pub center: Point3,
+    pub radius: f64,
+    pub radiance: Color,
+    /// Number of points sampled on the light for each
+    /// surface it lights.
+    pub samples: usize,
+    pub falloff: Falloff,
+}
+
+impl SphereLight {
+    pub fn new(center: Point3, radius: f64, radiance: Color) -> Self {
+        Self {
+            center,
+            radius,
+            radiance,
+            samples: 16,
+            falloff: Falloff::InverseSquare,
+        }
+    }
+
+    pub fn with_samples(mut self, samples: usize) -> Self {
+        self.samples = samples;
+        self
+    }
+
+    pub fn with_falloff(mut self, falloff: Falloff) -> Self {
+        self.falloff = falloff;
+        self
+    }
+}
+
+impl Light for SphereLight {
+    /// Only the part of the sphere facing `p` can light it,
+    /// so directions are drawn uniformly from the cone of
+    /// directions toward the sphere, stratified in the
+    /// angle from its axis and around it. A point inside
+    /// the sphere gets no light.
+    fn sample(&self, m: &Model, p: &Point3, depth: usize) -> Vec<LightSample> {
+        let v = self.center - *p;
+        let d2 = v.mag2();
+        let r2 = self.radius * self.radius;
+        if d2 <= r2 {
+            return Vec::new();
+        }
+        // Cosine of the half-angle of the cone and its
+        // solid angle.
+        let cos_max = (1.0 - r2 / d2).sqrt();
+        let solid = 2.0 * PI * (r2 / d2) / (1.0 + cos_max);
+        let frame = Frame::new(&v.unit(), &Vec3::default());
+
+        let n = samples_at(self.samples, depth);
+        let mut samples = Vec::new();
+        for (a, b) in stratified(n) {
+            let cos = 1.0 - a * (1.0 - cos_max);
+            let sin = (1.0 - cos * cos).max(0.0).sqrt();
+            let phi = 2.0 * PI * b;
+            let wi = frame.to_world(&Vec3::new(sin * phi.cos(), sin * phi.sin(), cos));
+            // Nearer crossing of the ray with the sphere,
+            // taken to the edge if it just misses.
+            let b = wi * v;
+            let t = b - (b * b - d2 + r2).max(0.0).sqrt();
+            if !visible(m, p, &(*p + wi * t)) {
+                continue;
+            }
+            samples.push(LightSample {
+                wi,
+                li: self.radiance * (solid / n as f64 * relative(self.falloff, t)),
+            });
+        }
+        samples
+    }
+}
+
+// Samples of the light reaching `p` from the given points,
+// spread uniformly over a one-sided surface of the given
+// normal, area and radiance.
+fn surface_samples(
+    m: &Model,
+    p: &Point3,
+    points: impl Iterator<Item = Point3>,
+    normal: &Normal3,
+    area: f64,
+    radiance: &Color,
+    falloff: Falloff,
+) -> Vec<LightSample> {
+    let points: Vec<Point3> = points.collect();
+    let n = points.len() as f64;
+    let mut samples = Vec::new();
+    for q in points {
+        let v = q - *p;
+        let d2 = v.mag2();
+        let wi = v.unit();
+        let cos = -(wi * *normal);
+        if cos <= 0.0 || d2 < TINY || !visible(m, p, &q) {
+            continue;
+        }
+        samples.push(LightSample {
+            wi,
+            li: *radiance * (cos * area / (d2 * n) * relative(falloff, d2.sqrt())),
+        });
+    }
+    samples
+}
+
+#[test]
+fn test_area_light_irradiance() {
+    // A unit square, a disk of unit area and a unit ball of
+    // light, each centered 4 units above the origin, with
+    // their exact irradiance at the origin.
+    let kblack = Color::new(0.0, 0.0, 0.0);
+    let glow = Color::new(1.0, 1.0, 1.0);
+    let dark = PointLight::new(Vec3::default(), kblack);
+    let m = Model::new(Vec3::default(), Box::new(dark), Vec::new(), kblack);
+    let h = Vec3::new(0.0, 0.0, 4.0);
+    let down = Vec3::new(0.0, 0.0, -1.0);
+    let x: f64 = 0.5 / 4.0;
+    let xs = x / (1.0 + x * x).sqrt();
+    let r = (1.0 / PI).sqrt();
+    let lights: Vec<(Box<dyn Light>, f64)> = vec![
+        (
+            Box::new(RectLight::new(
+                Vec3::new(-0.5, 0.5, 4.0),
+                Vec3::new(1.0, 0.0, 0.0),
+                Vec3::new(0.0, -1.0, 0.0),
+                glow,
+            )),
+            4.0 * xs * xs.atan(),
+        ),
+        (
+            Box::new(DiskLight::new(h, down, r, glow)),
+            PI * r * r / (r * r + 16.0),
+        ),
+        (Box::new(SphereLight::new(h, 1.0, glow)), PI / 16.0),
+    ];
+    for (light, e) in lights {
+        let irradiance: f64 = light
+            .sample(&m, &Vec3::default(), 1)
+            .iter()
+            .map(|s| s.li[R] * s.wi[Z])
+            .sum();
+        assert!((irradiance - e).abs() < 0.01 * e);
+    }
+
+    // Nothing shines on the back of the square or disk.
+    let above = Vec3::new(0.0, 0.0, 8.0);
+    let rect = RectLight::new(h, Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, -1.0, 0.0), glow);
+    assert!(rect.sample(&m, &above, 1).is_empty());
+    assert!(DiskLight::new(h, down, r, glow)
+        .sample(&m, &above, 1)
+        .is_empty());
+}
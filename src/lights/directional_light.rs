@@ -0,0 +1,77 @@
+use crate::*;
+
+/// A light so far away, like the sun, that its light
+/// arrives everywhere from the same direction with the same
+/// strength. It has no position, so it does not fall off.
+pub struct DirectionalLight {
+    /// Direction in which the light travels.
+    pub dir: Vec3,
+    /// Luminance of light source.
+    pub intensity: Color,
+}
+
+impl DirectionalLight {
+    pub fn new(dir: Vec3, intensity: Color) -> Self {
+        Self {
+            dir: dir.unit(),
+            intensity,
+        }
+    }
+}
+
+impl Light for DirectionalLight {
+    /// The one direction toward the light, unless something
+    /// in the way puts `p` in shadow. As with `PointLight`,
+    /// a surface facing the light gets π times its color as
+    /// irradiance.
+    fn sample(&self, m: &Model, p: &Point3, _depth: usize) -> Vec<LightSample> {
+        let wi = -self.dir;
+        if !visible_along(m, p, &wi) {
+            return Vec::new();
+        }
+        vec![LightSample {
+            wi,
+            li: self.intensity * PI,
+        }]
+    }
+}
+
+#[test]
+fn test_directional_light() {
+    // Sunlight slanting down onto the floor, the same
+    // everywhere: a surface facing it gets π times the
+    // light's color as irradiance.
+    let kwhite = Color::new(1.0, 1.0, 1.0);
+    let sun = DirectionalLight::new(Vec3::new(1.0, 0.0, -1.0), kwhite);
+    let m = Model::new(
+        Vec3::default(),
+        Box::new(PointLight::new(Vec3::default(), kwhite * 0.0)),
+        Vec::new(),
+        kwhite,
+    );
+    for p in [Vec3::default(), Vec3::new(100.0, -50.0, 3.0)] {
+        let s = sun.sample(&m, &p, 1);
+        assert_eq!(s.len(), 1);
+        assert!((s[0].wi - Vec3::new(-1.0, 0.0, 1.0).unit()).mag() < TINY);
+        assert!((s[0].li[R] / PI - 1.0).abs() < TINY);
+    }
+
+    // A slab up the light's way shadows the points below
+    // it, however far off, but not those beside it.
+    let mut s = Cuboid::new(Vec3::new(-3.0, -1.0, 1.9), Vec3::new(-1.0, 1.0, 2.1));
+    s.complete(&Xform::identity());
+    let kblack = Color::new(0.0, 0.0, 0.0);
+    let slab = Thing {
+        shape: Box::new(s),
+        texture: Box::new(SolidTexture::new(kblack, kblack, kblack, 1.0)),
+        xform: Xform::identity(),
+    };
+    let m = Model::new(
+        Vec3::default(),
+        Box::new(PointLight::new(Vec3::default(), kblack)),
+        vec![slab],
+        kwhite,
+    );
+    assert!(sun.sample(&m, &Vec3::default(), 1).is_empty());
+    assert_eq!(sun.sample(&m, &Vec3::new(0.0, 5.0, 0.0), 1).len(), 1);
+}
@@ -0,0 +1,175 @@
+use super::{relative, samples_at};
+use crate::*;
+
+/// A thing that gives off light, as a light source for
+/// other surfaces. Points on it are found by tracing rays
+/// toward its bounding sphere, so any bounded shape will
+/// do; a ray that hits something else first is in shadow.
+/// The thing may glow all over, or only in some parts of a
+/// compound shape such as a `Group` or `Csg`.
+pub struct Emitter {
+    /// Index of the thing in the model's scene.
+    pub thing: usize,
+    center: Point3,
+    radius: f64,
+    samples: usize,
+}
+
+impl Emitter {
+    /// The light sources among the given things: those
+    /// with some part that gives off light and whose shapes
+    /// are bounded.
+    pub fn find(scene: &[Thing]) -> Vec<Emitter> {
+        let mut emitters = Vec::new();
+        for (thing, p) in scene.iter().enumerate() {
+            let samples = p.light_samples();
+            if samples == 0 {
+                continue;
+            }
+            if let Some(b) = p.shape.bounds() {
+                let b = b.transform(&p.xform);
+                emitters.push(Emitter {
+                    thing,
+                    center: b.centroid(),
+                    radius: (b.max - b.min).mag() * 0.5,
+                    samples,
+                });
+            }
+        }
+        emitters
+    }
+}
+
+impl Light for Emitter {
+    /// Directions are drawn uniformly from the cone of
+    /// directions toward the bounding sphere, stratified in
+    /// the angle from its axis and around it, or from all
+    /// directions if `p` is inside the sphere. Samples in
+    /// shadow or missing the thing are left out. Each sample
+    /// falls off with distance as the texture of the part
+    /// hit asks.
+    fn sample(&self, m: &Model, p: &Point3, depth: usize) -> Vec<LightSample> {
+        let v = self.center - *p;
+        let d2 = v.mag2();
+        let r2 = self.radius * self.radius;
+        // Cosine of the half-angle of the cone and its
+        // solid angle.
+        let (cos_max, solid) = if d2 > r2 {
+            let cos_max = (1.0 - r2 / d2).sqrt();
+            (cos_max, 2.0 * PI * (r2 / d2) / (1.0 + cos_max))
+        } else {
+            (-1.0, 4.0 * PI)
+        };
+        let frame = Frame::new(&v.unit(), &Vec3::default());
+        let thing = &m.scene[self.thing];
+
+        let n = samples_at(self.samples, depth);
+        let mut samples = Vec::new();
+        for (a, b) in stratified(n) {
+            let cos = 1.0 - a * (1.0 - cos_max);
+            let sin = (1.0 - cos * cos).max(0.0).sqrt();
+            let phi = 2.0 * PI * b;
+            let wi = frame.to_world(&Vec3::new(sin * phi.cos(), sin * phi.sin(), cos));
+            let ray = Ray::new(*p, wi);
+            let Some((i, q)) = first_hit(&ray, m) else {
+                continue;
+            };
+            if !std::ptr::eq(q, thing) {
+                continue;
+            }
+            let texture = i.texture.unwrap_or(q.texture.as_ref());
+            let footprint = surface_footprint(&ray, &i);
+            let li = texture.emitted(&i.at, &footprint, &i.normal);
+            samples.push(LightSample {
+                wi,
+                li: li * (solid / n as f64 * relative(texture.light_falloff(), i.t)),
+            });
+        }
+        samples
+    }
+}
+
+#[test]
+fn test_emitter_irradiance() {
+    // A glowing unit sphere 4 units above the origin. Its
+    // irradiance at the origin is π L sin² of the angle it
+    // subtends.
+    let lamp = || {
+        let mut s = Sphere::default();
+        let xform = Xform::translation(&Vec3::new(0.0, 0.0, 4.0));
+        s.complete(&xform);
+        Thing {
+            shape: Box::new(s),
+            texture: Box::new(Emissive::new(Color::new(2.0, 2.0, 2.0)).with_samples(1024)),
+            xform,
+        }
+    };
+    let model = |scene| {
+        let light = PointLight::new(Vec3::default(), Color::new(0.0, 0.0, 0.0));
+        let bg = Color::new(0.0, 0.0, 0.0);
+        Model::new(Vec3::new(0.0, 0.0, -1.0), Box::new(light), scene, bg)
+    };
+    let irradiance = |m: &Model| -> f64 {
+        m.emitted_light(&Vec3::default(), 1)
+            .iter()
+            .map(|s| s.li[R] * s.wi[Z])
+            .sum()
+    };
+    let e = PI * 2.0 / 16.0;
+    let m = model(vec![lamp()]);
+    assert_eq!(m.emitters().len(), 1);
+    assert!(m.is_emitter(&m.scene[0]));
+    assert!((irradiance(&m) - e).abs() < 0.05 * e);
+
+    // The lamp lights the same when added to the scene
+    // later, or as part of a group.
+    let mut m = model(Vec::new());
+    m.scene.push(lamp());
+    assert!((irradiance(&m) - e).abs() < 0.05 * e);
+    let kblack = Color::new(0.0, 0.0, 0.0);
+    let group = Thing {
+        shape: Box::new(Group::new(vec![lamp()])),
+        texture: Box::new(SolidTexture::new(kblack, kblack, kblack, 1.0)),
+        xform: Xform::identity(),
+    };
+    let m = model(vec![group]);
+    assert!(m.is_emitter(&m.scene[0]));
+    assert!((irradiance(&m) - e).abs() < 0.05 * e);
+
+    // A slab between them puts the origin in shadow.
+    let mut s = Cuboid::new(Vec3::new(-2.0, -2.0, 1.9), Vec3::new(2.0, 2.0, 2.1));
+    s.complete(&Xform::identity());
+    let slab = Thing {
+        shape: Box::new(s),
+        texture: Box::new(SolidTexture::new(kblack, kblack, kblack, 1.0)),
+        xform: Xform::identity(),
+    };
+    let m = model(vec![lamp(), slab]);
+    assert!(!m.is_emitter(&m.scene[1]));
+    assert!(m.emitted_light(&Vec3::default(), 1).is_empty());
+
+    // A glowing unit disk 2 units above the origin gives
+    // irradiance π L / 5 there, or π L h² ln(1 + r²/h²) with
+    // no falloff.
+    let disk = |falloff| {
+        let mut s = Disk::default();
+        let xform = Xform::translation(&Vec3::new(0.0, 0.0, 2.0));
+        s.complete(&xform);
+        let texture = Emissive::new(Color::new(1.0, 1.0, 1.0))
+            .with_two_sided(true)
+            .with_samples(1024)
+            .with_falloff(falloff);
+        Thing {
+            shape: Box::new(s),
+            texture: Box::new(texture),
+            xform,
+        }
+    };
+    for (falloff, e) in [
+        (Falloff::InverseSquare, PI / 5.0),
+        (Falloff::None, PI * 4.0 * 1.25f64.ln()),
+    ] {
+        let m = model(vec![disk(falloff)]);
+        assert!((irradiance(&m) - e).abs() < 0.05 * e);
+    }
+}
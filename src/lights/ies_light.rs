@@ -0,0 +1,310 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+use crate::*;
+
+/// How brightly a light fixture shines in each direction,
+/// as measured by its maker: candela at each of a grid of
+/// vertical and horizontal angles, in degrees. This is type
+/// C photometry, the kind used for nearly all fixtures:
+/// vertical angles run from 0 straight down to 180 straight
+/// up, and horizontal angles turn around the vertical.
+#[derive(Clone, Debug, PartialEq)]
+pub struct IesProfile {
+    pub vertical: Vec<f64>,
+    pub horizontal: Vec<f64>,
+    /// Candela for each horizontal angle, at each vertical
+    /// angle.
+    pub candela: Vec<Vec<f64>>,
+}
+
+impl IesProfile {
+    /// Read a profile in the IESNA LM-63 format: header and
+    /// keyword lines up to a `TILT=` line, then numbers. Tilt
+    /// data is skipped, and the candela are scaled by the
+    /// multiplier and ballast factor.
+    pub fn read<R: BufRead>(r: R) -> io::Result<Self> {
+        let bad = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+
+        let mut lines = r.lines();
+        let tilt = loop {
+            let line = lines
+                .next()
+                .ok_or_else(|| bad("no TILT line in IES file"))??;
+            if let Some(tilt) = line.trim().strip_prefix("TILT=") {
+                break tilt.trim().to_string();
+            }
+        };
+
+        let mut words = Vec::new();
+        for line in lines {
+            let line = line?;
+            words.extend(
+                line.split(|c: char| c == ',' || c.is_whitespace())
+                    .filter(|w| !w.is_empty())
+                    .map(str::to_string),
+            );
+        }
+        let mut words = words.into_iter();
+        let mut next = || -> io::Result<f64> {
+            let w = words.next().ok_or_else(|| bad("truncated IES file"))?;
+            w.parse().map_err(|_| bad("bad number in IES file"))
+        };
+
+        // Lamp-to-luminaire geometry, then the angles and
+        // multiplying factors.
+        if tilt == "INCLUDE" {
+            next()?;
+            let n = next()? as usize;
+            for _ in 0..n.saturating_mul(2) {
+                next()?;
+            }
+        }
+
+        // Number of lamps and lumens per lamp, then the
+        // multiplier and the sizes of the grid.
+        next()?;
+        next()?;
+        let multiplier = next()?;
+        let nv = next()? as usize;
+        let nh = next()? as usize;
+        let photometric_type = next()?;
+        // Units and the width, length and height of the
+        // fixture.
+        for _ in 0..4 {
+            next()?;
+        }
+        let ballast = next()?;
+        // Ballast-lamp factor and input watts.
+        next()?;
+        next()?;
+        if photometric_type != 1.0 {
+            return Err(bad("only type C photometry is supported"));
+        }
+        if nv == 0 || nh == 0 {
+            return Err(bad("no angles in IES file"));
+        }
+
+        let mut angles = |n: usize| -> io::Result<Vec<f64>> {
+            let a = (0..n).map(|_| next()).collect::<io::Result<Vec<f64>>>()?;
+            if a.windows(2).any(|w| w[0] >= w[1]) {
+                return Err(bad("angles out of order in IES file"));
+            }
+            Ok(a)
+        };
+        let vertical = angles(nv)?;
+        let horizontal = angles(nh)?;
+        let mut candela = Vec::new();
+        for _ in 0..nh {
+            let c = (0..nv)
+                .map(|_| next().map(|c| c * multiplier * ballast))
+                .collect::<io::Result<Vec<f64>>>()?;
+            candela.push(c);
+        }
+        Ok(Self {
+            vertical,
+            horizontal,
+            candela,
+        })
+    }
+
+    /// Read a profile from an `.ies` file.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::read(BufReader::new(File::open(path)?))
+    }
+
+    /// Greatest candela in the profile.
+    pub fn max_candela(&self) -> f64 {
+        self.candela.iter().flatten().fold(0.0, |a, &c| a.max(c))
+    }
+
+    /// Candela at vertical angle `v` and horizontal angle `h`
+    /// in degrees, interpolated bilinearly. Directions beyond
+    /// the vertical angles measured get none, and horizontal
+    /// angles are folded by the symmetry that the measured
+    /// angles imply.
+    pub fn candela(&self, v: f64, h: f64) -> f64 {
+        let (first, last) = (self.vertical[0], self.vertical[self.vertical.len() - 1]);
+        if v < first || v > last {
+            return 0.0;
+        }
+        let (j, s) = bracket(&self.horizontal, self.fold(h));
+        let (i, t) = bracket(&self.vertical, v);
+        let plane = |j: usize| lerp(&self.candela[j], i, t);
+        if s > 0.0 {
+            plane(j) * (1.0 - s) + plane(j + 1) * s
+        } else {
+            plane(j)
+        }
+    }
+
+    // Horizontal angle `h` in degrees, brought into the
+    // measured range: the measurements may cover a single
+    // plane for a fixture symmetric all around, one quadrant
+    // for symmetry about two planes, or half the circle for
+    // symmetry about one plane.
+    fn fold(&self, h: f64) -> f64 {
+        let (first, last) = (
+            self.horizontal[0],
+            self.horizontal[self.horizontal.len() - 1],
+        );
+        let h = h.rem_euclid(360.0);
+        let h = if self.horizontal.len() == 1 {
+            first
+        } else if first == 90.0 && last == 270.0 {
+            if h < 90.0 {
+                180.0 - h
+            } else if h > 270.0 {
+                540.0 - h
+            } else {
+                h
+            }
+        } else if last == 90.0 {
+            let h = if h > 180.0 { 360.0 - h } else { h };
+            if h > 90.0 {
+                180.0 - h
+            } else {
+                h
+            }
+        } else if last == 180.0 && h > 180.0 {
+            360.0 - h
+        } else {
+            h
+        };
+        h.clamp(first, last)
+    }
+}
+
+// Index of the interval of the increasing `xs` that holds
+// `x`, and how far along it `x` lies.
+fn bracket(xs: &[f64], x: f64) -> (usize, f64) {
+    if xs.len() == 1 {
+        return (0, 0.0);
+    }
+    let i = xs.partition_point(|&a| a <= x).clamp(1, xs.len() - 1) - 1;
+    let t = (x - xs[i]) / (xs[i + 1] - xs[i]);
+    (i, t.clamp(0.0, 1.0))
+}
+
+// Value a fraction `t` of the way from `values[i]` to the
+// next one.
+fn lerp(values: &[f64], i: usize, t: f64) -> f64 {
+    if t > 0.0 {
+        values[i] * (1.0 - t) + values[i + 1] * t
+    } else {
+        values[i]
+    }
+}
+
+/// A point light that shines according to a measured
+/// profile. Vertical angles are measured from `aim`, and
+/// horizontal angles turn around it from the direction of
+/// the x axis, or of the y axis if `aim` is along x.
+pub struct IesLight {
+    /// Position of light source.
+    pub loc: Point3,
+    /// Unit direction in which the fixture points, at
+    /// vertical angle 0.
+    pub aim: Vec3,
+    /// Luminance of light source where the profile is
+    /// brightest.
+    pub intensity: Color,
+    pub falloff: Falloff,
+    profile: IesProfile,
+    /// Greatest candela of the profile.
+    max_candela: f64,
+}
+
+impl IesLight {
+    pub fn new(loc: Point3, aim: Vec3, profile: IesProfile, intensity: Color) -> Self {
+        Self {
+            loc,
+            aim: aim.unit(),
+            intensity,
+            falloff: Falloff::None,
+            max_candela: profile.max_candela(),
+            profile,
+        }
+    }
+
+    pub fn with_falloff(mut self, falloff: Falloff) -> Self {
+        self.falloff = falloff;
+        self
+    }
+
+    /// The profile the light shines by.
+    pub fn profile(&self) -> &IesProfile {
+        &self.profile
+    }
+
+    /// Fraction of the brightest light of the profile that
+    /// shines in the unit direction `w` from the light.
+    pub fn spread(&self, w: &Vec3) -> f64 {
+        if self.max_candela <= 0.0 {
+            return 0.0;
+        }
+        let local = Frame::new(&self.aim, &Vec3::new(1.0, 0.0, 0.0)).to_local(w);
+        let v = local[Z].clamp(-1.0, 1.0).acos().to_degrees();
+        let h = local[Y].atan2(local[X]).to_degrees();
+        self.profile.candela(v, h) / self.max_candela
+    }
+}
+
+impl Light for IesLight {
+    /// As with `PointLight`, a surface facing the light gets
+    /// π times its color as irradiance.
+    fn sample(&self, m: &Model, p: &Point3, _depth: usize) -> Vec<LightSample> {
+        let v = self.loc - *p;
+        let wi = v.unit();
+        let spread = self.spread(&-wi);
+        if spread <= 0.0 || !visible(m, p, &self.loc) {
+            return Vec::new();
+        }
+        vec![LightSample {
+            wi,
+            li: self.intensity * (PI * spread * self.falloff.factor(v.mag())),
+        }]
+    }
+}
+
+#[test]
+fn test_ies_profile() {
+    // Quadrant symmetry, with candela doubled by the
+    // multiplier.
+    let ies = "IESNA:LM-63-2002
+[TEST] test fixture
+[MANUFAC] nobody
+TILT=NONE
+1 1000 2 2 2 1 2 0.5 0.5 0
+1.0 1.0 100
+0 90
+0 90
+50 25
+50 5
+";
+    let profile = IesProfile::read(ies.as_bytes()).unwrap();
+    assert_eq!(profile.max_candela(), 100.0);
+    assert!((profile.candela(45.0, 0.0) - 75.0).abs() < TINY);
+    assert!((profile.candela(90.0, 90.0) - 10.0).abs() < TINY);
+    assert!((profile.candela(90.0, 45.0) - 30.0).abs() < TINY);
+    assert!((profile.candela(90.0, 180.0) - 50.0).abs() < TINY);
+    assert!((profile.candela(90.0, 270.0) - 10.0).abs() < TINY);
+    assert_eq!(profile.candela(120.0, 0.0), 0.0);
+
+    // Tilt data is skipped.
+    let tilted = ies.replace("TILT=NONE", "TILT=INCLUDE\n1\n2\n0 90\n1 1");
+    assert_eq!(IesProfile::read(tilted.as_bytes()).unwrap(), profile);
+    assert!(IesProfile::read(&ies.as_bytes()[..ies.len() - 5]).is_err());
+
+    // Aimed down, the light is brightest straight below.
+    let light = IesLight::new(
+        Vec3::new(0.0, 0.0, 2.0),
+        Vec3::new(0.0, 0.0, -1.0),
+        profile,
+        Color::new(1.0, 1.0, 1.0),
+    );
+    assert!((light.spread(&Vec3::new(0.0, 0.0, -1.0)) - 1.0).abs() < TINY);
+    assert!((light.spread(&Vec3::new(1.0, 0.0, 0.0)) - 0.5).abs() < TINY);
+    assert!((light.spread(&Vec3::new(0.0, 1.0, 0.0)) - 0.1).abs() < TINY);
+}
@@ -1,9 +1,136 @@
 pub mod point_light;
 pub use point_light::*;
 
+pub mod directional_light;
+pub use directional_light::*;
+
+pub mod spot_light;
+pub use spot_light::*;
+
+pub mod ies_light;
+pub use ies_light::*;
+
+pub mod area_light;
+pub use area_light::*;
+
+pub mod emitter;
+pub use emitter::*;
+
 use crate::*;
 
+/// Light arriving at a point from one direction, as one
+/// sample of the light arriving from a light source. The
+/// radiance is divided by the density of the sample and
+/// by the number of samples, so that summing `f li cos θ`
+/// over the samples estimates the light reflected by a
+/// surface with BSDF `f`.
+#[derive(Clone, Copy)]
+pub struct LightSample {
+    /// Unit direction toward the light.
+    pub wi: Vec3,
+    pub li: Color,
+}
+
+/// A source of light for the direct lighting of surfaces.
 pub trait Light: Send + Sync {
-    fn at(&self) -> Point;
-    fn i(&self) -> Color;
+    /// Samples of the light reaching `p` directly from the
+    /// light, leaving out those in shadow. The number of
+    /// samples may be cut down past the first bounce, when
+    /// `depth` is more than 1.
+    fn sample(&self, m: &Model, p: &Point3, depth: usize) -> Vec<LightSample>;
+}
+
+/// How the light from a light source weakens with the
+/// distance from it. Point-like lights do not weaken by
+/// default, as in classic Phong lighting; area lights and
+/// glowing things weaken with the square of the distance,
+/// as physical lights do.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Falloff {
+    #[default]
+    None,
+    Linear,
+    InverseSquare,
+}
+
+impl Falloff {
+    /// Factor by which light is scaled at distance `d`.
+    pub fn factor(self, d: f64) -> f64 {
+        let d = d.max(TINY);
+        match self {
+            Falloff::None => 1.0,
+            Falloff::Linear => 1.0 / d,
+            Falloff::InverseSquare => 1.0 / (d * d),
+        }
+    }
+}
+
+/// Whether nothing in the model lies between `p` and `q`.
+pub fn visible(m: &Model, p: &Point3, q: &Point3) -> bool {
+    let v = *q - *p;
+    let d = v.mag();
+    let ray = Ray::new(*p, v);
+    match first_hit(&ray, m) {
+        Some((i, _)) => i.t >= d * (1.0 - TINY) - TINY,
+        None => true,
+    }
+}
+
+/// Whether nothing in the model lies beyond `p` in the
+/// direction `dir`.
+pub fn visible_along(m: &Model, p: &Point3, dir: &Vec3) -> bool {
+    first_hit(&Ray::new(*p, *dir), m).is_none()
+}
+
+/// `n` jittered points in the unit square, one in each cell
+/// of a grid as nearly square as `n` allows.
+pub fn stratified(n: usize) -> Vec<(f64, f64)> {
+    let mut nx = (n as f64).sqrt() as usize;
+    while nx > 1 && n % nx != 0 {
+        nx -= 1;
+    }
+    let nx = nx.max(1);
+    let ny = n / nx;
+    let mut points = Vec::with_capacity(n);
+    for i in 0..nx {
+        for j in 0..ny {
+            let u = (i as f64 + frandom()) / nx as f64;
+            let v = (j as f64 + frandom()) / ny as f64;
+            points.push((u, v));
+        }
+    }
+    points
+}
+
+// Number of samples to take of a light with the given
+// sample count at the given depth: only one past the first
+// bounce, to keep the number of rays in check.
+fn samples_at(samples: usize, depth: usize) -> usize {
+    if depth <= 1 {
+        samples
+    } else {
+        samples.min(1)
+    }
+}
+
+// Scale of light at distance `d` under the given falloff,
+// relative to the inverse-square falloff that comes of
+// sampling the surface of a light.
+fn relative(falloff: Falloff, d: f64) -> f64 {
+    falloff.factor(d) * d * d
+}
+
+#[test]
+fn test_stratified() {
+    for n in [1, 6, 7, 16] {
+        let points = stratified(n);
+        assert_eq!(points.len(), n);
+        assert!(points
+            .iter()
+            .all(|&(u, v)| (0.0..=1.0).contains(&u) && (0.0..=1.0).contains(&v)));
+    }
+    // Each quarter of the square has a quarter of the points.
+    let points = stratified(16);
+    let low = points.iter().filter(|&&(u, v)| u < 0.5 && v < 0.5).count();
+    assert_eq!(low, 4);
 }
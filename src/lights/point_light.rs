@@ -3,17 +3,78 @@ use crate::*;
 /// Information about a point light source.
 pub struct PointLight {
     /// Position of light source.
-    pub loc: Point,
+    pub loc: Point3,
     /// Luminance of light source.
     pub intensity: Color,
+    pub falloff: Falloff,
+    /// Whether things between the light and a surface put
+    /// it in shadow. Off by default, so that scenes lit in
+    /// the classic way look as they always have.
+    pub shadows: bool,
 }
 
-impl Light for PointLight {
-    fn at(&self) -> Point {
-        self.loc.clone()
+impl PointLight {
+    pub fn new(loc: Point3, intensity: Color) -> Self {
+        Self {
+            loc,
+            intensity,
+            falloff: Falloff::None,
+            shadows: false,
+        }
+    }
+
+    pub fn with_falloff(mut self, falloff: Falloff) -> Self {
+        self.falloff = falloff;
+        self
     }
 
-    fn i(&self) -> Color {
-        self.intensity
+    pub fn with_shadows(mut self, shadows: bool) -> Self {
+        self.shadows = shadows;
+        self
     }
 }
+
+impl Light for PointLight {
+    /// The one direction toward the light, unless it casts
+    /// shadows and `p` is in shadow. A surface facing the
+    /// light gets π times its color as irradiance, so that a
+    /// white Lambertian surface facing it shows the light's
+    /// color, as with Phong shading.
+    fn sample(&self, m: &Model, p: &Point3, _depth: usize) -> Vec<LightSample> {
+        if self.shadows && !visible(m, p, &self.loc) {
+            return Vec::new();
+        }
+        let v = self.loc - *p;
+        vec![LightSample {
+            wi: v.unit(),
+            li: self.intensity * (PI * self.falloff.factor(v.mag())),
+        }]
+    }
+}
+
+#[test]
+fn test_point_light_shadows() {
+    // A slab between the light and the origin shadows it
+    // only when asked.
+    let mut s = Cuboid::new(Vec3::new(-1.0, -1.0, 1.9), Vec3::new(1.0, 1.0, 2.1));
+    s.complete(&Xform::identity());
+    let kblack = Color::new(0.0, 0.0, 0.0);
+    let slab = Thing {
+        shape: Box::new(s),
+        texture: Box::new(SolidTexture::new(kblack, kblack, kblack, 1.0)),
+        xform: Xform::identity(),
+    };
+    let light = PointLight::new(Vec3::new(0.0, 0.0, 4.0), Color::new(1.0, 1.0, 1.0));
+    let m = Model::new(
+        Vec3::new(0.0, 0.0, -1.0),
+        Box::new(light),
+        vec![slab],
+        kblack,
+    );
+    let origin = Vec3::default();
+    assert_eq!(m.light_samples(&origin, 1).len(), 1);
+    let light =
+        PointLight::new(Vec3::new(0.0, 0.0, 4.0), Color::new(1.0, 1.0, 1.0)).with_shadows(true);
+    assert!(light.sample(&m, &origin, 1).is_empty());
+    assert_eq!(light.sample(&m, &Vec3::new(3.0, 0.0, 0.0), 1).len(), 1);
+}
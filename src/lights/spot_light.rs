@@ -0,0 +1,110 @@
+use crate::*;
+
+/// A point light that shines only within a cone. Its light
+/// fades smoothly to nothing over the `penumbra` at the
+/// edge of the cone.
+pub struct SpotLight {
+    /// Position of light source.
+    pub loc: Point3,
+    /// Unit direction of the axis of the cone.
+    pub dir: Vec3,
+    /// Luminance of light source on the axis.
+    pub intensity: Color,
+    /// Angle from the axis to the edge of the cone, in
+    /// radians.
+    pub cone: f64,
+    /// Angle inside the edge of the cone over which the
+    /// light fades, in radians.
+    pub penumbra: f64,
+    pub falloff: Falloff,
+}
+
+impl SpotLight {
+    /// A spot light at `loc` shining toward `dir` in a cone
+    /// of the given half-angle, with a hard edge.
+    pub fn new(loc: Point3, dir: Vec3, intensity: Color, cone: f64) -> Self {
+        Self {
+            loc,
+            dir: dir.unit(),
+            intensity,
+            cone,
+            penumbra: 0.0,
+            falloff: Falloff::None,
+        }
+    }
+
+    pub fn with_penumbra(mut self, penumbra: f64) -> Self {
+        self.penumbra = penumbra;
+        self
+    }
+
+    pub fn with_falloff(mut self, falloff: Falloff) -> Self {
+        self.falloff = falloff;
+        self
+    }
+
+    /// Fraction of the light on the axis that shines in the
+    /// unit direction `w` from the light.
+    pub fn spread(&self, w: &Vec3) -> f64 {
+        let angle = (*w * self.dir).clamp(-1.0, 1.0).acos();
+        if angle >= self.cone {
+            return 0.0;
+        }
+        let inner = self.cone - self.penumbra.clamp(0.0, self.cone);
+        if angle <= inner {
+            return 1.0;
+        }
+        // Fade by smoothstep across the penumbra.
+        let t = (self.cone - angle) / (self.cone - inner);
+        t * t * (3.0 - 2.0 * t)
+    }
+}
+
+impl Light for SpotLight {
+    /// As with `PointLight`, a surface facing the light gets
+    /// π times its color as irradiance.
+    fn sample(&self, m: &Model, p: &Point3, _depth: usize) -> Vec<LightSample> {
+        let v = self.loc - *p;
+        let wi = v.unit();
+        let spread = self.spread(&-wi);
+        if spread <= 0.0 || !visible(m, p, &self.loc) {
+            return Vec::new();
+        }
+        vec![LightSample {
+            wi,
+            li: self.intensity * (PI * spread * self.falloff.factor(v.mag())),
+        }]
+    }
+}
+
+#[test]
+fn test_spot_light() {
+    let kwhite = Color::new(1.0, 1.0, 1.0);
+    let down = Vec3::new(0.0, 0.0, -1.0);
+    let spot =
+        SpotLight::new(Vec3::new(0.0, 0.0, 2.0), down, kwhite, PI / 4.0).with_penumbra(PI / 8.0);
+    let at = |deg: f64| {
+        let a = deg * PI / 180.0;
+        Vec3::new(a.sin(), 0.0, -a.cos())
+    };
+    assert_eq!(spot.spread(&down), 1.0);
+    assert_eq!(spot.spread(&at(20.0)), 1.0);
+    assert!((spot.spread(&at(33.75)) - 0.5).abs() < TINY);
+    assert_eq!(spot.spread(&at(50.0)), 0.0);
+    assert_eq!(spot.spread(&-down), 0.0);
+
+    // Light on the floor below, in the open, weakening with
+    // distance as asked.
+    let m = Model::new(
+        Vec3::default(),
+        Box::new(PointLight::new(Vec3::default(), kwhite * 0.0)),
+        Vec::new(),
+        kwhite,
+    );
+    let li = |spot: &SpotLight| spot.sample(&m, &Vec3::default(), 1)[0].li[R];
+    assert!((li(&spot) - PI).abs() < TINY);
+    let spot = spot.with_falloff(Falloff::Linear);
+    assert!((li(&spot) - PI / 2.0).abs() < TINY);
+    let spot = spot.with_falloff(Falloff::InverseSquare);
+    assert!((li(&spot) - PI / 4.0).abs() < TINY);
+}
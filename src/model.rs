@@ -1,36 +1,77 @@
+use std::sync::OnceLock;
+
 use crate::*;
 
 /// Scene model for rendering. So far stored as naïve arrays
 /// rather than octrees or something.
 pub struct Model {
-    pub eye: Point,
-    pub the_light: Box<dyn Light>,
+    pub eye: Point3,
+    /// Lights apart from the things of the scene.
+    pub lights: Vec<Box<dyn Light>>,
+    /// Things of the scene. Those that give off light are
+    /// light sources as well, wherever they are in the list,
+    /// as long as they are added before rendering.
     pub scene: Vec<Thing>,
     pub bg: Color,
     pub max_depth: usize,
+    // Light sources among the things of the scene, found
+    // the first time they are needed.
+    emitters: OnceLock<Vec<Emitter>>,
 }
 
 impl Model {
-    pub fn new(eye: Point, the_light: Box<dyn Light>, scene: Vec<Thing>, bg: Color) -> Self {
+    /// A model lit by the given light. More lights may be
+    /// pushed onto `lights`.
+    pub fn new(eye: Point3, the_light: Box<dyn Light>, scene: Vec<Thing>, bg: Color) -> Self {
         Self {
             eye,
-            the_light,
+            lights: vec![the_light],
             scene,
             bg,
             max_depth: 10,
+            emitters: OnceLock::new(),
         }
     }
 
+    /// The things of the scene that are light sources, as
+    /// by `Emitter::find()`. They are found once, the first
+    /// time this is called, so the scene should be complete
+    /// by then.
+    pub fn emitters(&self) -> &[Emitter] {
+        self.emitters.get_or_init(|| Emitter::find(&self.scene))
+    }
+
+    /// Whether the thing is one of the light sources.
+    pub fn is_emitter(&self, p: &Thing) -> bool {
+        p.light_samples() > 0 && p.shape.bounds().is_some()
+    }
+
+    /// Samples of the light reaching `p` directly from the
+    /// lights.
+    pub fn light_samples(&self, p: &Point3, depth: usize) -> Vec<LightSample> {
+        self.lights
+            .iter()
+            .flat_map(|l| l.sample(self, p, depth))
+            .collect()
+    }
+
+    /// Samples of the light reaching `p` directly from the
+    /// light sources among the things of the scene.
+    pub fn emitted_light(&self, p: &Point3, depth: usize) -> Vec<LightSample> {
+        self.emitters()
+            .iter()
+            .flat_map(|e| e.sample(self, p, depth))
+            .collect()
+    }
+
     /// XXX For now, hard-code the parameters in the
     /// model. We will eventually fix this.
     pub fn generate(scene: Vec<Thing>) -> Self {
         let he: f64 = D * A.sin();
 
-        let the_light = PointLight {
-            loc: Point::new([2.0 * he, -he, 0.0]),
-            intensity: Color::new(1.25, 1.25, 1.15),
-        };
-        let eye = Point::new([he, 0.0, 0.0]);
+        let the_light =
+            PointLight::new(Vec3::new(2.0 * he, -he, 0.0), Color::new(1.25, 1.25, 1.15));
+        let eye = Vec3::new(he, 0.0, 0.0);
         let dkgray = Color::new(0.2, 0.2, 0.2);
         Model::new(eye, Box::new(the_light), scene, dkgray)
     }
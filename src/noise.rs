@@ -0,0 +1,244 @@
+//! Solid noise functions: smooth pseudo-random scalar
+//! fields over 3-space, for procedural textures. All are
+//! deterministic, so that renders repeat exactly.
+
+use crate::*;
+
+// Permutation of 0..256 for hashing lattice points, made
+// at compile time by shuffling with an LCG.
+const PERM: [u8; 256] = {
+    let mut p = [0u8; 256];
+    let mut i = 0;
+    while i < 256 {
+        p[i] = i as u8;
+        i += 1;
+    }
+    let mut state = 0x2545f4914f6cdd1du64;
+    let mut i = 255;
+    while i > 0 {
+        state = state
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        let j = ((state >> 33) % (i as u64 + 1)) as usize;
+        let t = p[i];
+        p[i] = p[j];
+        p[j] = t;
+        i -= 1;
+    }
+    p
+};
+
+// Hash of an integer lattice point to 0..256.
+fn hash(i: i64, j: i64, k: i64) -> usize {
+    let p = |n: i64| PERM[(n & 255) as usize] as i64;
+    p(p(p(i) + j) + k) as usize
+}
+
+// Hash of an integer lattice point to 32 well-mixed bits.
+fn hash32(i: i64, j: i64, k: i64) -> u32 {
+    let mut h = (i as u32).wrapping_mul(0x8da6b343)
+        ^ (j as u32).wrapping_mul(0xd8163841)
+        ^ (k as u32).wrapping_mul(0xcb1ab31f);
+    h ^= h >> 16;
+    h = h.wrapping_mul(0x7feb352d);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x846ca68b);
+    h ^ (h >> 16)
+}
+
+// Integer lattice cell containing a point, and the offset
+// of the point within it.
+fn cell(p: &Point3) -> ([i64; 3], Vec3) {
+    let f = Vec3::new(p[X].floor(), p[Y].floor(), p[Z].floor());
+    ([f[X] as i64, f[Y] as i64, f[Z] as i64], *p - f)
+}
+
+/// Ken Perlin's improved gradient noise, in about -1 to 1.
+/// Zero at the integer lattice points.
+pub fn perlin(p: &Point3) -> f64 {
+    // Dot product of the offset with one of twelve
+    // gradient directions picked by the hash.
+    fn grad(h: usize, x: f64, y: f64, z: f64) -> f64 {
+        let h = h & 15;
+        let u = if h < 8 { x } else { y };
+        let v = match h {
+            0..=3 => y,
+            12 | 14 => x,
+            _ => z,
+        };
+        (if h & 1 == 0 { u } else { -u }) + (if h & 2 == 0 { v } else { -v })
+    }
+    fn fade(t: f64) -> f64 {
+        t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+    }
+    fn lerp(t: f64, a: f64, b: f64) -> f64 {
+        a + t * (b - a)
+    }
+
+    let ([i, j, k], f) = cell(p);
+    let (x, y, z) = (f[X], f[Y], f[Z]);
+    let (u, v, w) = (fade(x), fade(y), fade(z));
+    let g = |di: i64, dj: i64, dk: i64| {
+        let h = hash(i + di, j + dj, k + dk);
+        grad(h, x - di as f64, y - dj as f64, z - dk as f64)
+    };
+    lerp(
+        w,
+        lerp(
+            v,
+            lerp(u, g(0, 0, 0), g(1, 0, 0)),
+            lerp(u, g(0, 1, 0), g(1, 1, 0)),
+        ),
+        lerp(
+            v,
+            lerp(u, g(0, 0, 1), g(1, 0, 1)),
+            lerp(u, g(0, 1, 1), g(1, 1, 1)),
+        ),
+    )
+}
+
+/// Simplex noise, in about -1 to 1: like `perlin()` but
+/// summed over the corners of a tetrahedral lattice, which
+/// is cheaper and has fewer axis-aligned artifacts.
+pub fn simplex(p: &Point3) -> f64 {
+    const GRAD: [[f64; 3]; 12] = [
+        [1.0, 1.0, 0.0],
+        [-1.0, 1.0, 0.0],
+        [1.0, -1.0, 0.0],
+        [-1.0, -1.0, 0.0],
+        [1.0, 0.0, 1.0],
+        [-1.0, 0.0, 1.0],
+        [1.0, 0.0, -1.0],
+        [-1.0, 0.0, -1.0],
+        [0.0, 1.0, 1.0],
+        [0.0, -1.0, 1.0],
+        [0.0, 1.0, -1.0],
+        [0.0, -1.0, -1.0],
+    ];
+    const F3: f64 = 1.0 / 3.0;
+    const G3: f64 = 1.0 / 6.0;
+
+    // Skew to find the containing simplex cell, and unskew
+    // to find the offset from its first corner.
+    let s = (p[X] + p[Y] + p[Z]) * F3;
+    let ([i, j, k], _) = cell(&Vec3::new(p[X] + s, p[Y] + s, p[Z] + s));
+    let t = (i + j + k) as f64 * G3;
+    let d0 = *p - Vec3::new(i as f64 - t, j as f64 - t, k as f64 - t);
+
+    // The other corners are reached by unit steps along the
+    // axes in decreasing order of offset.
+    let (x, y, z) = (d0[X], d0[Y], d0[Z]);
+    let (c1, c2) = if x >= y {
+        if y >= z {
+            ([1, 0, 0], [1, 1, 0])
+        } else if x >= z {
+            ([1, 0, 0], [1, 0, 1])
+        } else {
+            ([0, 0, 1], [1, 0, 1])
+        }
+    } else if y < z {
+        ([0, 0, 1], [0, 1, 1])
+    } else if x < z {
+        ([0, 1, 0], [0, 1, 1])
+    } else {
+        ([0, 1, 0], [1, 1, 0])
+    };
+
+    let mut n = 0.0;
+    for (m, c) in [[0, 0, 0], c1, c2, [1, 1, 1]].into_iter().enumerate() {
+        let g = m as f64 * G3;
+        let d = d0 - Vec3::new(c[0] as f64 - g, c[1] as f64 - g, c[2] as f64 - g);
+        let a = 0.6 - d.mag2();
+        if a > 0.0 {
+            let gv = GRAD[hash(i + c[0], j + c[1], k + c[2]) % 12];
+            n += a * a * a * a * d.dot(&Vec3::from(gv));
+        }
+    }
+    32.0 * n
+}
+
+/// Worley's cellular noise: the distances from the point to
+/// the nearest and second-nearest of a scattering of
+/// feature points, one in each unit lattice cell.
+pub fn worley(p: &Point3) -> (f64, f64) {
+    let ([i, j, k], _) = cell(p);
+    let mut f = [f64::INFINITY; 2];
+    for di in -1..=1 {
+        for dj in -1..=1 {
+            for dk in -1..=1 {
+                let (ci, cj, ck) = (i + di, j + dj, k + dk);
+                let h = hash32(ci, cj, ck);
+                let frac = |shift: u32| ((h >> shift) & 0x3ff) as f64 / 1024.0;
+                let q = Vec3::new(
+                    ci as f64 + frac(0),
+                    cj as f64 + frac(10),
+                    ck as f64 + frac(20),
+                );
+                let d = (q - *p).mag();
+                if d < f[0] {
+                    f = [d, f[0]];
+                } else if d < f[1] {
+                    f[1] = d;
+                }
+            }
+        }
+    }
+    (f[0], f[1])
+}
+
+// Sum of octaves of `noise` at doubling frequencies and
+// halving amplitudes, normalized by the total amplitude.
+fn octaves<F: Fn(&Point3) -> f64>(p: &Point3, octaves: usize, noise: F) -> f64 {
+    let mut sum = 0.0;
+    let mut amplitude = 1.0;
+    let mut total = 0.0;
+    let mut q = *p;
+    for _ in 0..octaves {
+        sum += amplitude * noise(&q);
+        total += amplitude;
+        amplitude *= 0.5;
+        // Offset each octave a little so that their
+        // lattices do not line up.
+        q = q * 2.0 + Vec3::new(17.1, 31.7, 5.3);
+    }
+    if total > 0.0 {
+        sum / total
+    } else {
+        0.0
+    }
+}
+
+/// Fractional Brownian motion: octaves of `perlin()` noise,
+/// in about -1 to 1.
+pub fn fbm(p: &Point3, octaves: usize) -> f64 {
+    self::octaves(p, octaves, perlin)
+}
+
+/// Turbulence: octaves of the magnitude of `perlin()`
+/// noise, in about 0 to 1, with sharp creases where the
+/// noise crosses zero.
+pub fn turbulence(p: &Point3, octaves: usize) -> f64 {
+    self::octaves(p, octaves, |q| perlin(q).abs())
+}
+
+#[test]
+fn test_noise() {
+    let p = Vec3::new(1.3, -2.7, 0.4);
+    assert_eq!(0.0, perlin(&Vec3::new(3.0, -1.0, 7.0)));
+    assert_eq!(perlin(&p), perlin(&p));
+
+    // Sample ranges and continuity.
+    let mut prev = simplex(&p);
+    for n in 1..1000 {
+        let q = p + Vec3::new(0.001, 0.0013, 0.0007) * n as f64;
+        for v in [perlin(&q), simplex(&q), fbm(&q, 4)] {
+            assert!(v.abs() <= 1.1);
+        }
+        assert!((0.0..=1.0).contains(&turbulence(&q, 4)));
+        let s = simplex(&q);
+        assert!((s - prev).abs() < 0.05);
+        prev = s;
+        let (f1, f2) = worley(&q);
+        assert!(f1 <= f2 && f2 < 3.0f64.sqrt() * 2.0);
+    }
+}
@@ -0,0 +1,154 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::Path;
+
+use crate::*;
+
+/// In-memory 8-bit RGB image, filled in by rendering into
+/// it. Can be read and written as raw PPM.
+#[derive(Clone, PartialEq, Eq)]
+pub struct ImageBuffer {
+    pub xsize: usize,
+    pub ysize: usize,
+    /// Pixels in row-major order, top row first.
+    pub pixels: Vec<[u8; 3]>,
+}
+
+impl ImageBuffer {
+    /// Create a black image of the given size.
+    pub fn new(xsize: usize, ysize: usize) -> Self {
+        Self {
+            xsize,
+            ysize,
+            pixels: vec![[0; 3]; xsize * ysize],
+        }
+    }
+
+    /// Pixel at the given position.
+    pub fn get(&self, x: usize, y: usize) -> [u8; 3] {
+        self.pixels[y * self.xsize + x]
+    }
+
+    /// Set the pixel at the given position.
+    pub fn set(&mut self, x: usize, y: usize, rgb: [u8; 3]) {
+        self.pixels[y * self.xsize + x] = rgb;
+    }
+
+    /// Write the image as a raw (P6) PPM.
+    pub fn write_ppm<W: Write>(&self, mut w: W) -> io::Result<()> {
+        write!(w, "P6\n{}\n{}\n{}\n", self.xsize, self.ysize, 255)?;
+        for p in &self.pixels {
+            w.write_all(p)?;
+        }
+        w.flush()
+    }
+
+    /// Read a raw (P6) PPM with a maxval of at most 255.
+    pub fn read_ppm<R: BufRead>(mut r: R) -> io::Result<Self> {
+        let bad = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+
+        // Read the four whitespace-separated header fields,
+        // skipping comments.
+        let mut fields = Vec::new();
+        while fields.len() < 4 {
+            let mut word = Vec::new();
+            loop {
+                let mut b = [0u8];
+                r.read_exact(&mut b)?;
+                match b[0] {
+                    b'#' if word.is_empty() => {
+                        let mut comment = Vec::new();
+                        r.read_until(b'\n', &mut comment)?;
+                    }
+                    c if c.is_ascii_whitespace() => {
+                        if !word.is_empty() {
+                            break;
+                        }
+                    }
+                    c => word.push(c),
+                }
+            }
+            fields.push(String::from_utf8(word).map_err(|_| bad("bad PPM header"))?);
+        }
+        if fields[0] != "P6" {
+            return Err(bad("not a raw PPM"));
+        }
+        let num = |s: &str| s.parse::<usize>().map_err(|_| bad("bad PPM header"));
+        let xsize = num(&fields[1])?;
+        let ysize = num(&fields[2])?;
+        let maxval = num(&fields[3])?;
+        if maxval == 0 || maxval > 255 {
+            return Err(bad("unsupported PPM maxval"));
+        }
+
+        let mut data = vec![0u8; 3 * xsize * ysize];
+        r.read_exact(&mut data)?;
+        let scale = |v: u8| (v as usize * 255 / maxval) as u8;
+        let pixels = data
+            .chunks_exact(3)
+            .map(|p| [scale(p[0]), scale(p[1]), scale(p[2])])
+            .collect();
+        Ok(Self {
+            xsize,
+            ysize,
+            pixels,
+        })
+    }
+}
+
+impl ImageBuffer {
+    /// Read a PNG, dropping any alpha channel and reducing
+    /// 16-bit samples to 8 bits.
+    pub fn read_png<R: Read>(r: R) -> io::Result<Self> {
+        let bad = |e: png::DecodingError| io::Error::new(io::ErrorKind::InvalidData, e.to_string());
+
+        let mut decoder = png::Decoder::new(r);
+        decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+        let mut reader = decoder.read_info().map_err(bad)?;
+        let mut data = vec![0u8; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut data).map_err(bad)?;
+        let (xsize, ysize) = (info.width as usize, info.height as usize);
+        let channels = info.color_type.samples();
+        let pixels = data[..info.buffer_size()]
+            .chunks_exact(channels)
+            .map(|p| match channels {
+                1 | 2 => [p[0]; 3],
+                _ => [p[0], p[1], p[2]],
+            })
+            .collect();
+        Ok(Self {
+            xsize,
+            ysize,
+            pixels,
+        })
+    }
+
+    /// Read a PNG or raw PPM file, by its contents.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let mut r = BufReader::new(File::open(path)?);
+        if r.fill_buf()?.starts_with(b"\x89PNG") {
+            Self::read_png(r)
+        } else {
+            Self::read_ppm(r)
+        }
+    }
+}
+
+impl Output for ImageBuffer {
+    fn put_pixel(&mut self, x: usize, y: usize, c: Color) {
+        self.set(x, y, c.apply(gamma));
+    }
+
+    fn flush_row(&mut self) {}
+}
+
+#[test]
+fn test_ppm_roundtrip() {
+    let mut im = ImageBuffer::new(3, 2);
+    im.set(2, 1, [1, 2, 3]);
+    im.set(0, 0, [255, 0, 128]);
+    let mut ppm = Vec::new();
+    im.write_ppm(&mut ppm).unwrap();
+    let im2 = ImageBuffer::read_ppm(ppm.as_slice()).unwrap();
+    assert!(im == im2);
+}
@@ -1,6 +1,9 @@
 pub mod ppm_raw;
 pub use ppm_raw::*;
 
+pub mod image_buffer;
+pub use image_buffer::*;
+
 use crate::*;
 
 pub struct OutputInfo<T: Write> {
@@ -14,6 +17,18 @@ pub trait Output {
     fn flush_row(&mut self);
 }
 
+/// Render into a borrowed output, so that it can be
+/// inspected afterward.
+impl<T: Output + ?Sized> Output for &mut T {
+    fn put_pixel(&mut self, x: usize, y: usize, c: Color) {
+        (**self).put_pixel(x, y, c);
+    }
+
+    fn flush_row(&mut self) {
+        (**self).flush_row();
+    }
+}
+
 pub fn gamma(v: f64) -> u8 {
     (v.clamp(0.0, 1.0) * 255.0 + 0.5).floor() as u8
 }
@@ -3,9 +3,17 @@
 // Please see the file COPYING in the source
 // distribution of this software for license terms.
 
-//! Implementation of "points"; actually, short vectors of
-//! floating-point numbers. Based on the 1991
-//! implementation.
+//! Implementation of "points"; actually, short fixed-size
+//! vectors of floating-point numbers. Originally based on
+//! the 1991 implementation, which used dynamic-length
+//! vectors; these are `Copy` arrays, which the compiler is
+//! free to vectorize in the coordinate-wise loops.
+//!
+//! The types tell vectors apart by dimension only: mixing a
+//! `Vec2` with a `Vec3` is a compile-time error, but
+//! `Point3` and `Normal3` are the same type as `Vec3`, and
+//! nothing checks that a point or normal is transformed as
+//! one.
 
 use std::ops::{Add, AddAssign, Index, IndexMut, Mul, MulAssign, Neg, Sub, SubAssign};
 
@@ -21,216 +29,295 @@ pub const Z: usize = 2;
 /// 4-coordinate point.  (Homogeneous coordinate.)
 pub const W: usize = 3;
 
-/// A Point has dynamic length, which means care
-/// is required in using it.
-#[derive(Clone, Default)]
-pub struct Point {
-    /// Coordinates of the Point.
-    pub c: Vec<f64>,
+/// Two-coordinate vector, used for surface (texture)
+/// coordinates and planar polygon vertices.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[repr(C)]
+pub struct Vec2 {
+    /// Coordinates of the vector.
+    pub c: [f64; 2],
 }
 
-/// Operations on Points.
-impl Point {
-    /// Create a new Point from an array of floats.
-    /// (This should probably be a macro.)
-    pub fn new<const N: usize>(cs: [f64; N]) -> Self {
-        Point { c: cs.to_vec() }
-    }
+/// Three-coordinate vector.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[repr(C)]
+pub struct Vec3 {
+    /// Coordinates of the vector.
+    pub c: [f64; 3],
+}
+
+/// Four-coordinate homogeneous vector. Aligned so that it
+/// fits a 256-bit SIMD register.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[repr(C, align(32))]
+pub struct Vec4 {
+    /// Coordinates of the vector.
+    pub c: [f64; 4],
+}
 
-    /// Convert the Point to its coordinate-wise negation.
-    pub fn negate(&mut self) {
-        for i in 0..self.c.len() {
-            self.c[i] = -self.c[i];
+/// A location in 3-space. This is only another name for
+/// `Vec3`, to say what a value means: nothing stops a
+/// point being used as a vector. Transform points with
+/// `Xform::apply_point()`.
+pub type Point3 = Vec3;
+
+/// A surface normal in 3-space. Like `Point3`, this is only
+/// another name for `Vec3`. Transform normals with
+/// `Xform::apply_normal()`.
+pub type Normal3 = Vec3;
+
+// Operations common to all the vector types.
+macro_rules! vector_ops {
+    ($v:ident, $n:literal) => {
+        impl $v {
+            /// Dot product of two vectors.
+            pub fn dot(&self, p: &Self) -> f64 {
+                let mut r = 0.0;
+                for i in 0..$n {
+                    r += self.c[i] * p.c[i];
+                }
+                r
+            }
+
+            /// Convert the vector to its coordinate-wise negation.
+            pub fn negate(&mut self) {
+                for i in 0..$n {
+                    self.c[i] = -self.c[i];
+                }
+            }
+
+            /// Square of Euclidean magnitude of a vector.
+            pub fn mag2(&self) -> f64 {
+                self.dot(self)
+            }
+
+            /// Euclidean magnitude of a vector.
+            pub fn mag(&self) -> f64 {
+                self.mag2().sqrt()
+            }
+
+            /// Convert the vector to a unit (direction) vector.
+            pub fn unitize(&mut self) {
+                let m = self.mag();
+                assert!(m > TINY);
+                *self *= 1.0 / m;
+            }
+
+            /// Return the unit (direction) vector of a vector.
+            pub fn unit(&self) -> Self {
+                let mut r = *self;
+                r.unitize();
+                r
+            }
+
+            /// Return the number of coordinates of the vector.
+            pub const fn len(&self) -> usize {
+                $n
+            }
+
+            /// A vector is never empty; present for symmetry
+            /// with `len()`.
+            pub const fn is_empty(&self) -> bool {
+                false
+            }
         }
-    }
 
-    /// Cross product of two Points, which should be
-    /// homogenous.
-    pub fn cross_product(&self, p: &Self) -> Self {
-        assert!(self.c.len() == 3 && p.c.len() == 3);
-        let r = [
-            self.c[1] * p.c[2] - self.c[2] * p.c[1],
-            self.c[2] * p.c[0] - self.c[0] * p.c[2],
-            self.c[0] * p.c[1] - self.c[1] * p.c[0],
-            1.0,
-        ];
-        Point::new(r)
-    }
+        impl Index<usize> for $v {
+            type Output = f64;
 
-    /// Square of Euclidean magnitude of a Point.
-    pub fn mag2(&self) -> f64 {
-        let mut r = 0.0;
-        for i in 0..self.c.len() {
-            let c = self.c[i];
-            r += c * c;
+            /// Return the given coordinate of a vector using `[]`
+            /// subscripting.
+            fn index(&self, i: usize) -> &f64 {
+                &self.c[i]
+            }
         }
-        r
-    }
 
-    /// Euclidean magnitude of a Point.
-    pub fn mag(&self) -> f64 {
-        self.mag2().sqrt()
-    }
+        impl IndexMut<usize> for $v {
+            /// Assign the given coordinate of a vector using `[]`
+            /// subscripting.
+            fn index_mut(&mut self, i: usize) -> &mut f64 {
+                &mut self.c[i]
+            }
+        }
 
-    /// Convert the Point to a unit (direction) vector.
-    pub fn unitize(&mut self) {
-        let m = self.mag();
-        assert!(m > TINY);
-        *self *= 1.0 / m;
-    }
+        impl Mul for $v {
+            type Output = f64;
 
-    /// Return the unit (direction) vector of a Point.
-    pub fn unit(&self) -> Self {
-        let mut r = self.clone();
-        r.unitize();
-        r
-    }
+            /// Return the dot product of two vectors using `*`
+            /// notation.
+            fn mul(self, rhs: Self) -> f64 {
+                self.dot(&rhs)
+            }
+        }
 
-    /// Convert the 4-coordinate Point to a homogenous
-    /// representation. (Scale so that final coordinate is 1.)
-    pub fn homogenize(&mut self) {
-        assert!(self.c.len() == 4);
-        *self *= 1.0 / self.c[3];
-    }
+        impl Mul<f64> for $v {
+            type Output = Self;
 
-    /// Homogenous representation of a 4-coordinate
-    /// Point. (Scaled so that final coordinate is 1.)
-    pub fn homogeneous(&self) -> Self {
-        let mut r = self.clone();
-        r.homogenize();
-        r
-    }
+            /// Return the coordinate-wise product of a vector and
+            /// the given scale using `*` notation.
+            fn mul(self, rhs: f64) -> Self {
+                let mut tmp = self;
+                tmp *= rhs;
+                tmp
+            }
+        }
 
-    /// Return the number of coordinates of the Point.
-    pub fn len(&self) -> usize {
-        self.c.len()
-    }
+        impl Neg for $v {
+            type Output = Self;
 
-    pub fn is_empty(&self) -> bool {
-        self.len() == 0
-    }
+            /// Return the negation of a vector using unary `-`
+            /// notation.
+            fn neg(self) -> Self {
+                let mut tmp = self;
+                tmp.negate();
+                tmp
+            }
+        }
 
-    pub fn transform(&mut self, t: &Xform) {
-        let nself = self.len();
-        assert!(nself <= 4);
-        let mut p = [0.0, 0.0, 0.0, 1.0];
-        for (i, v) in self.c.iter().enumerate() {
-            p[i] = *v;
+        impl Add for $v {
+            type Output = Self;
+
+            /// Return the coordinate-wise sum of two vectors using
+            /// `+` notation.
+            fn add(self, rhs: Self) -> Self {
+                let mut tmp = self;
+                tmp += rhs;
+                tmp
+            }
         }
-        let mut x = t * &Point::new(p);
-        x.c.truncate(nself);
-        *self = x;
-    }
-}
 
-impl Index<usize> for Point {
-    type Output = f64;
+        impl Sub for $v {
+            type Output = Self;
 
-    /// Return the given coordinate of a Point using `[]`
-    /// subscripting.
-    fn index(&self, i: usize) -> &f64 {
-        &self.c[i]
-    }
-}
+            /// Return the coordinate-wise difference of two vectors
+            /// using `-` notation.
+            fn sub(self, rhs: Self) -> Self {
+                let mut tmp = self;
+                tmp -= rhs;
+                tmp
+            }
+        }
 
-impl IndexMut<usize> for Point {
-    /// Assign the given coordinate of a Point using `[]`
-    /// subscripting.
-    fn index_mut(&mut self, i: usize) -> &mut f64 {
-        &mut self.c[i]
-    }
-}
+        impl MulAssign<f64> for $v {
+            /// Multiply the vector by the given scalar using `*=`
+            /// notation.
+            fn mul_assign(&mut self, rhs: f64) {
+                for i in 0..$n {
+                    self.c[i] *= rhs;
+                }
+            }
+        }
 
-impl Mul for Point {
-    type Output = f64;
-
-    /// Return the dot product of two Points using `*` notation.
-    /// The points should be homogenous: the z coordinate is ignored.
-    fn mul(self, rhs: Self) -> f64 {
-        let nself = self.c.len();
-        assert_eq!(nself, rhs.c.len());
-        let mut r: f64 = 0.0;
-        for i in 0..nself {
-            r += self.c[i] * rhs.c[i];
+        impl AddAssign for $v {
+            /// Adjust each coordinate of the vector by adding the
+            /// corresponding coordinate of the given vector using
+            /// `+=` notation. (Vector sum.)
+            fn add_assign(&mut self, rhs: Self) {
+                for i in 0..$n {
+                    self.c[i] += rhs.c[i];
+                }
+            }
         }
-        r
-    }
+
+        impl SubAssign for $v {
+            /// Adjust each coordinate of the vector by subtracting
+            /// the corresponding coordinate of the given vector
+            /// using `-=` notation. (Vector difference.)
+            fn sub_assign(&mut self, rhs: Self) {
+                for i in 0..$n {
+                    self.c[i] -= rhs.c[i];
+                }
+            }
+        }
+
+        impl From<[f64; $n]> for $v {
+            fn from(c: [f64; $n]) -> Self {
+                Self { c }
+            }
+        }
+    };
 }
 
-impl Mul<f64> for Point {
-    type Output = Self;
+vector_ops!(Vec2, 2);
+vector_ops!(Vec3, 3);
+vector_ops!(Vec4, 4);
 
-    /// Return the coordinate-wise product of a Point and
-    /// the given scale using `*` notation.
-    fn mul(self, rhs: f64) -> Self {
-        let mut tmp = self;
-        tmp *= rhs;
-        tmp
+impl Vec2 {
+    /// Create a new vector from coordinates.
+    pub const fn new(x: f64, y: f64) -> Self {
+        Vec2 { c: [x, y] }
     }
 }
 
-impl Neg for Point {
-    type Output = Self;
+impl Vec3 {
+    /// Create a new vector from coordinates.
+    pub const fn new(x: f64, y: f64, z: f64) -> Self {
+        Vec3 { c: [x, y, z] }
+    }
 
-    /// Return the negation of a Point using unary `-`
-    /// notation.
-    fn neg(self) -> Self {
-        let mut tmp = self;
-        tmp.negate();
-        tmp
+    /// Cross product of two vectors.
+    pub fn cross_product(&self, p: &Self) -> Self {
+        Vec3::new(
+            self.c[Y] * p.c[Z] - self.c[Z] * p.c[Y],
+            self.c[Z] * p.c[X] - self.c[X] * p.c[Z],
+            self.c[X] * p.c[Y] - self.c[Y] * p.c[X],
+        )
     }
-}
 
-impl Add for Point {
-    type Output = Self;
+    /// The x and y coordinates of the vector.
+    pub fn xy(&self) -> Vec2 {
+        Vec2::new(self.c[X], self.c[Y])
+    }
 
-    /// Return the coordinate-wise sum of two Points using
-    /// `+` notation.
-    fn add(self, rhs: Self) -> Self {
-        let mut tmp = self;
-        tmp += rhs;
-        tmp
+    /// Transform the vector as a location: translation
+    /// applies.
+    pub fn transform(&mut self, t: &Xform) {
+        *self = t.apply_point(self);
     }
 }
 
-impl Sub for Point {
-    type Output = Self;
+impl Vec4 {
+    /// Create a new vector from coordinates.
+    pub const fn new(x: f64, y: f64, z: f64, w: f64) -> Self {
+        Vec4 { c: [x, y, z, w] }
+    }
 
-    /// Return the coordinate-wise difference of two Points
-    /// using `-` notation.
-    fn sub(self, rhs: Self) -> Self {
-        let mut tmp = self;
-        tmp -= rhs;
-        tmp
+    /// Homogeneous representation of a location.
+    pub fn point(p: &Vec3) -> Self {
+        Vec4::new(p[X], p[Y], p[Z], 1.0)
     }
-}
 
-impl MulAssign<f64> for Point {
-    /// Multiply the point by the given scalar using `*=`
-    /// notation.
-    fn mul_assign(&mut self, rhs: f64) {
-        for i in 0..self.c.len() {
-            self.c[i] *= rhs;
-        }
+    /// Homogeneous representation of a direction.
+    pub fn direction(p: &Vec3) -> Self {
+        Vec4::new(p[X], p[Y], p[Z], 0.0)
     }
-}
 
-impl AddAssign for Point {
-    /// Adjust each coordinate of the Point by adding the
-    /// corresponding coordinate of the given Point using `+=`
-    /// notation. (Vector sum.)
-    fn add_assign(&mut self, rhs: Self) {
-        for i in 0..self.c.len() {
-            self.c[i] += rhs.c[i];
-        }
+    /// The x, y and z coordinates of the vector.
+    pub fn xyz(&self) -> Vec3 {
+        Vec3::new(self.c[X], self.c[Y], self.c[Z])
     }
-}
 
-impl SubAssign for Point {
-    /// Adjust each coordinate of the Point by subtracting
-    /// the corresponding coordinate of the given Point
-    /// using `-=` notation. (Vector sum.)
-    fn sub_assign(&mut self, rhs: Self) {
-        *self += -rhs;
+    /// Convert the vector to a homogenous
+    /// representation. (Scale so that final coordinate is 1.)
+    pub fn homogenize(&mut self) {
+        *self *= 1.0 / self.c[W];
     }
+
+    /// Homogenous representation of the
+    /// vector. (Scaled so that final coordinate is 1.)
+    pub fn homogeneous(&self) -> Self {
+        let mut r = *self;
+        r.homogenize();
+        r
+    }
+}
+
+#[test]
+fn test_vec3_ops() {
+    let x = Vec3::new(1.0, 0.0, 0.0);
+    let y = Vec3::new(0.0, 1.0, 0.0);
+    assert_eq!(Vec3::new(0.0, 0.0, 1.0), x.cross_product(&y));
+    assert_eq!(0.0, x * y);
+    assert_eq!(Vec3::new(1.0, -1.0, 0.0), x - y);
+    assert_eq!(2.0f64.sqrt(), (x + y).mag());
 }
@@ -0,0 +1,223 @@
+//! Unit quaternions for representing and interpolating
+//! rotations.
+
+use std::ops::{Mul, Neg};
+
+use crate::*;
+
+/// A quaternion `w + xi + yj + zk`. Rotations are
+/// represented by unit quaternions.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Quaternion {
+    /// Scalar part.
+    pub w: f64,
+    /// Coefficient of i.
+    pub x: f64,
+    /// Coefficient of j.
+    pub y: f64,
+    /// Coefficient of k.
+    pub z: f64,
+}
+
+impl Default for Quaternion {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl Quaternion {
+    /// Create a new quaternion from components.
+    pub fn new(w: f64, x: f64, y: f64, z: f64) -> Self {
+        Self { w, x, y, z }
+    }
+
+    /// The identity rotation.
+    pub fn identity() -> Self {
+        Self::new(1.0, 0.0, 0.0, 0.0)
+    }
+
+    /// Rotation around the given axis by the given angle in
+    /// radians. The axis need not be unit length.
+    pub fn from_axis_angle(axis: &Vec3, angle: f64) -> Self {
+        let a = axis.unit();
+        let (s, c) = (0.5 * angle).sin_cos();
+        Self::new(c, s * a[X], s * a[Y], s * a[Z])
+    }
+
+    /// Rotation corresponding to the upper-left 3x3 of the
+    /// given matrix, which must be orthonormal with positive
+    /// determinant.
+    pub fn from_matrix(m: &XFMatrix) -> Self {
+        // Shepperd's method: pick the largest diagonal
+        // term to divide by for numerical stability.
+        let tr = m[0][0] + m[1][1] + m[2][2];
+        let q = if tr > 0.0 {
+            let s = 2.0 * (tr + 1.0).sqrt();
+            Self::new(
+                0.25 * s,
+                (m[2][1] - m[1][2]) / s,
+                (m[0][2] - m[2][0]) / s,
+                (m[1][0] - m[0][1]) / s,
+            )
+        } else if m[0][0] > m[1][1] && m[0][0] > m[2][2] {
+            let s = 2.0 * (1.0 + m[0][0] - m[1][1] - m[2][2]).sqrt();
+            Self::new(
+                (m[2][1] - m[1][2]) / s,
+                0.25 * s,
+                (m[0][1] + m[1][0]) / s,
+                (m[0][2] + m[2][0]) / s,
+            )
+        } else if m[1][1] > m[2][2] {
+            let s = 2.0 * (1.0 + m[1][1] - m[0][0] - m[2][2]).sqrt();
+            Self::new(
+                (m[0][2] - m[2][0]) / s,
+                (m[0][1] + m[1][0]) / s,
+                0.25 * s,
+                (m[1][2] + m[2][1]) / s,
+            )
+        } else {
+            let s = 2.0 * (1.0 + m[2][2] - m[0][0] - m[1][1]).sqrt();
+            Self::new(
+                (m[1][0] - m[0][1]) / s,
+                (m[0][2] + m[2][0]) / s,
+                (m[1][2] + m[2][1]) / s,
+                0.25 * s,
+            )
+        };
+        q.unit()
+    }
+
+    /// Four-dimensional dot product.
+    pub fn dot(&self, q: &Self) -> f64 {
+        self.w * q.w + self.x * q.x + self.y * q.y + self.z * q.z
+    }
+
+    /// Euclidean magnitude of the quaternion.
+    pub fn mag(&self) -> f64 {
+        self.dot(self).sqrt()
+    }
+
+    /// Return the quaternion scaled to unit magnitude.
+    pub fn unit(&self) -> Self {
+        let m = self.mag();
+        assert!(m > TINY);
+        Self::new(self.w / m, self.x / m, self.y / m, self.z / m)
+    }
+
+    /// Conjugate of the quaternion. For a unit quaternion
+    /// this is the inverse rotation.
+    pub fn conjugate(&self) -> Self {
+        Self::new(self.w, -self.x, -self.y, -self.z)
+    }
+
+    /// Axis and angle in radians of the rotation represented
+    /// by this unit quaternion. The identity rotation
+    /// reports the x axis.
+    pub fn axis_angle(&self) -> (Vec3, f64) {
+        let q = if self.w < 0.0 { -*self } else { *self };
+        let s = (q.x * q.x + q.y * q.y + q.z * q.z).sqrt();
+        if s < TINY {
+            return (Vec3::new(1.0, 0.0, 0.0), 0.0);
+        }
+        let angle = 2.0 * s.atan2(q.w);
+        (Vec3::new(q.x / s, q.y / s, q.z / s), angle)
+    }
+
+    /// Rotation matrix for this unit quaternion, in the
+    /// homogeneous form used by `Xform`.
+    pub fn to_matrix(&self) -> XFMatrix {
+        let Self { w, x, y, z } = *self;
+        [
+            [
+                1.0 - 2.0 * (y * y + z * z),
+                2.0 * (x * y - w * z),
+                2.0 * (x * z + w * y),
+                0.0,
+            ],
+            [
+                2.0 * (x * y + w * z),
+                1.0 - 2.0 * (x * x + z * z),
+                2.0 * (y * z - w * x),
+                0.0,
+            ],
+            [
+                2.0 * (x * z - w * y),
+                2.0 * (y * z + w * x),
+                1.0 - 2.0 * (x * x + y * y),
+                0.0,
+            ],
+            [0.0, 0.0, 0.0, 1.0],
+        ]
+    }
+
+    /// Spherical linear interpolation between two unit
+    /// quaternions, taking the shorter path. `t` of 0 gives
+    /// `self`, `t` of 1 gives `q`.
+    pub fn slerp(&self, q: &Self, t: f64) -> Self {
+        let mut q = *q;
+        let mut d = self.dot(&q);
+        if d < 0.0 {
+            q = -q;
+            d = -d;
+        }
+        if d > 1.0 - TINY {
+            // Nearly parallel: lerp to avoid dividing by a
+            // vanishing sine.
+            let r = Self::new(
+                self.w + t * (q.w - self.w),
+                self.x + t * (q.x - self.x),
+                self.y + t * (q.y - self.y),
+                self.z + t * (q.z - self.z),
+            );
+            return r.unit();
+        }
+        let theta = d.acos();
+        let s = theta.sin();
+        let a = ((1.0 - t) * theta).sin() / s;
+        let b = (t * theta).sin() / s;
+        Self::new(
+            a * self.w + b * q.w,
+            a * self.x + b * q.x,
+            a * self.y + b * q.y,
+            a * self.z + b * q.z,
+        )
+    }
+}
+
+impl Mul for Quaternion {
+    type Output = Self;
+
+    /// Hamilton product of two quaternions using `*`
+    /// notation. As rotations, `a * b` applies `b` first.
+    fn mul(self, q: Self) -> Self {
+        Self::new(
+            self.w * q.w - self.x * q.x - self.y * q.y - self.z * q.z,
+            self.w * q.x + self.x * q.w + self.y * q.z - self.z * q.y,
+            self.w * q.y - self.x * q.z + self.y * q.w + self.z * q.x,
+            self.w * q.z + self.x * q.y - self.y * q.x + self.z * q.w,
+        )
+    }
+}
+
+impl Neg for Quaternion {
+    type Output = Self;
+
+    /// Coordinate-wise negation. Represents the same
+    /// rotation.
+    fn neg(self) -> Self {
+        Self::new(-self.w, -self.x, -self.y, -self.z)
+    }
+}
+
+#[test]
+fn test_quaternion_slerp() {
+    let z = Vec3::new(0.0, 0.0, 1.0);
+    let q0 = Quaternion::identity();
+    let q1 = Quaternion::from_axis_angle(&z, PI / 2.0);
+    let (axis, angle) = q0.slerp(&q1, 0.5).axis_angle();
+    assert!((angle - PI / 4.0).abs() < TINY);
+    assert!((axis[Z] - 1.0).abs() < TINY);
+
+    let q = Quaternion::from_matrix(&q1.to_matrix());
+    assert!((q.dot(&q1).abs() - 1.0).abs() < TINY);
+}
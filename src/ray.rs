@@ -1,27 +1,71 @@
 use crate::*;
 
-#[derive(Clone)]
+/// Rays offset from a ray by one pixel in each direction of
+/// the image, for estimating how much of a surface the
+/// ray's pixel covers.
+#[derive(Clone, Copy)]
+pub struct RayDifferentials {
+    /// Origin and unit direction of the ray one pixel over
+    /// in x.
+    pub x: (Point3, Vec3),
+    /// Origin and unit direction of the ray one pixel over
+    /// in y.
+    pub y: (Point3, Vec3),
+}
+
+#[derive(Clone, Copy)]
 pub struct Ray {
-    pub ro: Point,
-    pub rd: Point,
+    pub ro: Point3,
+    pub rd: Vec3,
+    pub differentials: Option<RayDifferentials>,
 }
 
 impl Ray {
-    pub fn new(ro: Point, rd: Point) -> Self {
-        let mut result = Self { ro, rd };
+    pub fn new(ro: Point3, rd: Vec3) -> Self {
+        let mut result = Self {
+            ro,
+            rd,
+            differentials: None,
+        };
         result.rd.unitize();
         result
     }
 
+    /// Ray with the given offset rays. The offset directions
+    /// are made unit, as is that of the ray.
+    pub fn with_differentials(ro: Point3, rd: Vec3, x: (Point3, Vec3), y: (Point3, Vec3)) -> Self {
+        let mut result = Self::new(ro, rd);
+        result.differentials = Some(RayDifferentials {
+            x: (x.0, x.1.unit()),
+            y: (y.0, y.1.unit()),
+        });
+        result
+    }
+
+    /// Shrink the offset rays towards the ray by the given
+    /// factor, as when several rays sample each pixel.
+    pub fn scale_differentials(&mut self, s: f64) {
+        let (ro, rd) = (self.ro, self.rd);
+        if let Some(d) = self.differentials.as_mut() {
+            for (o, v) in [&mut d.x, &mut d.y] {
+                *o = ro + (*o - ro) * s;
+                *v = rd + (*v - rd) * s;
+            }
+        }
+    }
+
     pub fn transform(&mut self, t: &Xform) {
-        self.ro.transform(t);
-        self.rd.transform(t);
-        let mut dorg = Point::new([0.0, 0.0, 0.0]);
-        dorg.transform(t);
-        self.rd -= dorg;
+        self.ro = t.apply_point(&self.ro);
+        self.rd = t.apply_vector(&self.rd);
+        if let Some(d) = self.differentials.as_mut() {
+            for (o, v) in [&mut d.x, &mut d.y] {
+                *o = t.apply_point(o);
+                *v = t.apply_vector(v);
+            }
+        }
     }
 
-    pub fn at(&self, t: f64) -> Point {
-        self.rd.clone() * t + self.ro.clone()
+    pub fn at(&self, t: f64) -> Point3 {
+        self.rd * t + self.ro
     }
 }
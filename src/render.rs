@@ -7,18 +7,89 @@ pub fn trace(r: &Ray, m: &Model, depth: usize) -> Color {
         return m.bg;
     }
 
-    let first_intersection = m
-        .scene
+    match first_hit(r, m) {
+        Some((i, p)) => shade(r, &i, p, m, depth),
+        None => m.bg,
+    }
+}
+
+/// As `trace()`, but leaving out the light given off by a
+/// thing that is a light source, for a ray whose direction
+/// a surface chose for itself after sampling the light
+/// sources directly: that light is already counted. Parts
+/// of the thing that do not glow are shaded as usual.
+pub fn trace_indirect(r: &Ray, m: &Model, depth: usize) -> Color {
+    if depth > m.max_depth {
+        return m.bg;
+    }
+
+    let glows =
+        |i: &Intersection, p: &Thing| i.texture.unwrap_or(p.texture.as_ref()).light_samples() > 0;
+    match first_hit(r, m) {
+        Some((i, p)) if m.is_emitter(p) && glows(&i, p) => Color::new(0.0, 0.0, 0.0),
+        Some((i, p)) => shade(r, &i, p, m, depth),
+        None => m.bg,
+    }
+}
+
+// Color of the thing where the ray hit it.
+fn shade(r: &Ray, i: &Intersection, p: &Thing, m: &Model, depth: usize) -> Color {
+    let texture = i.texture.unwrap_or(p.texture.as_ref());
+    let footprint = surface_footprint(r, i);
+    texture.filtered_value(&i.at, &footprint, &r.at(i.t), &i.normal, m, depth + 1)
+}
+
+/// The nearest hit of the ray with the things of the
+/// model, and the thing hit.
+pub fn first_hit<'a>(r: &Ray, m: &'a Model) -> Option<(Intersection<'a>, &'a Thing)> {
+    m.scene
         .iter()
         .filter_map(|p| p.shape.intersect(&p.xform, r).map(|i| (i, p)))
-        .min_by(|x1, x2| x1.0.t.partial_cmp(&x2.0.t).unwrap());
+        .min_by(|x1, x2| x1.0.t.partial_cmp(&x2.0.t).unwrap())
+}
 
-    match first_intersection {
-        Some((i, p)) => {
-            let nr = r.at(i.t);
-            p.texture.value(&i.at, &nr, &i.normal, m, depth + 1)
+/// Everything a texture may want to know about where and
+/// how the ray hit the thing.
+pub fn surface_footprint(r: &Ray, i: &Intersection) -> Footprint {
+    let mut footprint = footprint(r, i);
+    footprint.object = i.object;
+    footprint.dpdu = i.dpdu;
+    footprint.dpdv = i.dpdv;
+    footprint.dndu = i.dndu;
+    footprint.dndv = i.dndv;
+    footprint.object_dpdu = i.object_dpdu;
+    footprint.object_dpdv = i.object_dpdv;
+    footprint.dir = r.rd.unit();
+    footprint
+}
+
+// Find the footprint of a ray's pixel on the surface it
+// hit: the offset rays a pixel over are taken to the
+// tangent plane at the hit, and the step from the hit to
+// where they meet it is written in terms of the surface's
+// tangents. An offset ray parallel to the plane is taken
+// to the hit itself.
+fn footprint(r: &Ray, i: &Intersection) -> Footprint {
+    let Some(mut rays) = r.differentials else {
+        return Footprint::default();
+    };
+    let hit = r.at(i.t);
+
+    let mut duv = [Vec2::default(); 2];
+    for (k, (o, v)) in [&mut rays.x, &mut rays.y].into_iter().enumerate() {
+        let dn = v.dot(&i.normal);
+        if dn.abs() > TINY {
+            *o = *o + *v * ((hit - *o).dot(&i.normal) / dn);
+        } else {
+            *o = hit;
         }
-        None => m.bg,
+        duv[k] = uv_change(&(*o - hit), &i.dpdu, &i.dpdv);
+    }
+    Footprint {
+        dx: duv[0],
+        dy: duv[1],
+        rays: Some(rays),
+        ..Default::default()
     }
 }
 
@@ -47,33 +118,50 @@ where
     let scale: f64 = hs / w.max(h) as f64;
     let view_xform = Xform::rotation_y(-A);
 
-    let trace_one = |j, i| {
-        let mut rt = Point::new([
-            scale * (2.0 * j as f64 - w as f64),
-            scale * (2.0 * i as f64 - h as f64),
-            D,
-        ]);
-        match antialias {
-            None => {
-                rt.transform(&view_xform);
-                let r = Ray::new(m.eye.clone(), rt);
-                trace(&r, m, 0)
-            }
-            Some(aa) => {
-                let mut ave = Color::new(0.0, 0.0, 0.0);
-                for k in 0..aa {
-                    let mut rt = Point::new([
-                        2.0 * hs * (j as f64 + do_joggle(f64::cos, k, aa, frandom())) / h as f64 - hs,
-                        2.0 * hs * (i as f64 + do_joggle(f64::sin, k, aa, frandom())) / w as f64 - hs,
-                        D,
-                    ]);
-                    rt.transform(&view_xform);
-                    let r = Ray::new(m.eye.clone(), rt);
-                    ave += trace(&r, m, 0);
-                }
-                ave *= 1.0 / aa as f64;
-                ave
+    // Ray from the eye through the given position on the
+    // screen, with offset rays one pixel over in each
+    // direction.
+    let camera_ray = |aim: &dyn Fn(f64, f64) -> Vec3, x: f64, y: f64| {
+        let dir = |x, y| view_xform.apply_point(&aim(x, y));
+        Ray::with_differentials(
+            m.eye,
+            dir(x, y),
+            (m.eye, dir(x + 1.0, y)),
+            (m.eye, dir(x, y + 1.0)),
+        )
+    };
+
+    let trace_one = |j, i| match antialias {
+        None => {
+            let aim = |x: f64, y: f64| {
+                Vec3::new(
+                    scale * (2.0 * x - w as f64),
+                    scale * (2.0 * y - h as f64),
+                    D,
+                )
+            };
+            let r = camera_ray(&aim, j as f64, i as f64);
+            trace(&r, m, 0)
+        }
+        Some(aa) => {
+            let aim = |x: f64, y: f64| {
+                Vec3::new(
+                    2.0 * hs * x / h as f64 - hs,
+                    2.0 * hs * y / w as f64 - hs,
+                    D,
+                )
+            };
+            let mut ave = Color::new(0.0, 0.0, 0.0);
+            for k in 0..aa {
+                let x = j as f64 + do_joggle(f64::cos, k, aa, frandom());
+                let y = i as f64 + do_joggle(f64::sin, k, aa, frandom());
+                let mut r = camera_ray(&aim, x, y);
+                // Each sample covers a fraction of the pixel.
+                r.scale_differentials(1.0 / (aa as f64).sqrt());
+                ave += trace(&r, m, 0);
             }
+            ave *= 1.0 / aa as f64;
+            ave
         }
     };
 
@@ -100,3 +188,29 @@ where
         }
     }
 }
+
+#[test]
+fn test_footprint_analytic() {
+    // The footprint found from the tangents agrees with
+    // tracing the offset rays themselves, for offsets small
+    // enough that the sphere is nearly flat across them.
+    let kblack = Color::new(0.0, 0.0, 0.0);
+    let mut xform = Xform::scaling(&Vec3::new(2.0, 1.0, 1.0));
+    xform *= &Xform::translation(&Vec3::new(0.0, 0.0, 5.0));
+    let p = Thing {
+        shape: Box::<Sphere>::default(),
+        texture: Box::new(SolidTexture::new(kblack, kblack, kblack, 1.0)),
+        xform,
+    };
+
+    let eye = Vec3::new(0.0, 0.0, 0.0);
+    let aim = |x: f64, y: f64| Vec3::new(0.15 + x, 0.05 + y, 1.0);
+    let e = 1e-4;
+    let r = Ray::with_differentials(eye, aim(0.0, 0.0), (eye, aim(e, 0.0)), (eye, aim(0.0, e)));
+    let i = p.shape.intersect(&p.xform, &r).unwrap();
+    let f = surface_footprint(&r, &i);
+    for (v, duv) in [(aim(e, 0.0), f.dx), (aim(0.0, e), f.dy)] {
+        let j = p.shape.intersect(&p.xform, &Ray::new(eye, v)).unwrap();
+        assert!((j.at - i.at - duv).mag() < 1e-3 * duv.mag());
+    }
+}
@@ -0,0 +1,199 @@
+//! Hierarchical scene description. A scene is a tree of
+//! nodes, each with a transform relative to its parent and
+//! optionally a texture that its descendants inherit. The
+//! tree is flattened into `Thing`s for rendering.
+
+use std::sync::Arc;
+
+use crate::*;
+
+/// Errors in flattening a scene.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SceneError {
+    /// The shape at the given path has no texture of its own
+    /// and none to inherit.
+    NoTexture(String),
+}
+
+impl std::fmt::Display for SceneError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            SceneError::NoTexture(path) => write!(f, "no texture for shape {}", path),
+        }
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+/// What a scene node holds.
+pub enum SceneContents {
+    Shape(Box<dyn Shape>),
+    Group(Vec<SceneNode>),
+}
+
+/// A node of a scene tree.
+pub struct SceneNode {
+    /// Name by which the node can be found.
+    pub name: Option<String>,
+    /// Transform relative to the parent node: applied
+    /// before the parent's.
+    pub xform: Xform,
+    /// Texture for this node's shapes, and the default for
+    /// its descendants.
+    pub texture: Option<Arc<dyn Texture>>,
+    pub contents: SceneContents,
+}
+
+impl SceneNode {
+    fn new(contents: SceneContents) -> Self {
+        Self {
+            name: None,
+            xform: Xform::identity(),
+            texture: None,
+            contents,
+        }
+    }
+
+    /// Leaf node holding a shape.
+    pub fn shape<S: Shape + 'static>(shape: S) -> Self {
+        Self::new(SceneContents::Shape(Box::new(shape)))
+    }
+
+    /// Group node holding the given children.
+    pub fn group(children: Vec<SceneNode>) -> Self {
+        Self::new(SceneContents::Group(children))
+    }
+
+    pub fn named(mut self, name: &str) -> Self {
+        self.name = Some(name.to_string());
+        self
+    }
+
+    pub fn with_xform(mut self, xform: Xform) -> Self {
+        self.xform = xform;
+        self
+    }
+
+    pub fn with_texture<T: Texture + 'static>(mut self, texture: T) -> Self {
+        self.texture = Some(Arc::new(texture));
+        self
+    }
+
+    /// Add a child to a group node. Panics on a shape node.
+    pub fn push(&mut self, child: SceneNode) {
+        match &mut self.contents {
+            SceneContents::Group(children) => children.push(child),
+            SceneContents::Shape(_) => panic!("push onto a shape node"),
+        }
+    }
+
+    fn children(&self) -> &[SceneNode] {
+        match &self.contents {
+            SceneContents::Group(children) => children,
+            SceneContents::Shape(_) => &[],
+        }
+    }
+
+    /// First node with the given name, searching depth
+    /// first from this one.
+    pub fn find(&self, name: &str) -> Option<&SceneNode> {
+        if self.name.as_deref() == Some(name) {
+            return Some(self);
+        }
+        self.children().iter().find_map(|c| c.find(name))
+    }
+
+    /// First node with the given name, searching depth
+    /// first from this one, for adjustment: posing a joint
+    /// by changing its transform, say.
+    pub fn find_mut(&mut self, name: &str) -> Option<&mut SceneNode> {
+        if self.name.as_deref() == Some(name) {
+            return Some(self);
+        }
+        match &mut self.contents {
+            SceneContents::Group(children) => children.iter_mut().find_map(|c| c.find_mut(name)),
+            SceneContents::Shape(_) => None,
+        }
+    }
+
+    /// Transform taking the named node's coordinates to
+    /// those of this node, composed through every node
+    /// between.
+    pub fn world_xform(&self, name: &str) -> Option<Xform> {
+        if self.name.as_deref() == Some(name) {
+            return Some(self.xform);
+        }
+        let mut xform = self.children().iter().find_map(|c| c.world_xform(name))?;
+        xform *= &self.xform;
+        Some(xform)
+    }
+
+    /// Flatten the tree into completed `Thing`s with fully
+    /// composed transforms and inherited textures.
+    pub fn flatten(self) -> Result<Vec<Thing>, SceneError> {
+        let mut things = Vec::new();
+        self.flatten_into(&Xform::identity(), None, "", &mut things)?;
+        Ok(things)
+    }
+
+    fn flatten_into(
+        self,
+        parent: &Xform,
+        inherited: Option<&Arc<dyn Texture>>,
+        path: &str,
+        things: &mut Vec<Thing>,
+    ) -> Result<(), SceneError> {
+        let mut xform = self.xform;
+        xform *= parent;
+        let texture = self.texture.as_ref().or(inherited);
+        match self.contents {
+            SceneContents::Shape(mut shape) => {
+                let texture = texture.ok_or_else(|| SceneError::NoTexture(path.to_string()))?;
+                shape.complete(&xform);
+                things.push(Thing {
+                    shape,
+                    texture: Box::new(texture.clone()),
+                    xform,
+                });
+            }
+            SceneContents::Group(children) => {
+                for (i, c) in children.into_iter().enumerate() {
+                    // Unnamed nodes are identified by position.
+                    let step = c.name.clone().unwrap_or_else(|| i.to_string());
+                    let path = format!("{}/{}", path, step);
+                    c.flatten_into(&xform, texture, &path, things)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_scene_flatten() {
+    let kblack = Color::new(0.0, 0.0, 0.0);
+    let texture = SolidTexture::new(kblack, kblack, kblack, 1.0);
+
+    // A two-link arm: the forearm hangs off the end of the
+    // upper arm, which turns at the shoulder.
+    let forearm = SceneNode::group(vec![SceneNode::shape(Sphere::default()).named("hand")])
+        .named("elbow")
+        .with_xform(Xform::translation(&Vec3::new(2.0, 0.0, 0.0)));
+    let arm =
+        SceneNode::group(vec![SceneNode::shape(Sphere::default()), forearm]).named("shoulder");
+    let mut root = SceneNode::group(vec![arm])
+        .with_xform(Xform::translation(&Vec3::new(0.0, 0.0, 5.0)))
+        .with_texture(texture);
+
+    root.find_mut("shoulder").unwrap().xform = Xform::rotation_z(PI / 2.0);
+    let hand = root.world_xform("hand").unwrap();
+    assert!((hand.apply_point(&Vec3::default()) - Vec3::new(0.0, 2.0, 5.0)).mag() < TINY);
+
+    let things = root.flatten().unwrap();
+    assert_eq!(2, things.len());
+    let p = things[1].xform.apply_point(&Vec3::default());
+    assert!((p - Vec3::new(0.0, 2.0, 5.0)).mag() < TINY);
+
+    let bare = SceneNode::group(vec![SceneNode::shape(Sphere::default()).named("ball")]);
+    assert!(matches!(bare.flatten(), Err(SceneError::NoTexture(p)) if p == "/ball"));
+}
@@ -0,0 +1,19 @@
+//! Signed distance fields: functions giving the distance
+//! from a point to a surface, negative inside. They are
+//! rendered by `SdfShape` using sphere tracing.
+
+pub mod primitives;
+pub use primitives::*;
+
+pub mod operators;
+pub use operators::*;
+
+use crate::*;
+
+pub trait Sdf: Send + Sync {
+    /// Signed distance from the object-space point `p` to the
+    /// surface. This may underestimate the true distance,
+    /// but must never overestimate it by more than the
+    /// `step` factor of the `SdfShape` allows.
+    fn distance(&self, p: &Point3) -> f64;
+}
@@ -0,0 +1,136 @@
+use crate::*;
+
+// Polynomial smooth minimum of `a` and `b` with blending
+// radius `k`.
+fn smin(a: f64, b: f64, k: f64) -> f64 {
+    if k <= 0.0 {
+        return a.min(b);
+    }
+    let h = (0.5 + 0.5 * (b - a) / k).clamp(0.0, 1.0);
+    b + (a - b) * h - k * h * (1.0 - h)
+}
+
+/// Union of two fields, with the seam blended over the given
+/// radius. A radius of 0 gives a sharp union.
+pub struct SmoothUnion {
+    pub a: Box<dyn Sdf>,
+    pub b: Box<dyn Sdf>,
+    pub k: f64,
+}
+
+impl Sdf for SmoothUnion {
+    fn distance(&self, p: &Point3) -> f64 {
+        smin(self.a.distance(p), self.b.distance(p), self.k)
+    }
+}
+
+/// The first field with the second carved out of it, with
+/// the seam blended over the given radius. A radius of 0
+/// gives a sharp difference.
+pub struct SmoothSubtraction {
+    pub a: Box<dyn Sdf>,
+    pub b: Box<dyn Sdf>,
+    pub k: f64,
+}
+
+impl Sdf for SmoothSubtraction {
+    fn distance(&self, p: &Point3) -> f64 {
+        -smin(-self.a.distance(p), self.b.distance(p), self.k)
+    }
+}
+
+/// A field moved by the given offset, for placing parts of
+/// a compound field relative to each other.
+pub struct Translate {
+    pub sdf: Box<dyn Sdf>,
+    pub offset: Vec3,
+}
+
+impl Sdf for Translate {
+    fn distance(&self, p: &Point3) -> f64 {
+        self.sdf.distance(&(*p - self.offset))
+    }
+}
+
+/// Infinite repetition of a field on a grid with the given
+/// period along each axis; a period of 0 does not repeat
+/// along that axis. The field should fit within one cell
+/// centered at the origin.
+pub struct Repeat {
+    pub sdf: Box<dyn Sdf>,
+    pub period: Vec3,
+}
+
+impl Sdf for Repeat {
+    fn distance(&self, p: &Point3) -> f64 {
+        let mut q = *p;
+        for a in 0..3 {
+            let c = self.period[a];
+            if c > 0.0 {
+                q[a] -= c * (q[a] / c).round();
+            }
+        }
+        self.sdf.distance(&q)
+    }
+}
+
+/// A field twisted around the z axis by the given angle in
+/// radians per unit of z. This distorts distances, so the
+/// `SdfShape` step should be reduced to about
+/// `1 / sqrt(1 + (rate * r)²)` for a field of radius `r`.
+pub struct Twist {
+    pub sdf: Box<dyn Sdf>,
+    pub rate: f64,
+}
+
+impl Sdf for Twist {
+    fn distance(&self, p: &Point3) -> f64 {
+        let (s, c) = (self.rate * p[Z]).sin_cos();
+        let q = Vec3::new(c * p[X] + s * p[Y], c * p[Y] - s * p[X], p[Z]);
+        self.sdf.distance(&q)
+    }
+}
+
+/// A field displaced outward by a function of position,
+/// for bumps and procedural detail. The `SdfShape` step
+/// should be reduced according to how steep the
+/// displacement is.
+pub struct Displace {
+    pub sdf: Box<dyn Sdf>,
+    pub displacement: Box<dyn Fn(&Point3) -> f64 + Send + Sync>,
+}
+
+impl Sdf for Displace {
+    fn distance(&self, p: &Point3) -> f64 {
+        self.sdf.distance(p) - (self.displacement)(p)
+    }
+}
+
+#[test]
+fn test_sdf_operators() {
+    let ball = |x: f64| -> Box<dyn Sdf> {
+        Box::new(Translate {
+            sdf: Box::new(SdfSphere { radius: 1.0 }),
+            offset: Vec3::new(x, 0.0, 0.0),
+        })
+    };
+    let p = Vec3::new(0.0, 0.0, 1.0);
+    let sharp = SmoothUnion {
+        a: ball(-1.0),
+        b: ball(1.0),
+        k: 0.0,
+    };
+    let smooth = SmoothUnion {
+        a: ball(-1.0),
+        b: ball(1.0),
+        k: 0.5,
+    };
+    // Blending fills in the crease between the balls.
+    assert!(smooth.distance(&p) < sharp.distance(&p));
+
+    let r = Repeat {
+        sdf: Box::new(SdfSphere { radius: 1.0 }),
+        period: Vec3::new(4.0, 0.0, 0.0),
+    };
+    assert!((r.distance(&Vec3::new(8.0, 0.0, 2.0)) - 1.0).abs() < TINY);
+}
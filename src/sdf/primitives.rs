@@ -0,0 +1,105 @@
+use crate::*;
+
+/// Sphere of given radius at the origin.
+pub struct SdfSphere {
+    pub radius: f64,
+}
+
+impl Sdf for SdfSphere {
+    fn distance(&self, p: &Point3) -> f64 {
+        p.mag() - self.radius
+    }
+}
+
+// Coordinate-wise absolute value.
+fn abs(p: &Vec3) -> Vec3 {
+    Vec3::new(p[X].abs(), p[Y].abs(), p[Z].abs())
+}
+
+// Coordinate-wise maximum with zero.
+fn pos(p: &Vec3) -> Vec3 {
+    Vec3::new(p[X].max(0.0), p[Y].max(0.0), p[Z].max(0.0))
+}
+
+/// Axis-aligned box centered at the origin with the given
+/// half-extents.
+pub struct SdfBox {
+    pub half: Vec3,
+}
+
+impl Sdf for SdfBox {
+    fn distance(&self, p: &Point3) -> f64 {
+        let q = abs(p) - self.half;
+        pos(&q).mag() + q[X].max(q[Y]).max(q[Z]).min(0.0)
+    }
+}
+
+/// Axis-aligned box centered at the origin with the given
+/// half-extents, with its edges and corners rounded off to
+/// the given radius. The rounding is inside the extents.
+pub struct SdfRoundBox {
+    pub half: Vec3,
+    pub radius: f64,
+}
+
+impl Sdf for SdfRoundBox {
+    fn distance(&self, p: &Point3) -> f64 {
+        let r = self.radius;
+        let q = abs(p) - self.half + Vec3::new(r, r, r);
+        pos(&q).mag() + q[X].max(q[Y]).max(q[Z]).min(0.0) - r
+    }
+}
+
+/// Line segment between two points, thickened to the given
+/// radius.
+pub struct SdfCapsule {
+    pub a: Point3,
+    pub b: Point3,
+    pub radius: f64,
+}
+
+impl Sdf for SdfCapsule {
+    fn distance(&self, p: &Point3) -> f64 {
+        let pa = *p - self.a;
+        let ba = self.b - self.a;
+        let h = (pa.dot(&ba) / ba.mag2()).clamp(0.0, 1.0);
+        (pa - ba * h).mag() - self.radius
+    }
+}
+
+/// Torus around the z axis, centered at the origin.
+pub struct SdfTorus {
+    /// Distance from the center to the middle of the tube.
+    pub major: f64,
+    /// Radius of the tube.
+    pub minor: f64,
+}
+
+impl Sdf for SdfTorus {
+    fn distance(&self, p: &Point3) -> f64 {
+        let q = Vec2::new(p.xy().mag() - self.major, p[Z]);
+        q.mag() - self.minor
+    }
+}
+
+#[test]
+fn test_sdf_primitives() {
+    let p = Vec3::new(0.0, 0.0, 3.0);
+    assert!((SdfSphere { radius: 1.0 }.distance(&p) - 2.0).abs() < TINY);
+    let b = SdfBox {
+        half: Vec3::new(1.0, 1.0, 1.0),
+    };
+    assert!((b.distance(&p) - 2.0).abs() < TINY);
+    assert!((b.distance(&Vec3::new(0.0, 0.0, 0.5)) + 0.5).abs() < TINY);
+    let c = SdfCapsule {
+        a: Vec3::new(0.0, 0.0, -1.0),
+        b: Vec3::new(0.0, 0.0, 1.0),
+        radius: 0.5,
+    };
+    assert!((c.distance(&p) - 1.5).abs() < TINY);
+    let t = SdfTorus {
+        major: 1.0,
+        minor: 0.25,
+    };
+    assert!((t.distance(&Vec3::new(1.0, 0.0, 1.0)) - 0.75).abs() < TINY);
+}
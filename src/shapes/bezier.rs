@@ -0,0 +1,327 @@
+use std::io::{self, BufRead};
+
+use crate::*;
+
+/// Number of divisions along each parameter of the
+/// tessellation used to find starting points for Newton
+/// refinement.
+const DIVISIONS: usize = 8;
+
+/// A bicubic Bezier patch given by a 4x4 net of control
+/// points. Rays are intersected with a coarse tessellation
+/// of the patch, and the hit refined onto the true surface
+/// by Newton's method. Texture coordinates are the patch
+/// parameters (u, v), each running from 0 to 1, and the
+/// normal is the cross product of the u and v tangents.
+pub struct BezierPatch {
+    /// Control points, indexed by v then u.
+    pub control: [[Point3; 4]; 4],
+    /// Tessellation vertices, indexed by v then u.
+    grid: Vec<Point3>,
+    /// Bounding box of the control net, which contains the
+    /// patch.
+    bound: Bounds,
+    /// Padded bounding box of each tessellation cell.
+    cell_bounds: Vec<Bounds>,
+}
+
+// Cubic Bernstein polynomials and their derivatives at `t`.
+fn bernstein(t: f64) -> ([f64; 4], [f64; 4]) {
+    let s = 1.0 - t;
+    (
+        [s * s * s, 3.0 * t * s * s, 3.0 * t * t * s, t * t * t],
+        [
+            -3.0 * s * s,
+            3.0 * s * (s - 2.0 * t),
+            3.0 * t * (2.0 * s - t),
+            3.0 * t * t,
+        ],
+    )
+}
+
+// Determinant of the matrix with the given columns.
+fn det(a: &Vec3, b: &Vec3, c: &Vec3) -> f64 {
+    a.dot(&b.cross_product(c))
+}
+
+impl BezierPatch {
+    pub fn new(control: [[Point3; 4]; 4]) -> Self {
+        let mut bound = Bounds::empty();
+        for p in control.iter().flatten() {
+            bound.include(p);
+        }
+        let mut patch = Self {
+            control,
+            grid: Vec::with_capacity((DIVISIONS + 1) * (DIVISIONS + 1)),
+            bound,
+            cell_bounds: Vec::with_capacity(DIVISIONS * DIVISIONS),
+        };
+        let n = DIVISIONS as f64;
+        for j in 0..=DIVISIONS {
+            for i in 0..=DIVISIONS {
+                let p = patch.eval(i as f64 / n, j as f64 / n).0;
+                patch.grid.push(p);
+            }
+        }
+
+        // Bound each cell by samples of the patch over it,
+        // padded generously to allow for the surface bulging
+        // between the samples.
+        for j in 0..DIVISIONS {
+            for i in 0..DIVISIONS {
+                let mut b = Bounds::empty();
+                for sj in 0..=4 {
+                    for si in 0..=4 {
+                        let u = (i as f64 + si as f64 / 4.0) / n;
+                        let v = (j as f64 + sj as f64 / 4.0) / n;
+                        b.include(&patch.eval(u, v).0);
+                    }
+                }
+                let pad = 0.1 * (b.max - b.min).mag() + TINY;
+                patch.cell_bounds.push(b.padded(pad));
+            }
+        }
+        patch
+    }
+
+    /// Read a set of patches in the classic Utah teapot
+    /// format: the number of patches, then for each patch
+    /// the 1-based indices of its 16 control points, then
+    /// the number of vertices, then the coordinates of each.
+    /// Numbers may be separated by commas or whitespace.
+    pub fn read_patches<R: BufRead>(r: R) -> io::Result<Vec<BezierPatch>> {
+        let bad = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+
+        let mut words = Vec::new();
+        for line in r.lines() {
+            let line = line?;
+            words.extend(
+                line.split(|c: char| c == ',' || c.is_whitespace())
+                    .filter(|w| !w.is_empty())
+                    .map(str::to_string),
+            );
+        }
+        let mut words = words.into_iter();
+        let mut next = || words.next().ok_or_else(|| bad("truncated patch file"));
+        let mut count =
+            || -> io::Result<usize> { next()?.parse().map_err(|_| bad("bad count in patch file")) };
+
+        let npatches = count()?;
+        let mut indices = Vec::with_capacity(16 * npatches);
+        for _ in 0..16 * npatches {
+            indices.push(count()?);
+        }
+        let nvertices = count()?;
+        let mut vertices = Vec::with_capacity(nvertices);
+        for _ in 0..nvertices {
+            let mut p = Vec3::default();
+            for a in 0..3 {
+                p[a] = next()?
+                    .parse()
+                    .map_err(|_| bad("bad coordinate in patch file"))?;
+            }
+            vertices.push(p);
+        }
+
+        indices
+            .chunks_exact(16)
+            .map(|ix| {
+                let mut control = [[Vec3::default(); 4]; 4];
+                for (k, &i) in ix.iter().enumerate() {
+                    if i == 0 || i > nvertices {
+                        return Err(bad("bad vertex index in patch file"));
+                    }
+                    control[k / 4][k % 4] = vertices[i - 1];
+                }
+                Ok(BezierPatch::new(control))
+            })
+            .collect()
+    }
+
+    /// Point on the patch at (u, v), with its u and v
+    /// tangents.
+    pub fn eval(&self, u: f64, v: f64) -> (Point3, Vec3, Vec3) {
+        let (bu, dbu) = bernstein(u);
+        let (bv, dbv) = bernstein(v);
+        let mut p = Vec3::default();
+        let mut pu = Vec3::default();
+        let mut pv = Vec3::default();
+        for (j, row) in self.control.iter().enumerate() {
+            for (i, c) in row.iter().enumerate() {
+                p += *c * (bu[i] * bv[j]);
+                pu += *c * (dbu[i] * bv[j]);
+                pv += *c * (bu[i] * dbv[j]);
+            }
+        }
+        (p, pu, pv)
+    }
+
+    // Object-space normal at (u, v). Where the patch is
+    // degenerate (as at the pole of the teapot lid), step a
+    // little towards the middle of the patch.
+    fn normal(&self, u: f64, v: f64) -> Normal3 {
+        let (_, pu, pv) = self.eval(u, v);
+        let n = pu.cross_product(&pv);
+        if n.mag2() > TINY * TINY {
+            return n;
+        }
+        let nudge = |t: f64| t + 1.0e-4 * (0.5 - t).signum();
+        let (_, pu, pv) = self.eval(nudge(u), nudge(v));
+        pu.cross_product(&pv)
+    }
+
+    // Refine an approximate hit (u, v, t) of the object-space
+    // ray onto the patch by Newton's method on
+    // P(u, v) - (ro + t rd) = 0.
+    fn refine(
+        &self,
+        ro: &Point3,
+        rd: &Vec3,
+        (mut u, mut v, mut t): (f64, f64, f64),
+    ) -> Option<(f64, f64, f64)> {
+        for _ in 0..12 {
+            let (p, pu, pv) = self.eval(u, v);
+            let f = p - (*ro + *rd * t);
+            if f.mag() < 1.0e-10 {
+                break;
+            }
+            let c = -*rd;
+            let d = det(&pu, &pv, &c);
+            if d.abs() < 1.0e-14 {
+                return None;
+            }
+            let r = -f;
+            // Keep the parameters on the patch, so that the
+            // iteration can slide along an edge.
+            u = (u + det(&r, &pv, &c) / d).clamp(0.0, 1.0);
+            v = (v + det(&pu, &r, &c) / d).clamp(0.0, 1.0);
+            t += det(&pu, &pv, &r) / d;
+        }
+        let p = self.eval(u, v).0;
+        ((p - (*ro + *rd * t)).mag() < 1.0e-6).then_some((u, v, t))
+    }
+}
+
+// Whether the object-space ray passes through the box
+// ahead of its origin.
+fn in_box(ro: &Point3, rd: &Vec3, b: &Bounds) -> bool {
+    b.hit(ro, rd).is_some_and(|(_, t1)| t1 > TINY)
+}
+
+impl Shape for BezierPatch {
+    fn intersect(&self, xform: &Xform, ray: &Ray) -> Option<Intersection<'_>> {
+        // Get the ray in our coordinates.
+        let mut ray = *ray;
+        ray.transform(&xform.inverse());
+        let Ray { rd, ro, .. } = ray;
+        if !in_box(&ro, &rd, &self.bound) {
+            return None;
+        }
+
+        // Refine every tessellation hit, since a coarse
+        // triangle can be hit further away than the patch
+        // is, and keep the nearest. Near silhouettes the ray
+        // can hit the patch but miss the triangles, so also
+        // start from the middle and corners of every cell whose
+        // bound the ray passes through.
+        let n = DIVISIONS;
+        let du = 1.0 / n as f64;
+        let mut best: Option<(f64, f64, f64)> = None;
+        let mut keep = |hit: Option<(f64, f64, f64)>| {
+            if let Some(hit) = hit {
+                if hit.2 > TINY && best.is_none_or(|b| hit.2 < b.2) {
+                    best = Some(hit);
+                }
+            }
+        };
+        for j in 0..n {
+            for i in 0..n {
+                if !in_box(&ro, &rd, &self.cell_bounds[j * n + i]) {
+                    continue;
+                }
+                for (a, b) in [(0.5, 0.5), (0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (1.0, 1.0)] {
+                    let (u, v) = ((i as f64 + a) * du, (j as f64 + b) * du);
+                    let t = (self.eval(u, v).0 - ro).dot(&rd);
+                    keep(self.refine(&ro, &rd, (u, v, t)));
+                }
+                let idx = [(i, j), (i + 1, j), (i + 1, j + 1), (i, j + 1)];
+                for tri in [[0, 1, 2], [0, 2, 3]] {
+                    let uv = tri.map(|k| idx[k]);
+                    let p = uv.map(|(i, j)| self.grid[j * (n + 1) + i]);
+                    let Some((t, b1, b2)) = intersect_triangle(&ro, &rd, &p) else {
+                        continue;
+                    };
+                    let b0 = 1.0 - b1 - b2;
+                    let param = |c: fn(&(usize, usize)) -> usize| {
+                        (b0 * c(&uv[0]) as f64 + b1 * c(&uv[1]) as f64 + b2 * c(&uv[2]) as f64) * du
+                    };
+                    let guess = (param(|x| x.0), param(|x| x.1), t);
+                    keep(self.refine(&ro, &rd, guess));
+                }
+            }
+        }
+
+        let (u, v, t) = best?;
+        let (p, pu, pv) = self.eval(u, v);
+        Some(Intersection {
+            t,
+            normal: xform.apply_normal(&self.normal(u, v)),
+            at: Vec2::new(u, v),
+            dpdu: xform.apply_vector(&pu),
+            dpdv: xform.apply_vector(&pv),
+            dndu: Vec3::default(),
+            dndv: Vec3::default(),
+            object: p,
+            object_dpdu: pu,
+            object_dpdv: pv,
+            texture: None,
+        })
+    }
+
+    fn bounds(&self) -> Option<Bounds> {
+        Some(self.bound)
+    }
+
+    fn complete(&mut self, _xform: &Xform) {
+        // Nothing to precompute: the ray is taken to object
+        // space and the normal back to world space at
+        // intersection time.
+    }
+}
+
+#[test]
+fn test_bezier_patch() {
+    // A single patch bulging up out of the unit square,
+    // given in teapot format.
+    let mut text = String::from("1\n");
+    let ix: Vec<String> = (1..=16).map(|i| i.to_string()).collect();
+    text += &ix.join(", ");
+    text += "\n16\n";
+    for j in 0..4 {
+        for i in 0..4 {
+            let z = if (1..3).contains(&i) && (1..3).contains(&j) {
+                1.0
+            } else {
+                0.0
+            };
+            text += &format!("{}, {}, {}\n", i as f64 / 3.0, j as f64 / 3.0, z);
+        }
+    }
+    let mut patches = BezierPatch::read_patches(text.as_bytes()).unwrap();
+    assert_eq!(1, patches.len());
+    let mut s = patches.pop().unwrap();
+    let xform = Xform::translation(&Vec3::new(0.0, 0.0, -3.0));
+    s.complete(&xform);
+
+    // The middle of the patch is at height 9/16 by the
+    // Bernstein weights, and flat there.
+    let ray = Ray::new(Vec3::new(0.5, 0.5, 0.0), Vec3::new(0.0, 0.0, -1.0));
+    let i = s.intersect(&xform, &ray).unwrap();
+    assert!((i.t - (3.0 - 9.0 / 16.0)).abs() < 1.0e-9);
+    assert!((i.normal - Vec3::new(0.0, 0.0, 1.0)).mag() < 1.0e-9);
+    assert!((i.at - Vec2::new(0.5, 0.5)).mag() < 1.0e-9);
+
+    // Off the patch.
+    let ray = Ray::new(Vec3::new(1.5, 0.5, 0.0), Vec3::new(0.0, 0.0, -1.0));
+    assert!(s.intersect(&xform, &ray).is_none());
+}
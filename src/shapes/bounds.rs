@@ -0,0 +1,83 @@
+use crate::*;
+
+/// An axis-aligned bounding box, used to cull rays that
+/// cannot hit a shape.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Bounds {
+    pub min: Point3,
+    pub max: Point3,
+}
+
+impl Bounds {
+    pub fn new(min: Point3, max: Point3) -> Self {
+        Self { min, max }
+    }
+
+    /// The box containing nothing, to grow from.
+    pub fn empty() -> Self {
+        let inf = Vec3::new(f64::INFINITY, f64::INFINITY, f64::INFINITY);
+        Self::new(inf, -inf)
+    }
+
+    /// Grow the box to contain the point.
+    pub fn include(&mut self, p: &Point3) {
+        for a in 0..3 {
+            self.min[a] = self.min[a].min(p[a]);
+            self.max[a] = self.max[a].max(p[a]);
+        }
+    }
+
+    /// The smallest box containing both boxes.
+    pub fn union(&self, b: &Bounds) -> Bounds {
+        let mut r = *self;
+        r.include(&b.min);
+        r.include(&b.max);
+        r
+    }
+
+    /// The box grown by `pad` on every side.
+    pub fn padded(&self, pad: f64) -> Bounds {
+        let pad = Vec3::new(pad, pad, pad);
+        Bounds::new(self.min - pad, self.max + pad)
+    }
+
+    pub fn centroid(&self) -> Point3 {
+        (self.min + self.max) * 0.5
+    }
+
+    /// The box containing this one after transformation.
+    pub fn transform(&self, xform: &Xform) -> Bounds {
+        let mut r = Bounds::empty();
+        for corner in 0..8 {
+            let mut p = self.min;
+            for a in 0..3 {
+                if corner & (1 << a) != 0 {
+                    p[a] = self.max[a];
+                }
+            }
+            r.include(&xform.apply_point(&p));
+        }
+        r
+    }
+
+    /// Parameters where the ray with origin `ro` and
+    /// direction `rd` enters and leaves the box, if it
+    /// passes through it.
+    pub fn hit(&self, ro: &Point3, rd: &Vec3) -> Option<(f64, f64)> {
+        let mut t0 = f64::NEG_INFINITY;
+        let mut t1 = f64::INFINITY;
+        for a in 0..3 {
+            if rd[a].abs() < TINY {
+                if ro[a] < self.min[a] || ro[a] > self.max[a] {
+                    return None;
+                }
+                continue;
+            }
+            let ta = (self.min[a] - ro[a]) / rd[a];
+            let tb = (self.max[a] - ro[a]) / rd[a];
+            t0 = t0.max(ta.min(tb));
+            t1 = t1.min(ta.max(tb));
+        }
+        (t0 <= t1).then_some((t0, t1))
+    }
+}
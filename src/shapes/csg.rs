@@ -0,0 +1,212 @@
+use crate::*;
+
+/// Constructive solid geometry operations.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CsgOp {
+    /// Inside either child.
+    Union,
+    /// Inside both children.
+    Intersection,
+    /// Inside the first child but not the second.
+    Difference,
+}
+
+impl CsgOp {
+    fn inside(self, a: bool, b: bool) -> bool {
+        match self {
+            CsgOp::Union => a || b,
+            CsgOp::Intersection => a && b,
+            CsgOp::Difference => a && !b,
+        }
+    }
+}
+
+/// Two closed shapes combined by a set operation. The
+/// children are `Thing`s whose transforms are relative to
+/// the CSG node, and whose textures are used for the parts
+/// of the surface they contribute. CSG nodes may be nested.
+///
+/// Only shapes that give their `intervals` have an inside:
+/// spheres, boxes, planes, quadrics and tori, and CSG nodes,
+/// instances and `UvMapped` wrappers of them. Any other
+/// child, such as a polygon, disk or group, adds nothing to
+/// a union and leaves nothing of an intersection.
+pub struct Csg {
+    pub op: CsgOp,
+    children: [Thing; 2],
+}
+
+impl Csg {
+    pub fn new(op: CsgOp, a: Thing, b: Thing) -> Self {
+        Self {
+            op,
+            children: [a, b],
+        }
+    }
+
+    /// Region inside either `a` or `b`.
+    pub fn union(a: Thing, b: Thing) -> Self {
+        Self::new(CsgOp::Union, a, b)
+    }
+
+    /// Region inside both `a` and `b`.
+    pub fn intersection(a: Thing, b: Thing) -> Self {
+        Self::new(CsgOp::Intersection, a, b)
+    }
+
+    /// Region inside `a` but outside `b`.
+    pub fn difference(a: Thing, b: Thing) -> Self {
+        Self::new(CsgOp::Difference, a, b)
+    }
+}
+
+impl Shape for Csg {
+    fn intersect(&self, xform: &Xform, ray: &Ray) -> Option<Intersection<'_>> {
+        self.intervals(xform, ray)
+            .into_iter()
+            .flat_map(|s| [s.enter, s.exit])
+            .find(|i| i.t > TINY && i.t.is_finite())
+    }
+
+    /// Sweep along the ray through the boundaries of both
+    /// children's spans, tracking whether the ray is inside
+    /// each, and emit a boundary wherever being inside the
+    /// combination changes.
+    fn intervals(&self, xform: &Xform, ray: &Ray) -> Vec<Span<'_>> {
+        let mut events = Vec::new();
+        for (which, c) in self.children.iter().enumerate() {
+            let texture = c.texture.as_ref();
+            let mut world = c.xform;
+            world *= xform;
+            for s in c.shape.intervals(&world, ray) {
+                for (entering, mut i) in [(true, s.enter), (false, s.exit)] {
+                    i.texture.get_or_insert(texture);
+                    events.push((which, entering, i));
+                }
+            }
+        }
+        events.sort_by(|a, b| a.2.t.total_cmp(&b.2.t));
+
+        let mut spans = Vec::new();
+        let mut in_child = [false; 2];
+        let mut inside = false;
+        let mut enter = None;
+        for (which, entering, mut i) in events {
+            in_child[which] = entering;
+            let now_inside = self.op.inside(in_child[0], in_child[1]);
+            if now_inside == inside {
+                continue;
+            }
+            inside = now_inside;
+            // The surface of a subtracted shape faces into it.
+            if which == 1 && self.op == CsgOp::Difference {
+                i.normal = -i.normal;
+                i.dndu = -i.dndu;
+                i.dndv = -i.dndv;
+            }
+            if inside {
+                enter = Some(i);
+            } else if let Some(enter) = enter.take() {
+                spans.push(Span { enter, exit: i });
+            }
+        }
+        spans
+    }
+
+    /// The bound of the union of the children, or of the
+    /// first child for a difference.
+    fn bounds(&self) -> Option<Bounds> {
+        let child = |c: &Thing| c.shape.bounds().map(|b| b.transform(&c.xform));
+        let a = child(&self.children[0])?;
+        if self.op == CsgOp::Difference {
+            return Some(a);
+        }
+        Some(a.union(&child(&self.children[1])?))
+    }
+
+    fn light_samples(&self) -> usize {
+        self.children
+            .iter()
+            .map(Thing::light_samples)
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn complete(&mut self, xform: &Xform) {
+        for c in self.children.iter_mut() {
+            let mut world = c.xform;
+            world *= xform;
+            c.shape.complete(&world);
+        }
+    }
+}
+
+#[test]
+fn test_csg_difference() {
+    let kblack = Color::new(0.0, 0.0, 0.0);
+    let texture = || Box::new(SolidTexture::new(kblack, kblack, kblack, 1.0));
+    let thing = |shape: Box<dyn Shape>, xform| Thing {
+        shape,
+        texture: texture(),
+        xform,
+    };
+
+    // A unit sphere with a bite taken out of its near side.
+    let a = thing(Box::<Sphere>::default(), Xform::identity());
+    let b = thing(
+        Box::<Cuboid>::default(),
+        Xform::translation(&Vec3::new(0.0, 0.0, -1.5)),
+    );
+    let mut s = Csg::difference(a, b);
+    let xform = Xform::translation(&Vec3::new(0.0, 0.0, 3.0));
+    s.complete(&xform);
+
+    // Straight in: enters through the box face at z = -0.5.
+    let ray = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
+    let i = s.intersect(&xform, &ray).unwrap();
+    assert!((i.t - 2.5).abs() < TINY);
+    assert!((i.normal - Vec3::new(0.0, 0.0, -1.0)).mag() < TINY);
+    assert!(std::ptr::eq(
+        i.texture.unwrap() as *const _ as *const u8,
+        s.children[1].texture.as_ref() as *const _ as *const u8,
+    ));
+
+    // From the side, behind the box: hits the sphere.
+    let ray = Ray::new(Vec3::new(0.0, 3.0, 3.0), Vec3::new(0.0, -1.0, 0.0));
+    let i = s.intersect(&xform, &ray).unwrap();
+    assert!((i.t - 2.0).abs() < TINY);
+    assert!((i.normal - Vec3::new(0.0, 1.0, 0.0)).mag() < TINY);
+
+    // Moved without completing again, it is found where the
+    // given transform puts it.
+    let xform = Xform::translation(&Vec3::new(0.0, 0.0, 5.0));
+    let ray = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
+    let i = s.intersect(&xform, &ray).unwrap();
+    assert!((i.t - 4.5).abs() < TINY);
+}
+
+#[test]
+fn test_csg_surface_child() {
+    // A disk has no inside, so a union with one is just the
+    // sphere.
+    let kblack = Color::new(0.0, 0.0, 0.0);
+    let texture = || Box::new(SolidTexture::new(kblack, kblack, kblack, 1.0));
+    let a = Thing {
+        shape: Box::<Sphere>::default(),
+        texture: texture(),
+        xform: Xform::identity(),
+    };
+    let b = Thing {
+        shape: Box::<Disk>::default(),
+        texture: texture(),
+        xform: Xform::scaling(&Vec3::new(3.0, 3.0, 3.0)),
+    };
+    let mut s = Csg::union(a, b);
+    let xform = Xform::identity();
+    s.complete(&xform);
+    let ray = Ray::new(Vec3::new(2.0, 0.0, -3.0), Vec3::new(0.0, 0.0, 1.0));
+    assert!(s.intersect(&xform, &ray).is_none());
+    let ray = Ray::new(Vec3::new(0.0, 0.0, -3.0), Vec3::new(0.0, 0.0, 1.0));
+    let i = s.intersect(&xform, &ray).unwrap();
+    assert!((i.t - 2.0).abs() < TINY);
+}
@@ -0,0 +1,179 @@
+use crate::*;
+
+/// An axis-aligned box between two corners. Each face has
+/// its own outward normal, and texture coordinates running
+/// from 0 to 1 across the face.
+pub struct Cuboid {
+    /// Corner with least coordinates.
+    pub min: Point3,
+    /// Corner with greatest coordinates.
+    pub max: Point3,
+    /// World-space outward normals of the faces, indexed by
+    /// axis: negative faces first, then positive.
+    normals: [Normal3; 6],
+}
+
+impl Cuboid {
+    pub fn new(min: Point3, max: Point3) -> Self {
+        for a in 0..3 {
+            assert!(min[a] < max[a]);
+        }
+        Self {
+            min,
+            max,
+            normals: std::array::from_fn(face_normal),
+        }
+    }
+}
+
+// Object-space outward normal of face `f`.
+fn face_normal(f: usize) -> Normal3 {
+    let mut n = Vec3::default();
+    n[f % 3] = if f < 3 { -1.0 } else { 1.0 };
+    n
+}
+
+impl Default for Cuboid {
+    /// The cube with corners at -1 and 1, the same size as
+    /// the default `Sphere`.
+    fn default() -> Self {
+        Cuboid::new(Vec3::new(-1.0, -1.0, -1.0), Vec3::new(1.0, 1.0, 1.0))
+    }
+}
+
+impl Cuboid {
+    // Slab test: intersect the object-space ray's parameter
+    // intervals within each pair of parallel faces,
+    // remembering which face bounds the interval at each
+    // end. Returns the entry and exit parameters and faces.
+    fn slabs(&self, ro: &Point3, rd: &Vec3) -> Option<((f64, usize), (f64, usize))> {
+        let mut t0 = f64::NEG_INFINITY;
+        let mut t1 = f64::INFINITY;
+        let mut f0 = 0;
+        let mut f1 = 0;
+        for a in 0..3 {
+            if rd[a].abs() < TINY {
+                // Parallel to this slab: miss unless inside it.
+                if ro[a] < self.min[a] || ro[a] > self.max[a] {
+                    return None;
+                }
+                continue;
+            }
+            let (mut ta, mut fa) = ((self.min[a] - ro[a]) / rd[a], a);
+            let (mut tb, mut fb) = ((self.max[a] - ro[a]) / rd[a], a + 3);
+            if ta > tb {
+                std::mem::swap(&mut ta, &mut tb);
+                std::mem::swap(&mut fa, &mut fb);
+            }
+            if ta > t0 {
+                t0 = ta;
+                f0 = fa;
+            }
+            if tb < t1 {
+                t1 = tb;
+                f1 = fb;
+            }
+            if t0 > t1 {
+                return None;
+            }
+        }
+        Some(((t0, f0), (t1, f1)))
+    }
+
+    // Hit information for parameter `t` along the
+    // object-space ray, on face `f`.
+    fn hit(
+        &self,
+        xform: &Xform,
+        ro: &Point3,
+        rd: &Vec3,
+        (t, f): (f64, usize),
+    ) -> Intersection<'static> {
+        // Texture coordinates come from the other two axes.
+        let i = *ro + *rd * t;
+        let a = f % 3;
+        let uv = |k: usize| (i[k] - self.min[k]) / (self.max[k] - self.min[k]);
+        let tangent = |k: usize| {
+            let mut v = Vec3::default();
+            v[k] = self.max[k] - self.min[k];
+            v
+        };
+        let (dpdu, dpdv) = (tangent((a + 1) % 3), tangent((a + 2) % 3));
+        Intersection {
+            t,
+            normal: self.normals[f],
+            at: Vec2::new(uv((a + 1) % 3), uv((a + 2) % 3)),
+            dpdu: xform.apply_vector(&dpdu),
+            dpdv: xform.apply_vector(&dpdv),
+            dndu: Vec3::default(),
+            dndv: Vec3::default(),
+            object: i,
+            object_dpdu: dpdu,
+            object_dpdv: dpdv,
+            texture: None,
+        }
+    }
+}
+
+impl Shape for Cuboid {
+    fn intersect(&self, xform: &Xform, ray: &Ray) -> Option<Intersection<'_>> {
+        // Get the ray in our coordinates.
+        let mut ray = *ray;
+        ray.transform(&xform.inverse());
+        let Ray { rd, ro, .. } = ray;
+
+        // Take the entry if it is ahead of us, otherwise the
+        // exit from inside.
+        let (enter, exit) = self.slabs(&ro, &rd)?;
+        if enter.0 > TINY {
+            Some(self.hit(xform, &ro, &rd, enter))
+        } else if exit.0 > TINY {
+            Some(self.hit(xform, &ro, &rd, exit))
+        } else {
+            None
+        }
+    }
+
+    fn intervals(&self, xform: &Xform, ray: &Ray) -> Vec<Span<'_>> {
+        let mut ray = *ray;
+        ray.transform(&xform.inverse());
+        let Ray { rd, ro, .. } = ray;
+
+        match self.slabs(&ro, &rd) {
+            Some((enter, exit)) => vec![Span {
+                enter: self.hit(xform, &ro, &rd, enter),
+                exit: self.hit(xform, &ro, &rd, exit),
+            }],
+            None => Vec::new(),
+        }
+    }
+
+    fn bounds(&self) -> Option<Bounds> {
+        Some(Bounds::new(self.min, self.max))
+    }
+
+    fn complete(&mut self, xform: &Xform) {
+        self.normals = std::array::from_fn(|f| xform.apply_normal(&face_normal(f)));
+    }
+}
+
+#[test]
+fn test_cuboid_intersect() {
+    let mut s = Cuboid::default();
+    let xform = Xform::translation(&Vec3::new(0.0, 0.0, 3.0));
+    s.complete(&xform);
+
+    let ray = Ray::new(Vec3::new(0.5, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
+    let i = s.intersect(&xform, &ray).unwrap();
+    assert!((i.t - 2.0).abs() < TINY);
+    assert!((i.normal - Vec3::new(0.0, 0.0, -1.0)).mag() < TINY);
+    assert!((i.at[X] - 0.75).abs() < TINY);
+
+    let ray = Ray::new(Vec3::new(0.0, 0.0, 3.0), Vec3::new(1.0, 0.0, 0.0));
+    let i = s.intersect(&xform, &ray).unwrap();
+    assert!((i.t - 1.0).abs() < TINY);
+    assert!((i.normal - Vec3::new(1.0, 0.0, 0.0)).mag() < TINY);
+
+    let ray = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 1.0));
+    assert!(s.intersect(&xform, &ray).is_none());
+}
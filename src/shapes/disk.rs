@@ -0,0 +1,112 @@
+use crate::*;
+
+/// A disk or annulus in the plane z = 0 centered at the
+/// origin, with normal +z. Texture coordinates are polar:
+/// x is the angle around the disk as a fraction of a turn,
+/// y runs from 0 at the inner edge to 1 at the outer.
+pub struct Disk {
+    /// Radius of the hole; 0 for a full disk.
+    pub inner: f64,
+    /// Radius of the disk.
+    pub outer: f64,
+    /// World-space normal.
+    normal: Normal3,
+}
+
+impl Disk {
+    pub fn new(inner: f64, outer: f64) -> Self {
+        assert!(0.0 <= inner && inner < outer);
+        Self {
+            inner,
+            outer,
+            normal: Vec3::new(0.0, 0.0, 1.0),
+        }
+    }
+}
+
+impl Default for Disk {
+    /// The full disk of unit radius.
+    fn default() -> Self {
+        Disk::new(0.0, 1.0)
+    }
+}
+
+impl Shape for Disk {
+    fn intersect(&self, xform: &Xform, ray: &Ray) -> Option<Intersection<'_>> {
+        // Get the ray in our coordinates.
+        let mut ray = *ray;
+        ray.transform(&xform.inverse());
+        let Ray { rd, ro, .. } = ray;
+
+        if rd[Z].abs() < TINY {
+            // The ray is parallel to the disk, so no hit.
+            return None;
+        }
+
+        let t = -ro[Z] / rd[Z];
+        if t < TINY {
+            // The ray is behind the disk, so no hit.
+            return None;
+        }
+
+        let p = ro + rd * t;
+        let i = p.xy();
+        let r2 = i.mag2();
+        if r2 > self.outer * self.outer || r2 < self.inner * self.inner {
+            // The ray misses the disk or goes through the hole.
+            return None;
+        }
+
+        let u = i[Y].atan2(i[X]) / (2.0 * PI);
+        let u = if u < 0.0 { u + 1.0 } else { u };
+        let r = r2.sqrt();
+        let v = (r - self.inner) / (self.outer - self.inner);
+        let dpdu = Vec3::new(-i[Y], i[X], 0.0) * (2.0 * PI);
+        let dpdv = if r > TINY {
+            Vec3::new(i[X], i[Y], 0.0) * ((self.outer - self.inner) / r)
+        } else {
+            Vec3::default()
+        };
+        Some(Intersection {
+            t,
+            normal: self.normal,
+            at: Vec2::new(u, v),
+            dpdu: xform.apply_vector(&dpdu),
+            dpdv: xform.apply_vector(&dpdv),
+            dndu: Vec3::default(),
+            dndv: Vec3::default(),
+            object: p,
+            object_dpdu: dpdu,
+            object_dpdv: dpdv,
+            texture: None,
+        })
+    }
+
+    fn bounds(&self) -> Option<Bounds> {
+        let r = self.outer;
+        Some(Bounds::new(Vec3::new(-r, -r, 0.0), Vec3::new(r, r, 0.0)))
+    }
+
+    fn complete(&mut self, xform: &Xform) {
+        self.normal = xform.apply_normal(&Vec3::new(0.0, 0.0, 1.0));
+    }
+}
+
+#[test]
+fn test_disk_intersect() {
+    let mut s = Disk::new(0.5, 1.0);
+    let xform = Xform::translation(&Vec3::new(0.0, 0.0, 3.0));
+    s.complete(&xform);
+
+    let ray = Ray::new(Vec3::new(0.75, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
+    let i = s.intersect(&xform, &ray).unwrap();
+    assert!((i.at[Y] - 0.5).abs() < TINY);
+
+    // Through the hole.
+    let ray = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
+    assert!(s.intersect(&xform, &ray).is_none());
+
+    // Outside the rim.
+    let ray = Ray::new(Vec3::new(1.5, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
+    assert!(s.intersect(&xform, &ray).is_none());
+}
@@ -0,0 +1,254 @@
+use crate::*;
+
+/// Most children in a leaf of the hierarchy.
+const LEAF_SIZE: usize = 2;
+
+// Node of the bounding volume hierarchy over the bounded
+// children of a group.
+enum Node {
+    /// Children `order[start..start + count]`.
+    Leaf {
+        bounds: Bounds,
+        start: usize,
+        count: usize,
+    },
+    /// Indices of the two subtrees.
+    Inner { bounds: Bounds, kids: [usize; 2] },
+}
+
+impl Node {
+    fn bounds(&self) -> &Bounds {
+        match self {
+            Node::Leaf { bounds, .. } | Node::Inner { bounds, .. } => bounds,
+        }
+    }
+}
+
+/// A collection of `Thing`s treated as one shape, with
+/// transforms relative to the group and textures used for
+/// the parts of the surface they contribute. Rays are
+/// culled against a bounding volume hierarchy over the
+/// children, so a group of many instances of shared shapes
+/// is cheap in both memory and time. Groups may be nested,
+/// and shared between instances themselves.
+pub struct Group {
+    children: Vec<Thing>,
+    /// Group-space bounds of each child, if bounded.
+    child_bounds: Vec<Option<Bounds>>,
+    /// Bounded children, in hierarchy leaf order.
+    order: Vec<usize>,
+    /// Unbounded children, tested for every ray.
+    unbounded: Vec<usize>,
+    /// Hierarchy nodes, root first.
+    nodes: Vec<Node>,
+    /// Most light samples asked for by any child.
+    light_samples: usize,
+}
+
+impl Group {
+    pub fn new(children: Vec<Thing>) -> Self {
+        let child_bounds: Vec<_> = children
+            .iter()
+            .map(|c| c.shape.bounds().map(|b| b.transform(&c.xform)))
+            .collect();
+        let (mut order, unbounded) = (0..children.len()).partition(|&i| child_bounds[i].is_some());
+        let light_samples = children.iter().map(Thing::light_samples).max().unwrap_or(0);
+        let mut group = Self {
+            children,
+            child_bounds,
+            order: Vec::new(),
+            unbounded,
+            nodes: Vec::new(),
+            light_samples,
+        };
+        if !order.is_empty() {
+            group.build(&mut order, 0);
+        }
+        group.order = order;
+        group
+    }
+
+    /// The children of the group.
+    pub fn children(&self) -> &[Thing] {
+        &self.children
+    }
+
+    fn child_bound(&self, i: usize) -> Bounds {
+        self.child_bounds[i].unwrap()
+    }
+
+    // Build the subtree over `order[start..]`, splitting at
+    // the median centroid along the widest axis, and return
+    // its node index.
+    fn build(&mut self, order: &mut [usize], start: usize) -> usize {
+        let bounds = order
+            .iter()
+            .fold(Bounds::empty(), |b, &i| b.union(&self.child_bound(i)));
+        let node = self.nodes.len();
+        if order.len() <= LEAF_SIZE {
+            self.nodes.push(Node::Leaf {
+                bounds,
+                start,
+                count: order.len(),
+            });
+            return node;
+        }
+
+        let mut centroids = Bounds::empty();
+        for &i in order.iter() {
+            centroids.include(&self.child_bound(i).centroid());
+        }
+        let extent = centroids.max - centroids.min;
+        let axis = (0..3)
+            .max_by(|&a, &b| extent[a].total_cmp(&extent[b]))
+            .unwrap();
+        let mid = order.len() / 2;
+        order.select_nth_unstable_by(mid, |&i, &j| {
+            let ci = self.child_bound(i).centroid()[axis];
+            let cj = self.child_bound(j).centroid()[axis];
+            ci.total_cmp(&cj)
+        });
+
+        // Reserve this node's slot before building below it.
+        self.nodes.push(Node::Inner {
+            bounds,
+            kids: [0, 0],
+        });
+        let (lo, hi) = order.split_at_mut(mid);
+        let left = self.build(lo, start);
+        let right = self.build(hi, start + mid);
+        self.nodes[node] = Node::Inner {
+            bounds,
+            kids: [left, right],
+        };
+        node
+    }
+
+    // Nearest hit of the group-space ray on child `c`,
+    // keeping it if it is nearer than `best`.
+    fn hit_child<'a>(&'a self, c: usize, ray: &Ray, best: &mut Option<Intersection<'a>>) {
+        let thing = &self.children[c];
+        if let Some(mut i) = thing.shape.intersect(&thing.xform, ray) {
+            if best.is_none_or(|b| i.t < b.t) {
+                i.texture.get_or_insert(thing.texture.as_ref());
+                *best = Some(i);
+            }
+        }
+    }
+}
+
+impl Shape for Group {
+    fn intersect(&self, xform: &Xform, ray: &Ray) -> Option<Intersection<'_>> {
+        // Get the ray in group coordinates. The ray
+        // transform does not renormalize the direction, so
+        // the ray parameter carries over.
+        let mut ray = *ray;
+        ray.transform(&xform.inverse());
+        let Ray { ro, rd, .. } = ray;
+
+        let mut best = None;
+        for &c in &self.unbounded {
+            self.hit_child(c, &ray, &mut best);
+        }
+
+        // Walk the hierarchy, skipping subtrees whose box
+        // lies entirely behind the ray or beyond the nearest
+        // hit so far.
+        let mut stack = Vec::new();
+        if !self.nodes.is_empty() {
+            stack.push(0);
+        }
+        while let Some(n) = stack.pop() {
+            let node = &self.nodes[n];
+            let Some((t0, t1)) = node.bounds().hit(&ro, &rd) else {
+                continue;
+            };
+            if t1 < TINY || best.is_some_and(|b: Intersection| t0 > b.t) {
+                continue;
+            }
+            match *node {
+                Node::Leaf { start, count, .. } => {
+                    for &c in &self.order[start..start + count] {
+                        self.hit_child(c, &ray, &mut best);
+                    }
+                }
+                Node::Inner { kids, .. } => stack.extend(kids),
+            }
+        }
+
+        best.map(|mut i| {
+            i.dndu = xform.apply_normal_change(&i.normal, &i.dndu);
+            i.dndv = xform.apply_normal_change(&i.normal, &i.dndv);
+            i.normal = xform.apply_normal(&i.normal);
+            i.dpdu = xform.apply_vector(&i.dpdu);
+            i.dpdv = xform.apply_vector(&i.dpdv);
+            i
+        })
+    }
+
+    fn bounds(&self) -> Option<Bounds> {
+        if !self.unbounded.is_empty() {
+            return None;
+        }
+        self.nodes.first().map(|n| *n.bounds())
+    }
+
+    fn light_samples(&self) -> usize {
+        self.light_samples
+    }
+
+    fn complete(&mut self, _xform: &Xform) {
+        // Children are completed in group coordinates, since
+        // rays are taken into group coordinates to hit them.
+        for c in self.children.iter_mut() {
+            c.shape.complete(&c.xform);
+        }
+    }
+}
+
+#[test]
+fn test_group_instances() {
+    use std::sync::Arc;
+
+    let kblack = Color::new(0.0, 0.0, 0.0);
+    let texture = || Box::new(SolidTexture::new(kblack, kblack, kblack, 1.0));
+
+    // A row of spheres sharing one shape, one of them
+    // squashed.
+    let sphere = Instance::share(Sphere::default());
+    let mut things = Vec::new();
+    for k in 0..10 {
+        let mut xform = Xform::scaling(&Vec3::new(1.0, 1.0, if k == 3 { 0.5 } else { 1.0 }));
+        xform *= &Xform::translation(&Vec3::new(3.0 * k as f64, 0.0, 0.0));
+        things.push(Thing {
+            shape: Box::new(Instance::new(sphere.clone())),
+            texture: texture(),
+            xform,
+        });
+    }
+    assert_eq!(11, Arc::strong_count(&sphere));
+    let mut g = Group::new(things);
+    let xform = Xform::translation(&Vec3::new(0.0, 0.0, 5.0));
+    g.complete(&xform);
+
+    let ray = Ray::new(Vec3::new(9.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
+    let i = g.intersect(&xform, &ray).unwrap();
+    assert!((i.t - 4.5).abs() < TINY);
+    assert!((i.normal - Vec3::new(0.0, 0.0, -1.0)).mag() < TINY);
+    // Where it hit the squashed sphere itself, for textures
+    // that move with it, rather than where in the group.
+    assert!((i.object - Vec3::new(0.0, 0.0, -1.0)).mag() < TINY);
+    assert!(std::ptr::eq(
+        i.texture.unwrap() as *const _ as *const u8,
+        g.children()[3].texture.as_ref() as *const _ as *const u8,
+    ));
+
+    // Along the row, the nearest sphere is hit first.
+    let ray = Ray::new(Vec3::new(40.0, 0.0, 5.0), Vec3::new(-1.0, 0.0, 0.0));
+    let i = g.intersect(&xform, &ray).unwrap();
+    assert!((i.t - 12.0).abs() < TINY);
+
+    // Between spheres.
+    let ray = Ray::new(Vec3::new(4.5, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
+    assert!(g.intersect(&xform, &ray).is_none());
+}
@@ -0,0 +1,263 @@
+use crate::*;
+
+/// A terrain surface z = h(x, y) over the unit square in x
+/// and y, given by heights sampled on a regular grid. Each
+/// grid cell is split into two triangles, with normals
+/// interpolated smoothly across them. Texture coordinates
+/// are x and y, running from 0 to 1 across the grid.
+pub struct Heightfield {
+    /// Number of samples in x.
+    nx: usize,
+    /// Number of samples in y.
+    ny: usize,
+    /// Heights, row-major with y varying slowest.
+    heights: Vec<f64>,
+    /// Object-space vertex normals.
+    normals: Vec<Normal3>,
+    /// Least and greatest height in each cell, for culling.
+    cell_range: Vec<(f64, f64)>,
+    /// Least and greatest height overall.
+    range: (f64, f64),
+}
+
+impl Heightfield {
+    /// Heightfield with `nx` by `ny` samples, given row by
+    /// row starting at y = 0.
+    pub fn new(nx: usize, ny: usize, heights: Vec<f64>) -> Self {
+        assert!(nx >= 2 && ny >= 2);
+        assert_eq!(nx * ny, heights.len());
+        let mut hf = Self {
+            nx,
+            ny,
+            heights,
+            normals: Vec::new(),
+            cell_range: Vec::new(),
+            range: (f64::INFINITY, f64::NEG_INFINITY),
+        };
+
+        // Vertex normals from central differences of the
+        // heights, one-sided at the edges.
+        let (dx, dy) = (1.0 / (nx - 1) as f64, 1.0 / (ny - 1) as f64);
+        for j in 0..ny {
+            for i in 0..nx {
+                let (i0, i1) = (i.saturating_sub(1), (i + 1).min(nx - 1));
+                let (j0, j1) = (j.saturating_sub(1), (j + 1).min(ny - 1));
+                let dhdx = (hf.h(i1, j) - hf.h(i0, j)) / ((i1 - i0) as f64 * dx);
+                let dhdy = (hf.h(i, j1) - hf.h(i, j0)) / ((j1 - j0) as f64 * dy);
+                hf.normals.push(Vec3::new(-dhdx, -dhdy, 1.0).unit());
+            }
+        }
+
+        for j in 0..ny - 1 {
+            for i in 0..nx - 1 {
+                let hs = [
+                    hf.h(i, j),
+                    hf.h(i + 1, j),
+                    hf.h(i, j + 1),
+                    hf.h(i + 1, j + 1),
+                ];
+                let lo = hs.iter().cloned().fold(f64::INFINITY, f64::min);
+                let hi = hs.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+                hf.cell_range.push((lo, hi));
+                hf.range = (hf.range.0.min(lo), hf.range.1.max(hi));
+            }
+        }
+        hf
+    }
+
+    /// Heightfield with `nx` by `ny` samples of the given
+    /// function over the unit square.
+    pub fn from_fn<F: Fn(f64, f64) -> f64>(nx: usize, ny: usize, f: F) -> Self {
+        let mut heights = Vec::with_capacity(nx * ny);
+        for j in 0..ny {
+            for i in 0..nx {
+                heights.push(f(i as f64 / (nx - 1) as f64, j as f64 / (ny - 1) as f64));
+            }
+        }
+        Self::new(nx, ny, heights)
+    }
+
+    /// Heightfield from the luminance of an image, scaled so
+    /// that white has height 1. The top row of the image is
+    /// at y = 1.
+    pub fn from_image(image: &ImageBuffer) -> Self {
+        let (nx, ny) = (image.xsize, image.ysize);
+        let mut heights = Vec::with_capacity(nx * ny);
+        for j in 0..ny {
+            for i in 0..nx {
+                let [r, g, b] = image.get(i, ny - 1 - j);
+                let y = 0.2126 * r as f64 + 0.7152 * g as f64 + 0.0722 * b as f64;
+                heights.push(y / 255.0);
+            }
+        }
+        Self::new(nx, ny, heights)
+    }
+
+    fn h(&self, i: usize, j: usize) -> f64 {
+        self.heights[j * self.nx + i]
+    }
+
+    fn vertex(&self, i: usize, j: usize) -> Point3 {
+        Vec3::new(
+            i as f64 / (self.nx - 1) as f64,
+            j as f64 / (self.ny - 1) as f64,
+            self.h(i, j),
+        )
+    }
+
+    // Intersect the object-space ray with the two triangles
+    // of cell (i, j), returning the nearest hit beyond `tmin`.
+    fn hit_cell(
+        &self,
+        ro: &Point3,
+        rd: &Vec3,
+        i: usize,
+        j: usize,
+        tmin: f64,
+    ) -> Option<(f64, Normal3)> {
+        let idx = [(i, j), (i + 1, j), (i + 1, j + 1), (i, j + 1)];
+        let mut best: Option<(f64, Normal3)> = None;
+        for tri in [[0, 1, 2], [0, 2, 3]] {
+            let v = tri.map(|k| idx[k]);
+            let p = v.map(|(i, j)| self.vertex(i, j));
+            if let Some((t, b1, b2)) = intersect_triangle(ro, rd, &p) {
+                if t > tmin && best.is_none_or(|(bt, _)| t < bt) {
+                    let n = |k: usize| self.normals[v[k].1 * self.nx + v[k].0];
+                    let normal = n(0) * (1.0 - b1 - b2) + n(1) * b1 + n(2) * b2;
+                    best = Some((t, normal));
+                }
+            }
+        }
+        best
+    }
+}
+
+impl Shape for Heightfield {
+    fn intersect(&self, xform: &Xform, ray: &Ray) -> Option<Intersection<'_>> {
+        // Get the ray in our coordinates.
+        let mut ray = *ray;
+        ray.transform(&xform.inverse());
+        let Ray { rd, ro, .. } = ray;
+
+        // Clip the ray to the bounding box of the terrain.
+        let lo = [0.0, 0.0, self.range.0];
+        let hi = [1.0, 1.0, self.range.1];
+        let mut t0 = TINY;
+        let mut t1 = f64::INFINITY;
+        for a in 0..3 {
+            if rd[a].abs() < TINY {
+                if ro[a] < lo[a] || ro[a] > hi[a] {
+                    return None;
+                }
+                continue;
+            }
+            let ta = (lo[a] - ro[a]) / rd[a];
+            let tb = (hi[a] - ro[a]) / rd[a];
+            t0 = t0.max(ta.min(tb));
+            t1 = t1.min(ta.max(tb));
+        }
+        if t0 > t1 {
+            return None;
+        }
+
+        // Walk the grid cells the ray passes over, from
+        // entry to exit (Amanatides and Woo).
+        let ncx = self.nx - 1;
+        let ncy = self.ny - 1;
+        let size = [ncx as f64, ncy as f64];
+        let ncells = [ncx as isize, ncy as isize];
+        let p = ro + rd * t0;
+        let mut cell = [0isize; 2];
+        let mut step = [0isize; 2];
+        let mut t_next = [f64::INFINITY; 2];
+        let mut t_delta = [f64::INFINITY; 2];
+        for a in 0..2 {
+            cell[a] = ((p[a] * size[a]).floor() as isize).clamp(0, ncells[a] - 1);
+            if rd[a].abs() >= TINY {
+                step[a] = if rd[a] > 0.0 { 1 } else { -1 };
+                let edge = (cell[a] + (step[a] + 1) / 2) as f64 / size[a];
+                t_next[a] = (edge - ro[a]) / rd[a];
+                t_delta[a] = 1.0 / (size[a] * rd[a].abs());
+            }
+        }
+
+        let mut t_enter = t0;
+        loop {
+            let t_exit = t_next[0].min(t_next[1]).min(t1);
+            let (i, j) = (cell[0] as usize, cell[1] as usize);
+
+            // Skip the cell unless the ray's height over it
+            // overlaps the cell's heights.
+            let (lo, hi) = self.cell_range[j * ncx + i];
+            let z0 = ro[Z] + rd[Z] * t_enter;
+            let z1 = ro[Z] + rd[Z] * t_exit;
+            if z0.min(z1) <= hi + TINY && z0.max(z1) >= lo - TINY {
+                if let Some((t, n)) = self.hit_cell(&ro, &rd, i, j, TINY) {
+                    let hp = ro + rd * t;
+                    let (dpdu, dpdv) = UvMap::Planar.tangents(&hp, &n);
+                    return Some(Intersection {
+                        t,
+                        normal: xform.apply_normal(&n),
+                        at: hp.xy(),
+                        dpdu: xform.apply_vector(&dpdu),
+                        dpdv: xform.apply_vector(&dpdv),
+                        dndu: Vec3::default(),
+                        dndv: Vec3::default(),
+                        object: hp,
+                        object_dpdu: dpdu,
+                        object_dpdv: dpdv,
+                        texture: None,
+                    });
+                }
+            }
+
+            if t_exit >= t1 {
+                return None;
+            }
+            let a = if t_next[0] < t_next[1] { 0 } else { 1 };
+            cell[a] += step[a];
+            if cell[a] < 0 || cell[a] >= ncells[a] {
+                return None;
+            }
+            t_enter = t_next[a];
+            t_next[a] += t_delta[a];
+        }
+    }
+
+    fn bounds(&self) -> Option<Bounds> {
+        let (lo, hi) = self.range;
+        Some(Bounds::new(
+            Vec3::new(0.0, 0.0, lo),
+            Vec3::new(1.0, 1.0, hi),
+        ))
+    }
+
+    fn complete(&mut self, _xform: &Xform) {
+        // Nothing to precompute: the ray is taken to object
+        // space and the normal back to world space at
+        // intersection time.
+    }
+}
+
+#[test]
+fn test_heightfield_intersect() {
+    // A ramp rising along x.
+    let mut s = Heightfield::from_fn(9, 5, |x, _| 0.5 * x);
+    let xform = Xform::translation(&Vec3::new(0.0, 0.0, -3.0));
+    s.complete(&xform);
+
+    let ray = Ray::new(Vec3::new(0.5, 0.5, 0.0), Vec3::new(0.0, 0.0, -1.0));
+    let i = s.intersect(&xform, &ray).unwrap();
+    assert!((i.t - 2.75).abs() < TINY);
+    assert!((i.normal - Vec3::new(-0.5, 0.0, 1.0).unit()).mag() < TINY);
+    assert!((i.at[X] - 0.5).abs() < TINY);
+
+    // Skimming along the ramp from the low side.
+    let ray = Ray::new(Vec3::new(-1.0, 0.3, -2.5), Vec3::new(1.0, 0.0, 0.0));
+    let i = s.intersect(&xform, &ray).unwrap();
+    assert!((i.t - 2.0).abs() < TINY);
+
+    // Off the edge.
+    let ray = Ray::new(Vec3::new(1.5, 0.5, 0.0), Vec3::new(0.0, 0.0, -1.0));
+    assert!(s.intersect(&xform, &ray).is_none());
+}
@@ -0,0 +1,77 @@
+use std::sync::Arc;
+
+use crate::*;
+
+/// A placement of a shared shape. Any number of `Thing`s
+/// may hold instances of the same shape, each with its own
+/// transform and texture, without copying the shape.
+///
+/// The shared shape is completed once with the identity
+/// transform; each instance takes rays into the shape's
+/// coordinates itself, and brings normals back out.
+#[derive(Clone)]
+pub struct Instance {
+    pub shape: Arc<dyn Shape>,
+}
+
+impl Instance {
+    /// Instance of a shape that has been completed with the
+    /// identity transform, as by `Instance::share()`.
+    pub fn new(shape: Arc<dyn Shape>) -> Self {
+        Self { shape }
+    }
+
+    /// Complete the shape with the identity transform and
+    /// wrap it for sharing between instances.
+    pub fn share<S: Shape + 'static>(mut shape: S) -> Arc<dyn Shape> {
+        shape.complete(&Xform::identity());
+        Arc::new(shape)
+    }
+}
+
+// Take a hit on the shared shape back out to world space.
+fn place<'a>(xform: &Xform, mut i: Intersection<'a>) -> Intersection<'a> {
+    i.dndu = xform.apply_normal_change(&i.normal, &i.dndu);
+    i.dndv = xform.apply_normal_change(&i.normal, &i.dndv);
+    i.normal = xform.apply_normal(&i.normal);
+    i.dpdu = xform.apply_vector(&i.dpdu);
+    i.dpdv = xform.apply_vector(&i.dpdv);
+    i
+}
+
+impl Shape for Instance {
+    fn intersect(&self, xform: &Xform, ray: &Ray) -> Option<Intersection<'_>> {
+        // The ray transform does not renormalize the
+        // direction, so the ray parameter carries over.
+        let mut ray = *ray;
+        ray.transform(&xform.inverse());
+        let identity = Xform::identity();
+        let i = self.shape.intersect(&identity, &ray)?;
+        Some(place(xform, i))
+    }
+
+    fn intervals(&self, xform: &Xform, ray: &Ray) -> Vec<Span<'_>> {
+        let mut ray = *ray;
+        ray.transform(&xform.inverse());
+        self.shape
+            .intervals(&Xform::identity(), &ray)
+            .into_iter()
+            .map(|s| Span {
+                enter: place(xform, s.enter),
+                exit: place(xform, s.exit),
+            })
+            .collect()
+    }
+
+    fn bounds(&self) -> Option<Bounds> {
+        self.shape.bounds()
+    }
+
+    fn light_samples(&self) -> usize {
+        self.shape.light_samples()
+    }
+
+    fn complete(&mut self, _xform: &Xform) {
+        // The shared shape was completed when it was shared.
+    }
+}
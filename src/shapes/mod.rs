@@ -1,22 +1,121 @@
+pub mod bounds;
+pub use bounds::*;
+
 pub mod sphere;
 pub use sphere::*;
 
 pub mod poly;
 pub use poly::*;
 
+pub mod plane;
+pub use plane::*;
+
+pub mod cuboid;
+pub use cuboid::*;
+
+pub mod disk;
+pub use disk::*;
+
+pub mod quadric;
+pub use quadric::*;
+
+pub mod torus;
+pub use torus::*;
+
+pub mod csg;
+pub use csg::*;
+
+pub mod sdf_shape;
+pub use sdf_shape::*;
+
+pub mod triangle;
+pub use triangle::*;
+
+pub mod heightfield;
+pub use heightfield::*;
+
+pub mod bezier;
+pub use bezier::*;
+
+pub mod instance;
+pub use instance::*;
+
+pub mod group;
+pub use group::*;
+
+pub mod uv_map;
+pub use uv_map::*;
+
 use crate::*;
 
-pub struct Intersection {
+/// Information about a ray hitting a surface.
+#[derive(Clone, Copy)]
+pub struct Intersection<'a> {
     pub t: f64,
-    pub normal: Point,
-    pub at: Point,
+    pub normal: Normal3,
+    pub at: Vec2,
+    /// World-space rates of change of the hit point with the
+    /// texture coordinates u and v, tangent to the surface,
+    /// for orienting bump and normal maps. Zero where the
+    /// shape cannot say.
+    pub dpdu: Vec3,
+    pub dpdv: Vec3,
+    /// World-space rates of change of the unit normal with
+    /// the texture coordinates, for following the spread of
+    /// reflected rays off a curved surface. Zero where the
+    /// surface is flat or the shape cannot say.
+    pub dndu: Vec3,
+    pub dndv: Vec3,
+    /// The hit point and the tangents along the texture
+    /// coordinates in the coordinates of the shape hit,
+    /// before any transform: of the part hit, for a compound
+    /// shape. For solid textures that should move with it.
+    pub object: Point3,
+    pub object_dpdu: Vec3,
+    pub object_dpdv: Vec3,
+    /// Texture of the part of a compound shape that was hit,
+    /// overriding the texture of the `Thing`.
+    pub texture: Option<&'a dyn Texture>,
+}
+
+/// A stretch of a ray inside a closed shape, with the hits
+/// where the ray enters and leaves it. Either end may be at
+/// infinity for an unbounded shape.
+#[derive(Clone, Copy)]
+pub struct Span<'a> {
+    pub enter: Intersection<'a>,
+    pub exit: Intersection<'a>,
 }
 
 pub trait Shape: Send + Sync {
     /// Given a ray specified by origin and unit direction,
     /// return the location and unit direction of intersection
     /// in texture coordinates, if intersection happens.
-    fn intersect(&self, xform: &Xform, ray: &Ray) -> Option<Intersection>;
+    fn intersect(&self, xform: &Xform, ray: &Ray) -> Option<Intersection<'_>>;
+
+    /// Given a ray, return all the stretches of it that are
+    /// inside the shape, in increasing order and including
+    /// those behind the ray origin. This is what constructive
+    /// solid geometry is built from. Shapes that do not
+    /// enclose a volume have no inside.
+    fn intervals(&self, _xform: &Xform, _ray: &Ray) -> Vec<Span<'_>> {
+        Vec::new()
+    }
+
+    /// Bounding box of the shape in its own coordinates,
+    /// before transformation, or `None` if it is unbounded.
+    /// Used to skip shapes that a ray cannot hit.
+    fn bounds(&self) -> Option<Bounds> {
+        None
+    }
+
+    /// For a compound shape whose parts have textures of
+    /// their own, the most points any of those textures
+    /// asks to be sampled as a light source, as by
+    /// `Texture::light_samples()`. By default 0.
+    fn light_samples(&self) -> usize {
+        0
+    }
 
     /// Inverse-transform the object's data according to the
     /// current transformation so that it gets hit.
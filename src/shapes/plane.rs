@@ -0,0 +1,117 @@
+use crate::*;
+
+/// The infinite plane z = 0, with normal +z. Texture
+/// coordinates are object-space x and y, as with `Poly`.
+/// For constructive solid geometry, the plane bounds the
+/// half-space z ≤ 0.
+pub struct Plane {
+    /// World-space normal.
+    normal: Normal3,
+}
+
+impl Default for Plane {
+    fn default() -> Self {
+        Plane {
+            normal: Vec3::new(0.0, 0.0, 1.0),
+        }
+    }
+}
+
+impl Shape for Plane {
+    fn intersect(&self, xform: &Xform, ray: &Ray) -> Option<Intersection<'_>> {
+        // Get the ray in our coordinates.
+        let mut ray = *ray;
+        ray.transform(&xform.inverse());
+        let Ray { rd, ro, .. } = ray;
+
+        if rd[Z].abs() < TINY {
+            // The ray is parallel to the plane, so no hit.
+            return None;
+        }
+
+        let t = -ro[Z] / rd[Z];
+        if t < TINY {
+            // The ray is behind the plane, so no hit.
+            return None;
+        }
+
+        let p = ro + rd * t;
+        Some(Intersection {
+            t,
+            normal: self.normal,
+            at: p.xy(),
+            dpdu: xform.apply_vector(&Vec3::new(1.0, 0.0, 0.0)),
+            dpdv: xform.apply_vector(&Vec3::new(0.0, 1.0, 0.0)),
+            dndu: Vec3::default(),
+            dndv: Vec3::default(),
+            object: p,
+            object_dpdu: Vec3::new(1.0, 0.0, 0.0),
+            object_dpdv: Vec3::new(0.0, 1.0, 0.0),
+            texture: None,
+        })
+    }
+
+    fn intervals(&self, xform: &Xform, ray: &Ray) -> Vec<Span<'_>> {
+        let mut ray = *ray;
+        ray.transform(&xform.inverse());
+        let Ray { rd, ro, .. } = ray;
+
+        let at_t = |t: f64| {
+            let p = if t.is_finite() {
+                ro + rd * t
+            } else {
+                Vec3::default()
+            };
+            Intersection {
+                t,
+                normal: self.normal,
+                at: p.xy(),
+                dpdu: xform.apply_vector(&Vec3::new(1.0, 0.0, 0.0)),
+                dpdv: xform.apply_vector(&Vec3::new(0.0, 1.0, 0.0)),
+                dndu: Vec3::default(),
+                dndv: Vec3::default(),
+                object: p,
+                object_dpdu: Vec3::new(1.0, 0.0, 0.0),
+                object_dpdv: Vec3::new(0.0, 1.0, 0.0),
+                texture: None,
+            }
+        };
+        let (enter, exit) = if rd[Z].abs() < TINY {
+            if ro[Z] > 0.0 {
+                return Vec::new();
+            }
+            (f64::NEG_INFINITY, f64::INFINITY)
+        } else {
+            let t = -ro[Z] / rd[Z];
+            if rd[Z] > 0.0 {
+                (f64::NEG_INFINITY, t)
+            } else {
+                (t, f64::INFINITY)
+            }
+        };
+        vec![Span {
+            enter: at_t(enter),
+            exit: at_t(exit),
+        }]
+    }
+
+    fn complete(&mut self, xform: &Xform) {
+        self.normal = xform.apply_normal(&Vec3::new(0.0, 0.0, 1.0));
+    }
+}
+
+#[test]
+fn test_plane_intersect() {
+    let mut s = Plane::default();
+    let mut xform = Xform::rotation_x(PI / 2.0);
+    xform *= &Xform::translation(&Vec3::new(0.0, -1.0, 0.0));
+    s.complete(&xform);
+    assert!((s.normal - Vec3::new(0.0, -1.0, 0.0)).mag() < TINY);
+
+    let ray = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, -1.0, 1.0));
+    let i = s.intersect(&xform, &ray).unwrap();
+    assert!((ray.at(i.t)[Y] + 1.0).abs() < TINY);
+
+    let ray = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 1.0));
+    assert!(s.intersect(&xform, &ray).is_none());
+}
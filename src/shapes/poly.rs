@@ -1,23 +1,172 @@
+use std::ops::ControlFlow;
+
 use crate::*;
 
+/// Rule deciding which points are inside a polygon whose
+/// boundary crosses itself or has holes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FillRule {
+    /// Inside if a ray from the point crosses the boundary
+    /// an odd number of times. Holes may be wound either
+    /// way.
+    EvenOdd,
+    /// Inside if the boundary winds around the point at all.
+    /// Holes must be wound opposite to the outer boundary.
+    NonZero,
+}
+
+/// How texture coordinates are assigned across a polygon.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PolyUv {
+    /// Object-space x and y.
+    Object,
+    /// 0 to 1 across the bounding box of the outer boundary.
+    Bounds,
+    /// Given coordinates at each vertex of the outer
+    /// boundary, interpolated smoothly between them by mean
+    /// value coordinates.
+    Vertices(Vec<Vec2>),
+}
+
+/// Errors in constructing a polygon.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PolyError {
+    /// A boundary has fewer than three points.
+    TooFewPoints,
+    /// The outer boundary encloses no area.
+    Degenerate,
+    /// The points do not all lie in one plane.
+    NotPlanar,
+}
+
+impl std::fmt::Display for PolyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            PolyError::TooFewPoints => write!(f, "polygon boundary with fewer than three points"),
+            PolyError::Degenerate => write!(f, "polygon with no area"),
+            PolyError::NotPlanar => write!(f, "polygon points not in one plane"),
+        }
+    }
+}
+
+impl std::error::Error for PolyError {}
+
+/// A polygon in the plane z = 0, possibly concave and with
+/// holes. The normal is +z.
 pub struct Poly {
-    pub p: Vec<Point>,
+    /// Outer boundary.
+    pub p: Vec<Vec2>,
+    /// Boundaries of holes.
+    pub holes: Vec<Vec<Vec2>>,
+    pub rule: FillRule,
+    pub uv: PolyUv,
     pub dp: usize,
-    pub cnormal: Point,
+    pub cnormal: Normal3,
+    // Bounding box of the outer boundary, and the tangents
+    // if they are the same everywhere, found by `complete`.
+    extent: Bounds,
+    flat_tangents: Option<(Vec3, Vec3)>,
 }
 
 impl Poly {
-    pub fn new<const N: usize>(points: [Point; N]) -> Self {
-        Self {
-            p: points.into_iter().collect(),
+    pub fn new<const N: usize>(points: [Vec2; N]) -> Self {
+        Self::with_holes(points.into_iter().collect(), Vec::new())
+    }
+
+    /// Polygon with the given outer boundary and holes,
+    /// filled by the even-odd rule.
+    pub fn with_holes(p: Vec<Vec2>, holes: Vec<Vec<Vec2>>) -> Self {
+        let mut poly = Self {
+            p,
+            holes,
+            rule: FillRule::EvenOdd,
+            uv: PolyUv::Object,
             dp: 0,
-            cnormal: Point::new([0.0, 0.0, 1.0]),
+            cnormal: Vec3::new(0.0, 0.0, 1.0),
+            extent: Bounds::empty(),
+            flat_tangents: None,
+        };
+        poly.prepare();
+        poly
+    }
+
+    // Find the bounding box and tangents for the current
+    // boundary and texture mapping.
+    fn prepare(&mut self) {
+        let mut b = Bounds::empty();
+        for p in &self.p {
+            b.include(&Vec3::new(p[X], p[Y], 0.0));
+        }
+        self.extent = b;
+        self.flat_tangents = match self.uv {
+            PolyUv::Object | PolyUv::Bounds => Some(self.tangents(&Vec2::default())),
+            PolyUv::Vertices(_) => None,
+        };
+    }
+
+    /// Polygon from boundaries given in 3-space, which must
+    /// lie in a common plane. Returns the polygon in its
+    /// own plane, with the transform that puts it back where
+    /// the points were: the first point is the origin, and
+    /// the first edge lies along x. The normal is on the
+    /// side from which the outer boundary runs
+    /// counterclockwise.
+    pub fn from_points(
+        outer: &[Point3],
+        holes: &[Vec<Point3>],
+    ) -> Result<(Self, Xform), PolyError> {
+        if outer.len() < 3 || holes.iter().any(|h| h.len() < 3) {
+            return Err(PolyError::TooFewPoints);
+        }
+
+        // Newell's method gives the normal of the best-fit
+        // plane, with length twice the enclosed area.
+        let mut n = Vec3::default();
+        let mut size = 0.0f64;
+        for (i, a) in outer.iter().enumerate() {
+            let b = outer[(i + 1) % outer.len()];
+            n += a.cross_product(&b);
+            size = size.max((b - outer[0]).mag());
+        }
+        if n.mag() <= TINY * size * size {
+            return Err(PolyError::Degenerate);
         }
+        let z = n.unit();
+        let o = outer[0];
+        let mut x = outer[1] - o;
+        x -= z * x.dot(&z);
+        let x = x.unit();
+        let y = z.cross_product(&x);
+
+        let tolerance = 1.0e-9 * size.max(1.0);
+        let flatten = |ring: &[Point3]| -> Result<Vec<Vec2>, PolyError> {
+            ring.iter()
+                .map(|p| {
+                    let d = *p - o;
+                    if d.dot(&z).abs() > tolerance {
+                        return Err(PolyError::NotPlanar);
+                    }
+                    Ok(Vec2::new(d.dot(&x), d.dot(&y)))
+                })
+                .collect()
+        };
+        let p = flatten(outer)?;
+        let holes = holes.iter().map(|h| flatten(h)).collect::<Result<_, _>>()?;
+
+        let mut m = XFMatrix::default();
+        for (c, v) in [x, y, z, o].iter().enumerate() {
+            for r in 0..3 {
+                m[r][c] = v[r];
+            }
+        }
+        m[3][3] = 1.0;
+        let xform = Xform::from_matrix(m).map_err(|_| PolyError::Degenerate)?;
+        Ok((Self::with_holes(p, holes), xform))
     }
 }
 
 // Find which side of the line `origin`→`v1` `v2` is on.
-fn side(origin: &Point, v1: &Point, v2: &Point) -> f64 {
+fn side(origin: &Vec2, v1: &Vec2, v2: &Vec2) -> f64 {
     let c1 = (v1[X] - origin[X]) * (v2[Y] - origin[Y]);
     let c2 = (v2[X] - origin[X]) * (v1[Y] - origin[Y]);
     (c1 - c2).signum()
@@ -25,53 +174,129 @@ fn side(origin: &Point, v1: &Point, v2: &Point) -> f64 {
 
 #[test]
 fn test_side() {
-    let origin = Point::new([0.0, 0.0]);
-    let v1 = Point::new([1.0, 1.0]);
-    let x = Point::new([1.0, 0.9]);
-    let y = Point::new([0.9, 1.0]);
+    let origin = Vec2::new(0.0, 0.0);
+    let v1 = Vec2::new(1.0, 1.0);
+    let x = Vec2::new(1.0, 0.9);
+    let y = Vec2::new(0.9, 1.0);
     assert_eq!(-1.0, side(&origin, &v1, &x));
     assert_eq!(1.0, side(&origin, &v1, &y));
 }
 
 impl Poly {
-    pub fn contains(&self, v: &Point) -> bool {
-        // Find the two edges of the convex polygon that
-        // surround `v` in the y direction.
-        let pn = self.p.len();
-        let edges: Vec<_> = (0..pn)
-            .filter_map(|i| {
-                let mut pp = [&self.p[i], &self.p[(i + 1) % pn]];
-                if pp[0][Y] > pp[1][Y] {
-                    pp.swap(0, 1);
+    /// Whether the point is inside the polygon by its fill
+    /// rule.
+    pub fn contains(&self, v: &Vec2) -> bool {
+        let mut winding = 0;
+        let mut crossings = 0;
+        for ring in std::iter::once(&self.p).chain(self.holes.iter()) {
+            let pn = ring.len();
+            for i in 0..pn {
+                let (a, b) = (&ring[i], &ring[(i + 1) % pn]);
+                // Count edges crossing the horizontal ray
+                // from `v` towards +x. Edges include their
+                // lower end but not their upper, so that a
+                // vertex at the height of `v` is counted
+                // once.
+                if (a[Y] <= v[Y]) == (b[Y] <= v[Y]) {
+                    continue;
                 }
-                if pp[0][Y] < v[Y] && v[Y] < pp[1][Y] {
-                    Some(pp)
-                } else {
-                    None
+                let s = side(a, b, v);
+                if a[Y] <= v[Y] && s > 0.0 {
+                    winding += 1;
+                    crossings += 1;
+                } else if b[Y] <= v[Y] && s < 0.0 {
+                    winding -= 1;
+                    crossings += 1;
                 }
-            })
-            .collect();
-        if edges.len() != 2 {
-            return false;
+            }
         }
+        match self.rule {
+            FillRule::EvenOdd => crossings % 2 == 1,
+            FillRule::NonZero => winding != 0,
+        }
+    }
 
-        // What side of each edge is `v` on?
-        let s0 = side(edges[0][0], edges[0][1], v);
-        let s1 = side(edges[1][0], edges[1][1], v);
+    // Texture coordinates of a point in the polygon.
+    fn uv(&self, v: &Vec2) -> Vec2 {
+        match &self.uv {
+            PolyUv::Object => *v,
+            PolyUv::Bounds => {
+                let b = &self.extent;
+                let size = b.max - b.min;
+                Vec2::new((v[X] - b.min[X]) / size[X], (v[Y] - b.min[Y]) / size[Y])
+            }
+            PolyUv::Vertices(uvs) => mean_value(&self.p, uvs, v),
+        }
+    }
 
-        // Iff `v` is on opposite sides of each edge,
-        // `v` is contained in this polygon.
-        s0 != s1
+    // Object-space tangents dP/du and dP/dv at a point in
+    // the polygon, from the inverse of the Jacobian of the
+    // texture coordinates, found by central differences.
+    fn tangents(&self, v: &Vec2) -> (Vec3, Vec3) {
+        let h = 1.0e-5 * (self.extent.max - self.extent.min).mag().max(TINY);
+        let d = |dv: Vec2| (self.uv(&(*v + dv)) - self.uv(&(*v - dv))) * (0.5 / h);
+        let (ux, uy) = (d(Vec2::new(h, 0.0)), d(Vec2::new(0.0, h)));
+        let det = ux[X] * uy[Y] - uy[X] * ux[Y];
+        if det.abs() < TINY {
+            return (Vec3::default(), Vec3::default());
+        }
+        let dpdu = Vec3::new(uy[Y], -ux[Y], 0.0) * (1.0 / det);
+        let dpdv = Vec3::new(-uy[X], ux[X], 0.0) * (1.0 / det);
+        (dpdu, dpdv)
+    }
+}
+
+// Interpolate the values `f` at the vertices `p` of a
+// polygon to the point `v`, by the mean value coordinates
+// of Hormann and Floater, which are smooth and well defined
+// for concave polygons.
+fn mean_value(p: &[Vec2], f: &[Vec2], v: &Vec2) -> Vec2 {
+    let n = p.len();
+    // The tangent of half the angle that the edge from
+    // vertex `i` subtends at `v`, or the interpolated value
+    // if `v` lies on the edge.
+    let edge = |i: usize| -> ControlFlow<Vec2, f64> {
+        let j = (i + 1) % n;
+        let (si, sj) = (p[i] - *v, p[j] - *v);
+        let (ri, rj) = (si.mag(), sj.mag());
+        if ri < TINY {
+            return ControlFlow::Break(f[i]);
+        }
+        let area = si[X] * sj[Y] - si[Y] * sj[X];
+        let dot = si.dot(&sj);
+        if area.abs() < TINY && dot < 0.0 {
+            // On the edge: interpolate along it.
+            let a = ri / (ri + rj);
+            return ControlFlow::Break(f[i] * (1.0 - a) + f[j] * a);
+        }
+        ControlFlow::Continue(area / (ri * rj + dot))
+    };
+    let mut prev = match edge(n - 1) {
+        ControlFlow::Continue(t) => t,
+        ControlFlow::Break(value) => return value,
+    };
+    let mut sum = Vec2::default();
+    let mut total = 0.0;
+    for i in 0..n {
+        let t = match edge(i) {
+            ControlFlow::Continue(t) => t,
+            ControlFlow::Break(value) => return value,
+        };
+        let w = (prev + t) / (p[i] - *v).mag();
+        sum += f[i] * w;
+        total += w;
+        prev = t;
     }
+    sum * (1.0 / total)
 }
 
 impl Shape for Poly {
-    fn intersect(&self, xform: &Xform, ray: &Ray) -> Option<Intersection> {
+    fn intersect(&self, xform: &Xform, ray: &Ray) -> Option<Intersection<'_>> {
         // Get the ray in our coordinates.
-        let mut ray = ray.clone();
+        let mut ray = *ray;
         let toi = xform.inverse();
         ray.transform(&toi);
-        let Ray { rd, ro } = ray;
+        let Ray { rd, ro, .. } = ray;
 
         let b = rd[Z];
         if b.abs() < TINY {
@@ -85,13 +310,23 @@ impl Shape for Poly {
             return None;
         }
 
-        let i = ro + rd * t;
+        let p = ro + rd * t;
+        let i = p.xy();
         if self.contains(&i) {
             // Return the hit information.
+            let (dpdu, dpdv) = self.flat_tangents.unwrap_or_else(|| self.tangents(&i));
             Some(Intersection {
-                normal: self.cnormal.clone(),
-                at: Point::new([i[X], i[Y]]),
+                normal: self.cnormal,
+                at: self.uv(&i),
                 t,
+                dpdu: xform.apply_vector(&dpdu),
+                dpdv: xform.apply_vector(&dpdv),
+                dndu: Vec3::default(),
+                dndv: Vec3::default(),
+                object: p,
+                object_dpdu: dpdu,
+                object_dpdv: dpdv,
+                texture: None,
             })
         } else {
             // The ray misses the polygon, so no hit.
@@ -99,37 +334,118 @@ impl Shape for Poly {
         }
     }
 
+    fn bounds(&self) -> Option<Bounds> {
+        let mut b = Bounds::empty();
+        for p in &self.p {
+            b.include(&Vec3::new(p[X], p[Y], 0.0));
+        }
+        Some(b)
+    }
+
+    /// Also finds the bounding box and tangents used at each
+    /// hit, so the boundary and texture mapping should be set
+    /// first.
     fn complete(&mut self, xform: &Xform) {
-        let mut o = Point::new([0.0, 0.0, 0.0]);
-        let mut r = Point::new([0.0, 0.0, 1.0]);
-        let toi = xform.inverse();
-        r.transform(&toi);
-        o.transform(&toi);
-        r -= o;
-        r.unitize();
-        self.cnormal = r;
+        self.prepare();
+        self.cnormal = xform.apply_normal(&Vec3::new(0.0, 0.0, 1.0));
     }
 }
 
 #[test]
 fn test_poly_intersect() {
     let points = [
-        Point::new([-0.5, -0.5]),
-        Point::new([-0.5, 0.5]),
-        Point::new([0.5, 0.5]),
-        Point::new([0.5, -0.5]),
+        Vec2::new(-0.5, -0.5),
+        Vec2::new(-0.5, 0.5),
+        Vec2::new(0.5, 0.5),
+        Vec2::new(0.5, -0.5),
     ];
     let mut s = Poly::new(points);
-    let x = Point::new([0.0, 0.0, 3.0]);
+    let x = Vec3::new(0.0, 0.0, 3.0);
     let xform = Xform::translation(&x);
     s.complete(&xform);
 
-    let ray = Ray::new(Point::new([0.0, 0.0, 0.0]), Point::new([0.0, 0.0, 1.0]));
+    let ray = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
     assert!(s.intersect(&xform, &ray).is_some());
 
-    let ray = Ray::new(
-        Point::new([0.0, 0.0, 0.0]),
-        Point::new([1.0, 0.0, 1.0]).unit(),
-    );
+    let ray = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 1.0).unit());
     assert!(s.intersect(&xform, &ray).is_none());
 }
+
+#[test]
+fn test_poly_holes() {
+    // An L-shape with a square hole, wound the same way.
+    let outer = vec![
+        Vec2::new(0.0, 0.0),
+        Vec2::new(4.0, 0.0),
+        Vec2::new(4.0, 2.0),
+        Vec2::new(2.0, 2.0),
+        Vec2::new(2.0, 4.0),
+        Vec2::new(0.0, 4.0),
+    ];
+    let hole = vec![
+        Vec2::new(0.5, 0.5),
+        Vec2::new(1.5, 0.5),
+        Vec2::new(1.5, 1.5),
+        Vec2::new(0.5, 1.5),
+    ];
+    let mut s = Poly::with_holes(outer, vec![hole.clone()]);
+    assert!(s.contains(&Vec2::new(3.0, 1.0)));
+    assert!(s.contains(&Vec2::new(1.0, 3.0)));
+    assert!(!s.contains(&Vec2::new(3.0, 3.0)));
+    assert!(!s.contains(&Vec2::new(1.0, 1.0)));
+    // At the height of a vertex.
+    assert!(s.contains(&Vec2::new(1.0, 2.0)));
+
+    // Same-wound hole is filled under the nonzero rule,
+    // but not when reversed.
+    s.rule = FillRule::NonZero;
+    assert!(s.contains(&Vec2::new(1.0, 1.0)));
+    s.holes = vec![hole.into_iter().rev().collect()];
+    assert!(!s.contains(&Vec2::new(1.0, 1.0)));
+
+    // A tilted square in 3-space.
+    let pts: Vec<_> = [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)]
+        .iter()
+        .map(|&(a, b)| Vec3::new(a, b, a + 2.0))
+        .collect();
+    let (mut s, xform) = Poly::from_points(&pts, &[]).unwrap();
+    s.uv = PolyUv::Bounds;
+    s.complete(&xform);
+    let ray = Ray::new(Vec3::new(0.5, 0.5, 0.0), Vec3::new(0.0, 0.0, 1.0));
+    let i = s.intersect(&xform, &ray).unwrap();
+    assert!((i.t - 2.5).abs() < TINY);
+    assert!((i.at - Vec2::new(0.5, 0.5)).mag() < TINY);
+    // Wound counterclockwise seen from above.
+    assert!((i.normal - Vec3::new(-1.0, 0.0, 1.0).unit()).mag() < TINY);
+
+    let mut bent = pts.clone();
+    bent[2][Z] += 0.1;
+    assert!(matches!(
+        Poly::from_points(&bent, &[]),
+        Err(PolyError::NotPlanar)
+    ));
+}
+
+#[test]
+fn test_poly_vertex_uv() {
+    // Corner coordinates of a square interpolate as they
+    // would bilinearly at its center and along its edges.
+    let points = [
+        Vec2::new(0.0, 0.0),
+        Vec2::new(2.0, 0.0),
+        Vec2::new(2.0, 2.0),
+        Vec2::new(0.0, 2.0),
+    ];
+    let mut s = Poly::new(points);
+    s.uv = PolyUv::Vertices(points.iter().map(|p| *p * 0.5).collect());
+    let xform = Xform::identity();
+    s.complete(&xform);
+    assert!((s.uv(&Vec2::new(1.0, 1.0)) - Vec2::new(0.5, 0.5)).mag() < TINY);
+    assert!((s.uv(&Vec2::new(2.0, 0.5)) - Vec2::new(1.0, 0.25)).mag() < TINY);
+    assert!((s.uv(&Vec2::new(0.0, 2.0)) - Vec2::new(0.0, 1.0)).mag() < TINY);
+
+    let ray = Ray::new(Vec3::new(1.0, 1.0, -1.0), Vec3::new(0.0, 0.0, 1.0));
+    let i = s.intersect(&xform, &ray).unwrap();
+    assert!((i.dpdu - Vec3::new(2.0, 0.0, 0.0)).mag() < 1.0e-6);
+    assert!((i.dpdv - Vec3::new(0.0, 2.0, 0.0)).mag() < 1.0e-6);
+}
@@ -0,0 +1,441 @@
+use crate::*;
+
+/// A quadric surface: the points `p` with `pᵀ Q p = 0` for a
+/// symmetric 4x4 coefficient matrix `Q` acting on
+/// homogeneous coordinates. Points with `pᵀ Q p < 0` are
+/// inside, so the gradient gives the outward normal.
+///
+/// The surface is clipped to a range of z, and may have
+/// flat caps closing it off at either end of the range. The
+/// named constructors all build surfaces of revolution
+/// around the z axis; texture coordinates are the angle
+/// around the axis as a fraction of a turn and the height
+/// as a fraction of the z range (or raw z if the range is
+/// infinite). Cap texture coordinates are the angle and the
+/// distance from the axis.
+pub struct Quadric {
+    /// Coefficient matrix.
+    pub q: XFMatrix,
+    /// Least z of the surface.
+    pub zmin: f64,
+    /// Greatest z of the surface.
+    pub zmax: f64,
+    /// Whether the finite ends of the z range are closed
+    /// off by flat caps.
+    pub capped: bool,
+}
+
+impl Quadric {
+    /// General quadric with the given coefficient matrix,
+    /// which should be symmetric, clipped to the given z
+    /// range. Either end of the range may be infinite.
+    pub fn new(q: XFMatrix, zmin: f64, zmax: f64, capped: bool) -> Self {
+        assert!(zmin < zmax);
+        Self {
+            q,
+            zmin,
+            zmax,
+            capped,
+        }
+    }
+
+    /// Cylinder of unit radius from z = 0 to z = 1.
+    pub fn cylinder(capped: bool) -> Self {
+        Self::frustum(1.0, 1.0, capped)
+    }
+
+    /// Cone with its apex at the origin, opening to unit
+    /// radius at z = 1.
+    pub fn cone(capped: bool) -> Self {
+        Self::frustum(0.0, 1.0, capped)
+    }
+
+    /// Truncated cone with radius `r0` at z = 0 and `r1` at
+    /// z = 1.
+    pub fn frustum(r0: f64, r1: f64, capped: bool) -> Self {
+        assert!(r0 >= 0.0 && r1 >= 0.0 && r0 + r1 > 0.0);
+        // x² + y² - (kz + r0)² = 0
+        let k = r1 - r0;
+        let mut q = XFMatrix::default();
+        q[0][0] = 1.0;
+        q[1][1] = 1.0;
+        q[2][2] = -k * k;
+        q[2][3] = -k * r0;
+        q[3][2] = -k * r0;
+        q[3][3] = -r0 * r0;
+        Self::new(q, 0.0, 1.0, capped)
+    }
+
+    /// Paraboloid with its vertex at the origin, opening to
+    /// unit radius at z = 1.
+    pub fn paraboloid(capped: bool) -> Self {
+        // x² + y² - z = 0
+        let mut q = XFMatrix::default();
+        q[0][0] = 1.0;
+        q[1][1] = 1.0;
+        q[2][3] = -0.5;
+        q[3][2] = -0.5;
+        Self::new(q, 0.0, 1.0, capped)
+    }
+
+    /// Hyperboloid of one sheet with radius `waist` at
+    /// z = 0, from z = -1 to z = 1.
+    pub fn hyperboloid(waist: f64, capped: bool) -> Self {
+        assert!(waist > 0.0);
+        // x² + y² - z² - waist² = 0
+        let mut q = XFMatrix::default();
+        q[0][0] = 1.0;
+        q[1][1] = 1.0;
+        q[2][2] = -1.0;
+        q[3][3] = -waist * waist;
+        Self::new(q, -1.0, 1.0, capped)
+    }
+
+    // `Q v`.
+    fn apply(&self, v: &Vec4) -> Vec4 {
+        let mut r = Vec4::default();
+        for i in 0..4 {
+            for j in 0..4 {
+                r[i] += self.q[i][j] * v[j];
+            }
+        }
+        r
+    }
+
+    // Value of the implicit function at a point: negative
+    // inside.
+    fn f(&self, p: &Point3) -> f64 {
+        let p = Vec4::point(p);
+        p.dot(&self.apply(&p))
+    }
+
+    // Fraction of a turn around the z axis.
+    fn angle(p: &Point3) -> f64 {
+        let u = p[Y].atan2(p[X]) / (2.0 * PI);
+        if u < 0.0 {
+            u + 1.0
+        } else {
+            u
+        }
+    }
+}
+
+// Which part of a quadric a ray crosses.
+#[derive(Clone, Copy)]
+enum Part {
+    Side,
+    /// Cap with the given z component of its normal.
+    Cap(f64),
+}
+
+impl Quadric {
+    // All the parameters where the object-space ray crosses
+    // the clipped surface or its caps, in increasing order.
+    fn crossings(&self, ro: &Point3, rd: &Vec3) -> ([(f64, Part); 4], usize) {
+        let mut xs = [(0.0, Part::Side); 4];
+        let mut n = 0;
+        let in_range = |t: f64| {
+            let z = ro[Z] + rd[Z] * t;
+            self.zmin <= z && z <= self.zmax
+        };
+
+        // Solve a t² + 2b t + c = 0 for the side surface.
+        let o = Vec4::point(ro);
+        let d = Vec4::direction(rd);
+        let qo = self.apply(&o);
+        let a = d.dot(&self.apply(&d));
+        let b = d.dot(&qo);
+        let c = o.dot(&qo);
+        let roots = if a.abs() < TINY {
+            // Degenerate: the ray is parallel to a line on
+            // the surface, so there is at most one hit.
+            if b.abs() < TINY {
+                [None, None]
+            } else {
+                [Some(-c / (2.0 * b)), None]
+            }
+        } else {
+            let disc = b * b - a * c;
+            if disc < 0.0 {
+                [None, None]
+            } else {
+                let s = disc.sqrt();
+                [Some((-b - s) / a), Some((-b + s) / a)]
+            }
+        };
+        for t in roots.into_iter().flatten() {
+            if in_range(t) {
+                xs[n] = (t, Part::Side);
+                n += 1;
+            }
+        }
+
+        // Caps, where the end plane is inside the surface.
+        if self.capped && rd[Z].abs() >= TINY {
+            for (z0, nz) in [(self.zmin, -1.0), (self.zmax, 1.0)] {
+                if !z0.is_finite() {
+                    continue;
+                }
+                let t = (z0 - ro[Z]) / rd[Z];
+                let mut p = *ro + *rd * t;
+                p[Z] = z0;
+                if self.f(&p) <= 0.0 {
+                    xs[n] = (t, Part::Cap(nz));
+                    n += 1;
+                }
+            }
+        }
+
+        xs[..n].sort_by(|a, b| a.0.total_cmp(&b.0));
+        (xs, n)
+    }
+
+    // Hit information for a crossing of the object-space ray.
+    fn hit(
+        &self,
+        xform: &Xform,
+        ro: &Point3,
+        rd: &Vec3,
+        (t, part): (f64, Part),
+    ) -> Intersection<'static> {
+        let p = *ro + *rd * t;
+        // The angle varies as in a cylindrical mapping.
+        let (gu, _) = UvMap::Cylindrical.gradients(&p);
+        let (n, at, gv, curved) = match part {
+            Part::Side => {
+                let mut n = self.apply(&Vec4::point(&p)).xyz();
+                let curved = n.mag2() >= TINY * TINY;
+                if !curved {
+                    // At the apex of a cone the gradient
+                    // vanishes: face the ray instead.
+                    n = -*rd;
+                }
+                let (v, gv) = if self.zmin.is_finite() && self.zmax.is_finite() {
+                    let dz = self.zmax - self.zmin;
+                    ((p[Z] - self.zmin) / dz, Vec3::new(0.0, 0.0, 1.0 / dz))
+                } else {
+                    (p[Z], Vec3::new(0.0, 0.0, 1.0))
+                };
+                (n, Vec2::new(Self::angle(&p), v), gv, curved)
+            }
+            Part::Cap(nz) => {
+                let r = p.xy().mag();
+                let gv = if r > TINY {
+                    Vec3::new(p[X] / r, p[Y] / r, 0.0)
+                } else {
+                    Vec3::default()
+                };
+                (
+                    Vec3::new(0.0, 0.0, nz),
+                    Vec2::new(Self::angle(&p), r),
+                    gv,
+                    false,
+                )
+            }
+        };
+        let (dpdu, dpdv) = uv_tangents(&n, &gu, &gv);
+        // The gradient is linear in the point, so it changes
+        // along a tangent by the matrix applied to that tangent.
+        let change = |d: &Vec3| {
+            if curved {
+                xform.apply_normal_change(&n, &self.apply(&Vec4::direction(d)).xyz())
+            } else {
+                Vec3::default()
+            }
+        };
+        Intersection {
+            t,
+            normal: xform.apply_normal(&n),
+            at,
+            dpdu: xform.apply_vector(&dpdu),
+            dpdv: xform.apply_vector(&dpdv),
+            dndu: change(&dpdu),
+            dndv: change(&dpdv),
+            object: p,
+            object_dpdu: dpdu,
+            object_dpdv: dpdv,
+            texture: None,
+        }
+    }
+}
+
+impl Shape for Quadric {
+    fn intersect(&self, xform: &Xform, ray: &Ray) -> Option<Intersection<'_>> {
+        // Get the ray in our coordinates.
+        let mut ray = *ray;
+        ray.transform(&xform.inverse());
+        let Ray { rd, ro, .. } = ray;
+
+        let (xs, n) = self.crossings(&ro, &rd);
+        let x = xs[..n].iter().find(|x| x.0 > TINY)?;
+        Some(self.hit(xform, &ro, &rd, *x))
+    }
+
+    /// Spans run between crossings where the ray goes in
+    /// and out, judged by whether it is inside just past
+    /// each one, so that a ray touching the surface or
+    /// passing through the apex of a cone is paired off
+    /// properly. This is only meaningful if the quadric is
+    /// closed.
+    fn intervals(&self, xform: &Xform, ray: &Ray) -> Vec<Span<'_>> {
+        let mut ray = *ray;
+        ray.transform(&xform.inverse());
+        let Ray { rd, ro, .. } = ray;
+
+        let (xs, n) = self.crossings(&ro, &rd);
+        let inside = |t: f64| {
+            let p = ro + rd * t;
+            self.zmin <= p[Z] && p[Z] <= self.zmax && self.f(&p) < 0.0
+        };
+        let mut spans = Vec::new();
+        let mut enter = None;
+        for k in 0..n {
+            let now_inside = k + 1 < n && inside(0.5 * (xs[k].0 + xs[k + 1].0));
+            match enter {
+                None if now_inside => enter = Some(xs[k]),
+                Some(e) if !now_inside => {
+                    spans.push(Span {
+                        enter: self.hit(xform, &ro, &rd, e),
+                        exit: self.hit(xform, &ro, &rd, xs[k]),
+                    });
+                    enter = None;
+                }
+                _ => (),
+            }
+        }
+        spans
+    }
+
+    /// For a surface of revolution around the z axis, as
+    /// the named constructors build, with both ends of its z
+    /// range finite: the box out to its widest radius.
+    fn bounds(&self) -> Option<Bounds> {
+        let q = &self.q;
+        let round = q[0][0] > 0.0
+            && q[1][1] == q[0][0]
+            && (0..4).all(|j| {
+                (j == 0 || q[0][j] == 0.0 && q[j][0] == 0.0)
+                    && (j == 1 || q[1][j] == 0.0 && q[j][1] == 0.0)
+            });
+        if !round || !self.zmin.is_finite() || !self.zmax.is_finite() {
+            return None;
+        }
+        // Squared radius of the surface at height z, which
+        // is widest at an end of the range unless it bulges
+        // out in between.
+        let r2 = |z: f64| -(q[2][2] * z * z + 2.0 * q[2][3] * z + q[3][3]) / q[0][0];
+        let mut widest = r2(self.zmin).max(r2(self.zmax));
+        if q[2][2] > 0.0 {
+            let z = -q[2][3] / q[2][2];
+            if self.zmin < z && z < self.zmax {
+                widest = widest.max(r2(z));
+            }
+        }
+        let r = widest.max(0.0).sqrt();
+        Some(Bounds::new(
+            Vec3::new(-r, -r, self.zmin),
+            Vec3::new(r, r, self.zmax),
+        ))
+    }
+
+    fn complete(&mut self, _xform: &Xform) {
+        // Nothing to precompute: the ray is taken to object
+        // space and the normal back to world space at
+        // intersection time.
+    }
+}
+
+#[test]
+fn test_quadric_intersect() {
+    let xform = Xform::translation(&Vec3::new(0.0, 0.0, 3.0));
+    let along = Ray::new(Vec3::new(0.5, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
+    let across = Ray::new(Vec3::new(-5.0, 0.0, 3.5), Vec3::new(1.0, 0.0, 0.0));
+
+    let mut s = Quadric::cylinder(false);
+    s.complete(&xform);
+    assert!(s.intersect(&xform, &along).is_none());
+    let i = s.intersect(&xform, &across).unwrap();
+    assert!((i.t - 4.0).abs() < TINY);
+    assert!((i.normal - Vec3::new(-1.0, 0.0, 0.0)).mag() < TINY);
+    assert!((i.at[Y] - 0.5).abs() < TINY);
+
+    let mut s = Quadric::cylinder(true);
+    s.complete(&xform);
+    let i = s.intersect(&xform, &along).unwrap();
+    assert!((i.t - 3.0).abs() < TINY);
+    assert!((i.normal - Vec3::new(0.0, 0.0, -1.0)).mag() < TINY);
+
+    // The cone is half as wide halfway up, and its normal
+    // tilts away from the apex.
+    let mut s = Quadric::cone(false);
+    s.complete(&xform);
+    let i = s.intersect(&xform, &across).unwrap();
+    assert!((i.t - 4.5).abs() < TINY);
+    let n = Vec3::new(-1.0, 0.0, -1.0).unit();
+    assert!((i.normal - n).mag() < TINY);
+
+    // Bounds reach out to the widest radius in the range.
+    let b = s.bounds().unwrap();
+    assert!((b.max - Vec3::new(1.0, 1.0, 1.0)).mag() < TINY);
+    assert!((b.min - Vec3::new(-1.0, -1.0, 0.0)).mag() < TINY);
+    let b = Quadric::hyperboloid(0.5, false).bounds().unwrap();
+    assert!((b.max[X] - 1.25f64.sqrt()).abs() < TINY);
+    // A sphere, widest halfway up.
+    let mut q = XFMatrix::default();
+    q[0][0] = 1.0;
+    q[1][1] = 1.0;
+    q[2][2] = 1.0;
+    q[3][3] = -1.0;
+    let b = Quadric::new(q, -0.5, 0.5, true).bounds().unwrap();
+    assert!((b.max[X] - 1.0).abs() < TINY);
+    // An endless cylinder.
+    let q = Quadric::cylinder(false).q;
+    assert!(Quadric::new(q, 0.0, f64::INFINITY, false)
+        .bounds()
+        .is_none());
+}
+
+#[test]
+fn test_quadric_apex() {
+    // A ray straight through the apex of a cone has a
+    // normal facing back along it.
+    let xform = Xform::identity();
+    let through = Ray::new(Vec3::new(0.0, 0.0, -5.0), Vec3::new(0.0, 0.0, 1.0));
+    let s = Quadric::cone(true);
+    let i = s.intersect(&xform, &through).unwrap();
+    assert!((i.t - 5.0).abs() < TINY);
+    assert!((i.normal - Vec3::new(0.0, 0.0, -1.0)).mag() < TINY);
+    let spans = s.intervals(&xform, &through);
+    assert_eq!(spans.len(), 1);
+    assert!((spans[0].enter.t - 5.0).abs() < TINY);
+    assert!((spans[0].exit.t - 6.0).abs() < TINY);
+    assert!(spans[0].enter.normal.mag() > 0.5);
+
+    // A ray grazing a cylinder goes nowhere inside it.
+    let s = Quadric::cylinder(true);
+    let grazing = Ray::new(Vec3::new(-5.0, 1.0, 0.5), Vec3::new(1.0, 0.0, 0.0));
+    assert!(s
+        .intervals(&xform, &grazing)
+        .iter()
+        .all(|s| s.exit.t - s.enter.t < TINY));
+}
+
+#[test]
+fn test_quadric_normal_change() {
+    let mut s = Quadric::paraboloid(false);
+    let mut xform = Xform::scaling(&Vec3::new(1.0, 2.0, 3.0));
+    xform *= &Xform::translation(&Vec3::new(0.0, 0.0, 5.0));
+    s.complete(&xform);
+
+    // Up into the bowl from below.
+    let ray = Ray::new(Vec3::new(0.3, 0.4, 0.0), Vec3::new(0.0, 0.0, 1.0));
+    let i = s.intersect(&xform, &ray).unwrap();
+    let p = ray.at(i.t);
+    let e = 1e-5;
+    for (dp, dn) in [(i.dpdu, i.dndu), (i.dpdv, i.dndv)] {
+        // Aim at a point a little way along the tangent.
+        let q = p + dp * e;
+        let j = s.intersect(&xform, &Ray::new(ray.ro, q - ray.ro)).unwrap();
+        assert!((j.normal - (i.normal + dn * e)).mag() < 1e-8);
+    }
+}
@@ -0,0 +1,180 @@
+use crate::*;
+
+/// A surface given by a signed distance field, intersected by
+/// sphere tracing: stepping along the ray by the distance to
+/// the surface until it is close enough. Normals come from
+/// the gradient of the field. Texture coordinates are
+/// object-space x and y; wrap the shape in `UvMapped` for
+/// others.
+pub struct SdfShape {
+    pub sdf: Box<dyn Sdf>,
+    /// Radius of a sphere around the origin containing the
+    /// surface, or infinity for an unbounded field.
+    pub bound: f64,
+    /// Fraction of the distance to take as each step: 1 for
+    /// an exact field, less for distorted ones.
+    pub step: f64,
+    /// Largest number of steps to take before giving up.
+    pub max_steps: usize,
+    /// Distance from the surface that counts as a hit.
+    pub epsilon: f64,
+    /// Furthest to march when the field is unbounded.
+    pub max_distance: f64,
+}
+
+impl SdfShape {
+    pub fn new(sdf: Box<dyn Sdf>, bound: f64) -> Self {
+        Self {
+            sdf,
+            bound,
+            step: 1.0,
+            max_steps: 256,
+            epsilon: 10.0 * TINY,
+            max_distance: 1000.0,
+        }
+    }
+
+    /// Gradient of the field at `p` by central differences.
+    pub fn gradient(&self, p: &Point3) -> Vec3 {
+        let h = self.epsilon;
+        let mut g = Vec3::default();
+        for a in 0..3 {
+            let mut dp = Vec3::default();
+            dp[a] = h;
+            g[a] = self.sdf.distance(&(*p + dp)) - self.sdf.distance(&(*p - dp));
+        }
+        g
+    }
+}
+
+impl Shape for SdfShape {
+    fn intersect(&self, xform: &Xform, ray: &Ray) -> Option<Intersection<'_>> {
+        // Get the ray in our coordinates, and march along it
+        // by object-space distance.
+        let mut ray = *ray;
+        ray.transform(&xform.inverse());
+        let Ray { rd, ro, .. } = ray;
+        let len = rd.mag();
+        let dir = rd * (1.0 / len);
+
+        // Clip the march to the bounding sphere.
+        let (mut s, s_end) = if self.bound.is_finite() {
+            let b = ro * dir;
+            let c = ro.mag2() - self.bound * self.bound;
+            let disc = b * b - c;
+            if disc < 0.0 {
+                return None;
+            }
+            (-b - disc.sqrt(), -b + disc.sqrt())
+        } else {
+            (0.0, self.max_distance)
+        };
+        // Step off the surface a ray may be leaving.
+        s = s.max(10.0 * self.epsilon);
+
+        for _ in 0..self.max_steps {
+            if s > s_end {
+                return None;
+            }
+            let p = ro + dir * s;
+            let d = self.sdf.distance(&p).abs();
+            if d < self.epsilon {
+                let n = self.gradient(&p);
+                let (dpdu, dpdv) = UvMap::Planar.tangents(&p, &n);
+                return Some(Intersection {
+                    t: s / len,
+                    normal: xform.apply_normal(&n),
+                    at: p.xy(),
+                    dpdu: xform.apply_vector(&dpdu),
+                    dpdv: xform.apply_vector(&dpdv),
+                    dndu: Vec3::default(),
+                    dndv: Vec3::default(),
+                    object: p,
+                    object_dpdu: dpdu,
+                    object_dpdv: dpdv,
+                    texture: None,
+                });
+            }
+            s += self.step * d;
+        }
+        None
+    }
+
+    fn bounds(&self) -> Option<Bounds> {
+        let r = self.bound;
+        if !r.is_finite() {
+            return None;
+        }
+        Some(Bounds::new(Vec3::new(-r, -r, -r), Vec3::new(r, r, r)))
+    }
+
+    fn complete(&mut self, _xform: &Xform) {
+        // Nothing to precompute: the ray is taken to object
+        // space and the normal back to world space at
+        // intersection time.
+    }
+}
+
+#[test]
+fn test_sdf_intersect() {
+    let mut s = SdfShape::new(
+        Box::new(SdfRoundBox {
+            half: Vec3::new(1.0, 1.0, 1.0),
+            radius: 0.25,
+        }),
+        2.0,
+    );
+    let xform = Xform::translation(&Vec3::new(0.0, 0.0, 3.0));
+    s.complete(&xform);
+
+    let ray = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
+    let i = s.intersect(&xform, &ray).unwrap();
+    assert!((i.t - 2.0).abs() < s.epsilon);
+    assert!((i.normal - Vec3::new(0.0, 0.0, -1.0)).mag() < 1.0e-3);
+
+    // Just misses the rounded corner.
+    let ray = Ray::new(Vec3::new(0.95, 0.95, 0.0), Vec3::new(0.0, 0.0, 1.0));
+    assert!(s.intersect(&xform, &ray).is_none());
+}
+
+#[test]
+fn test_sdf_unbounded() {
+    // A layer of spheres repeating across the plane z = 0,
+    // turned and moved up beside a bounded sphere in a
+    // group.
+    let layer = SdfShape::new(
+        Box::new(Repeat {
+            sdf: Box::new(SdfSphere { radius: 0.5 }),
+            period: Vec3::new(2.0, 2.0, 0.0),
+        }),
+        f64::INFINITY,
+    );
+    assert!(layer.bounds().is_none());
+    let kblack = Color::new(0.0, 0.0, 0.0);
+    let texture = || Box::new(SolidTexture::new(kblack, kblack, kblack, 1.0));
+    let mut xform = Xform::rotation_z(0.3);
+    xform *= &Xform::translation(&Vec3::new(0.0, 0.0, 3.0));
+    let things = vec![
+        Thing {
+            shape: Box::new(layer),
+            texture: texture(),
+            xform,
+        },
+        Thing {
+            shape: Box::new(Sphere::default()),
+            texture: texture(),
+            xform: Xform::translation(&Vec3::new(5.0, 0.0, 0.0)),
+        },
+    ];
+    let mut g = Group::new(things);
+    assert!(g.bounds().is_none());
+    let xform = Xform::identity();
+    g.complete(&xform);
+
+    let ray = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
+    let i = g.intersect(&xform, &ray).unwrap();
+    assert!((i.t - 2.5).abs() < 1.0e-6);
+    let ray = Ray::new(Vec3::new(5.0, 0.0, -3.0), Vec3::new(0.0, 0.0, 1.0));
+    let i = g.intersect(&xform, &ray).unwrap();
+    assert!((i.t - 2.0).abs() < TINY);
+}
@@ -1,32 +1,21 @@
 use crate::*;
 
-/// A sphere of unit radius at a given position.
-pub struct Sphere {
-    /// Position of sphere.
-    tr: Point,
-}
+/// A sphere of unit radius centered at the origin. Texture
+/// coordinates are longitude and latitude, as by
+/// `UvMap::Spherical`.
+#[derive(Default)]
+pub struct Sphere {}
 
-impl Default for Sphere {
-    fn default() -> Self {
-        Sphere {
-            tr: Point::new([0.0, 0.0, 0.0]),
-        }
-    }
-}
+impl Sphere {
+    // Put the ray in our coords, and find the parameters
+    // where it enters and leaves the sphere, if it hits.
+    fn roots(&self, xform: &Xform, ray: &Ray) -> Option<(Ray, f64, f64)> {
+        let mut r = *ray;
+        r.transform(&xform.inverse());
 
-impl Shape for Sphere {
-    /// Iff the incoming ray is pointing in the right direction
-    /// and hits the sphere, return a homogeneous
-    /// point representing its xy coordinate.
-    fn intersect(&self, xform: &Xform, ray: &Ray) -> Option<Intersection> {
-        // Put the ray in our coords…
-        let mut r = ray.clone();
-        let toi = xform.inverse();
-        r.transform(&toi);
-
-        let a = r.rd.clone().mag2();
-        let b = r.ro.clone() * r.rd.clone();
-        let c = r.ro.clone().mag2() - 1.0;
+        let a = r.rd.mag2();
+        let b = r.ro * r.rd;
+        let c = r.ro.mag2() - 1.0;
         let d = b * b - a * c;
 
         if d < TINY {
@@ -34,48 +23,120 @@ impl Shape for Sphere {
             return None;
         }
 
-        let t = if a < 0.0 {
-            (-b + d.sqrt()) / a
-        } else {
-            (-b - d.sqrt()) / a
-        };
+        let t0 = (-b - d.sqrt()) / a;
+        let t1 = (-b + d.sqrt()) / a;
+        Some((r, t0, t1))
+    }
+
+    // Hit information for parameter `t` along the ray `r` in
+    // our coords.
+    fn hit(&self, xform: &Xform, r: &Ray, t: f64) -> Intersection<'static> {
+        // Find the intersection point in object coords.
+        let p = r.ro + r.rd * t;
+        let (dpdu, dpdv) = UvMap::Spherical.tangents(&p, &p);
+        Intersection {
+            t,
+            at: UvMap::Spherical.map(&p),
+            normal: xform.apply_normal(&p),
+            dpdu: xform.apply_vector(&dpdu),
+            dpdv: xform.apply_vector(&dpdv),
+            // The normal is the point itself.
+            dndu: xform.apply_normal_change(&p, &dpdu),
+            dndv: xform.apply_normal_change(&p, &dpdv),
+            object: p,
+            object_dpdu: dpdu,
+            object_dpdv: dpdv,
+            texture: None,
+        }
+    }
+}
+
+impl Shape for Sphere {
+    /// Iff the incoming ray is pointing in the right direction
+    /// and hits the sphere, return a homogeneous
+    /// point representing its xy coordinate.
+    fn intersect(&self, xform: &Xform, ray: &Ray) -> Option<Intersection<'_>> {
+        // Take the entry if it is ahead of us, otherwise the
+        // exit: a ray refracted into the sphere starts inside,
+        // and must find the far side to get out again.
+        let (r, t0, t1) = self.roots(xform, ray)?;
+        let t = if t0 >= TINY { t0 } else { t1 };
         if t < TINY {
             // The ray is travelling away from the sphere, so no hit.
             return None;
         }
+        Some(self.hit(xform, &r, t))
+    }
 
-        // Find the intersection point in object coords.
-        let mut i = r.ro + r.rd * t;
-        i.transform(&toi);
+    fn intervals(&self, xform: &Xform, ray: &Ray) -> Vec<Span<'_>> {
+        match self.roots(xform, ray) {
+            Some((r, t0, t1)) => vec![Span {
+                enter: self.hit(xform, &r, t0),
+                exit: self.hit(xform, &r, t1),
+            }],
+            None => Vec::new(),
+        }
+    }
 
-        // There are many possible mappings -- here's a lame one
-        Some(Intersection {
-            t,
-            at: Point::new([i[X], i[Y]]),
-            normal: (i - self.tr.clone()).unit(),
-        })
+    fn bounds(&self) -> Option<Bounds> {
+        Some(Bounds::new(
+            Vec3::new(-1.0, -1.0, -1.0),
+            Vec3::new(1.0, 1.0, 1.0),
+        ))
     }
 
-    fn complete(&mut self, xform: &Xform) {
-        let mut o = Point::new([0.0, 0.0, 0.0]);
-        o.transform(&xform.inverse());
-        self.tr = o;
+    fn complete(&mut self, _xform: &Xform) {
+        // Nothing to precompute: the ray is taken to object
+        // space.
     }
 }
 
 #[test]
 fn test_sphere_intersect() {
     let mut s = Sphere::default();
-    let x = Point::new([0.0, 0.0, 3.0]);
+    let x = Vec3::new(0.0, 0.0, 3.0);
     let xform = Xform::translation(&x);
     s.complete(&xform);
 
-    let ray = Ray::new(Point::new([0.0, 0.0, 0.0]), Point::new([0.0, 0.0, 1.0]));
-    assert!(s.intersect(&xform, &ray).is_some());
+    // Hits the -z pole, at the bottom of the texture.
+    let ray = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
+    let i = s.intersect(&xform, &ray).unwrap();
+    assert!(i.at[Y].abs() < TINY);
+
+    // Hits the equator at longitude 0.
+    let ray = Ray::new(Vec3::new(5.0, 0.0, 3.0), Vec3::new(-1.0, 0.0, 0.0));
+    let i = s.intersect(&xform, &ray).unwrap();
+    assert!((i.at - Vec2::new(0.0, 0.5)).mag() < TINY);
 
-    let ray = Ray::new(
-        Point::new([0.0, 0.0, 0.0]),
-        Point::new([1.0, 0.0, 1.0]).unit(),
-    );
+    let ray = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 0.0, 1.0).unit());
     assert!(s.intersect(&xform, &ray).is_none());
+
+    // From inside, the ray leaves through the far side, with
+    // the normal still pointing out.
+    let ray = Ray::new(Vec3::new(0.0, 0.0, 3.5), Vec3::new(0.0, 0.0, 1.0));
+    let i = s.intersect(&xform, &ray).unwrap();
+    assert!((i.t - 0.5).abs() < TINY);
+    assert!((i.normal - Vec3::new(0.0, 0.0, 1.0)).mag() < TINY);
+}
+
+#[test]
+fn test_sphere_normal_change() {
+    // A squashed, turned sphere, whose normal is not its
+    // point.
+    let mut s = Sphere::default();
+    let mut xform = Xform::scaling(&Vec3::new(2.0, 1.0, 0.5));
+    xform *= &Xform::rotation_y(0.4);
+    xform *= &Xform::translation(&Vec3::new(0.0, 0.0, 5.0));
+    s.complete(&xform);
+
+    let ray = Ray::new(Vec3::new(0.3, 0.2, 0.0), Vec3::new(0.0, 0.0, 1.0));
+    let i = s.intersect(&xform, &ray).unwrap();
+    let p = ray.at(i.t);
+    let e = 1e-5;
+    for (dp, dn) in [(i.dpdu, i.dndu), (i.dpdv, i.dndv)] {
+        // Aim at a point a little way along the tangent.
+        let q = p + dp * e;
+        let j = s.intersect(&xform, &Ray::new(ray.ro, q - ray.ro)).unwrap();
+        assert!((j.normal - (i.normal + dn * e)).mag() < 1e-8);
+    }
 }
@@ -0,0 +1,226 @@
+use crate::*;
+
+/// A torus around the z axis, centered at the origin. Texture
+/// coordinates are the angle around the z axis and the angle
+/// around the tube, each as a fraction of a turn.
+pub struct Torus {
+    /// Distance from the center to the middle of the tube.
+    pub major: f64,
+    /// Radius of the tube.
+    pub minor: f64,
+}
+
+impl Torus {
+    pub fn new(major: f64, minor: f64) -> Self {
+        assert!(major > 0.0 && minor > 0.0);
+        Self { major, minor }
+    }
+}
+
+// Fraction of a turn for the angle of (x, y).
+fn turns(y: f64, x: f64) -> f64 {
+    let u = y.atan2(x) / (2.0 * PI);
+    if u < 0.0 {
+        u + 1.0
+    } else {
+        u
+    }
+}
+
+impl Torus {
+    // Value of the implicit function at a point: negative
+    // inside.
+    fn f(&self, p: &Point3) -> f64 {
+        let r2 = self.major * self.major;
+        let k = p.mag2() + r2 - self.minor * self.minor;
+        k * k - 4.0 * r2 * (p[X] * p[X] + p[Y] * p[Y])
+    }
+
+    // All the parameters where the object-space ray crosses
+    // the torus, in increasing order.
+    fn crossings(&self, ro: &Point3, rd: &Vec3) -> Roots {
+        // Reject against the bounding sphere, and move the
+        // origin up to it: the quartic is much better
+        // conditioned near the surface.
+        let rb = self.major + self.minor;
+        let a = rd.mag2();
+        let b = *ro * *rd;
+        let c = ro.mag2() - rb * rb;
+        let disc = b * b - a * c;
+        if disc < 0.0 {
+            return Roots::default();
+        }
+        let t_near = (-b - disc.sqrt()) / a;
+        let o = *ro + *rd * t_near;
+
+        // (|p|² + R² - r²)² - 4R²(x² + y²) = 0 along the ray.
+        let r2 = self.major * self.major;
+        let k = o.mag2() + r2 - self.minor * self.minor;
+        let od = o * *rd;
+        let oxy = o[X] * rd[X] + o[Y] * rd[Y];
+        let dxy = rd[X] * rd[X] + rd[Y] * rd[Y];
+        let roots = solve_quartic(
+            a * a,
+            4.0 * a * od,
+            4.0 * od * od + 2.0 * a * k - 4.0 * r2 * dxy,
+            4.0 * od * k - 8.0 * r2 * oxy,
+            k * k - 4.0 * r2 * (o[X] * o[X] + o[Y] * o[Y]),
+        );
+        roots.offset(t_near)
+    }
+
+    // Hit information for parameter `t` along the
+    // object-space ray.
+    fn hit(&self, xform: &Xform, ro: &Point3, rd: &Vec3, t: f64) -> Intersection<'static> {
+        let p = *ro + *rd * t;
+        let r2 = self.major * self.major;
+        let s = p.mag2();
+        let n = Vec3::new(
+            p[X] * (s - r2 - self.minor * self.minor),
+            p[Y] * (s - r2 - self.minor * self.minor),
+            p[Z] * (s + r2 - self.minor * self.minor),
+        );
+        let rxy = p.xy().mag();
+        // Around the z axis, and around the tube.
+        let dpdu = Vec3::new(-p[Y], p[X], 0.0) * (2.0 * PI);
+        let dpdv = if rxy > TINY {
+            Vec3::new(-p[Z] * p[X] / rxy, -p[Z] * p[Y] / rxy, rxy - self.major) * (2.0 * PI)
+        } else {
+            Vec3::default()
+        };
+        // Differentiate the gradient along a tangent.
+        let change = |d: &Vec3| {
+            let ds = 2.0 * (p * *d);
+            let dn = Vec3::new(
+                d[X] * (s - r2 - self.minor * self.minor) + p[X] * ds,
+                d[Y] * (s - r2 - self.minor * self.minor) + p[Y] * ds,
+                d[Z] * (s + r2 - self.minor * self.minor) + p[Z] * ds,
+            );
+            xform.apply_normal_change(&n, &dn)
+        };
+        Intersection {
+            t,
+            normal: xform.apply_normal(&n),
+            at: Vec2::new(turns(p[Y], p[X]), turns(p[Z], rxy - self.major)),
+            dpdu: xform.apply_vector(&dpdu),
+            dpdv: xform.apply_vector(&dpdv),
+            dndu: change(&dpdu),
+            dndv: change(&dpdv),
+            object: p,
+            object_dpdu: dpdu,
+            object_dpdv: dpdv,
+            texture: None,
+        }
+    }
+}
+
+impl Shape for Torus {
+    fn intersect(&self, xform: &Xform, ray: &Ray) -> Option<Intersection<'_>> {
+        // Get the ray in our coordinates.
+        let mut ray = *ray;
+        ray.transform(&xform.inverse());
+        let Ray { rd, ro, .. } = ray;
+
+        let roots = self.crossings(&ro, &rd);
+        let t = roots.as_slice().iter().find(|&&t| t > TINY)?;
+        Some(self.hit(xform, &ro, &rd, *t))
+    }
+
+    /// As for `Quadric`, spans run between crossings where
+    /// the ray goes in and out, judged by whether it is
+    /// inside just past each one, so that a ray touching the
+    /// surface is paired off properly.
+    fn intervals(&self, xform: &Xform, ray: &Ray) -> Vec<Span<'_>> {
+        let mut ray = *ray;
+        ray.transform(&xform.inverse());
+        let Ray { rd, ro, .. } = ray;
+
+        let roots = self.crossings(&ro, &rd);
+        let ts = roots.as_slice();
+        let mut spans = Vec::new();
+        let mut enter = None;
+        for k in 0..ts.len() {
+            let now_inside =
+                k + 1 < ts.len() && self.f(&(ro + rd * (0.5 * (ts[k] + ts[k + 1])))) < 0.0;
+            match enter {
+                None if now_inside => enter = Some(ts[k]),
+                Some(e) if !now_inside => {
+                    spans.push(Span {
+                        enter: self.hit(xform, &ro, &rd, e),
+                        exit: self.hit(xform, &ro, &rd, ts[k]),
+                    });
+                    enter = None;
+                }
+                _ => (),
+            }
+        }
+        spans
+    }
+
+    fn bounds(&self) -> Option<Bounds> {
+        let r = self.major + self.minor;
+        let m = self.minor;
+        Some(Bounds::new(Vec3::new(-r, -r, -m), Vec3::new(r, r, m)))
+    }
+
+    fn complete(&mut self, _xform: &Xform) {
+        // Nothing to precompute: the ray is taken to object
+        // space and the normal back to world space at
+        // intersection time.
+    }
+}
+
+#[test]
+fn test_torus_intersect() {
+    let mut s = Torus::new(1.0, 0.25);
+    let xform = Xform::translation(&Vec3::new(0.0, 0.0, 3.0));
+    s.complete(&xform);
+
+    // Straight down through the tube.
+    let ray = Ray::new(Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
+    let i = s.intersect(&xform, &ray).unwrap();
+    assert!((i.t - 2.75).abs() < TINY);
+    assert!((i.normal - Vec3::new(0.0, 0.0, -1.0)).mag() < TINY);
+
+    // Through the hole.
+    let ray = Ray::new(Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 0.0, 1.0));
+    assert!(s.intersect(&xform, &ray).is_none());
+
+    // Edge-on, hitting the outside of the ring.
+    let ray = Ray::new(Vec3::new(-5.0, 0.0, 3.0), Vec3::new(1.0, 0.0, 0.0));
+    let i = s.intersect(&xform, &ray).unwrap();
+    assert!((i.t - 3.75).abs() < TINY);
+    assert!((i.normal - Vec3::new(-1.0, 0.0, 0.0)).mag() < TINY);
+    let spans = s.intervals(&xform, &ray);
+    assert_eq!(spans.len(), 2);
+    assert!((spans[0].exit.t - 4.25).abs() < TINY);
+    assert!((spans[1].enter.t - 5.75).abs() < TINY);
+
+    // Along the edge of the hole, the ray touches the inside
+    // of the tube halfway through it.
+    let ray = Ray::new(Vec3::new(0.75, -5.0, 3.0), Vec3::new(0.0, 1.0, 0.0));
+    let spans = s.intervals(&xform, &ray);
+    assert_eq!(spans.len(), 1);
+    assert!((spans[0].enter.t - 4.0).abs() < TINY);
+    assert!((spans[0].exit.t - 6.0).abs() < TINY);
+}
+
+#[test]
+fn test_torus_normal_change() {
+    let mut s = Torus::new(1.0, 0.25);
+    let mut xform = Xform::scaling(&Vec3::new(1.0, 2.0, 1.0));
+    xform *= &Xform::rotation_x(0.5);
+    xform *= &Xform::translation(&Vec3::new(0.0, 0.0, 3.0));
+    s.complete(&xform);
+
+    let ray = Ray::new(Vec3::new(0.9, 0.4, 0.0), Vec3::new(0.0, 0.0, 1.0));
+    let i = s.intersect(&xform, &ray).unwrap();
+    let p = ray.at(i.t);
+    let e = 1e-5;
+    for (dp, dn) in [(i.dpdu, i.dndu), (i.dpdv, i.dndv)] {
+        // Aim at a point a little way along the tangent.
+        let q = p + dp * e;
+        let j = s.intersect(&xform, &Ray::new(ray.ro, q - ray.ro)).unwrap();
+        assert!((j.normal - (i.normal + dn * e)).mag() < 1e-8);
+    }
+}
@@ -0,0 +1,44 @@
+use crate::*;
+
+/// Intersect the ray with origin `ro` and direction `rd`
+/// with the triangle `p`, by the Möller-Trumbore method.
+/// Returns the ray parameter and the barycentric weights of
+/// `p[1]` and `p[2]` at the hit. Both sides of the triangle
+/// are hit, and hits behind the origin are reported.
+pub fn intersect_triangle(ro: &Point3, rd: &Vec3, p: &[Point3; 3]) -> Option<(f64, f64, f64)> {
+    let e1 = p[1] - p[0];
+    let e2 = p[2] - p[0];
+    let pv = rd.cross_product(&e2);
+    let det = e1.dot(&pv);
+    if det.abs() < 1.0e-12 {
+        // The ray is parallel to the triangle.
+        return None;
+    }
+    let inv = 1.0 / det;
+    let tv = *ro - p[0];
+    let u = tv.dot(&pv) * inv;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+    let qv = tv.cross_product(&e1);
+    let v = rd.dot(&qv) * inv;
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+    Some((e2.dot(&qv) * inv, u, v))
+}
+
+#[test]
+fn test_intersect_triangle() {
+    let p = [
+        Vec3::new(0.0, 0.0, 1.0),
+        Vec3::new(1.0, 0.0, 1.0),
+        Vec3::new(0.0, 1.0, 1.0),
+    ];
+    let ro = Vec3::new(0.25, 0.25, 0.0);
+    let (t, u, v) = intersect_triangle(&ro, &Vec3::new(0.0, 0.0, 1.0), &p).unwrap();
+    assert!((t - 1.0).abs() < TINY);
+    assert!((u - 0.25).abs() < TINY && (v - 0.25).abs() < TINY);
+    let ro = Vec3::new(0.75, 0.75, 0.0);
+    assert!(intersect_triangle(&ro, &Vec3::new(0.0, 0.0, 1.0), &p).is_none());
+}
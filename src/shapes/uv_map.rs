@@ -0,0 +1,204 @@
+use crate::*;
+
+/// Projections from object-space points to texture
+/// coordinates.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UvMap {
+    /// x and y.
+    Planar,
+    /// Longitude around the z axis as a fraction of a turn,
+    /// and latitude from the -z pole (0) to the +z pole (1).
+    Spherical,
+    /// Longitude around the z axis as a fraction of a turn,
+    /// and z.
+    Cylindrical,
+}
+
+impl UvMap {
+    /// Texture coordinates of an object-space point.
+    pub fn map(&self, p: &Point3) -> Vec2 {
+        // Fraction of a turn around the z axis.
+        let u = || p[Y].atan2(p[X]).rem_euclid(2.0 * PI) / (2.0 * PI);
+        match self {
+            UvMap::Planar => p.xy(),
+            UvMap::Spherical => {
+                let r = p.mag();
+                let v = if r > 0.0 {
+                    (-p[Z] / r).clamp(-1.0, 1.0).acos() / PI
+                } else {
+                    0.5
+                };
+                Vec2::new(u(), v)
+            }
+            UvMap::Cylindrical => Vec2::new(u(), p[Z]),
+        }
+    }
+
+    /// Gradients in 3-space of the texture coordinates of
+    /// an object-space point. The longitude gradient is zero
+    /// on the z axis, where it is undefined.
+    pub fn gradients(&self, p: &Point3) -> (Vec3, Vec3) {
+        let rxy2 = p[X] * p[X] + p[Y] * p[Y];
+        let rxy = rxy2.sqrt();
+        let gu = if rxy > TINY {
+            Vec3::new(-p[Y], p[X], 0.0) * (1.0 / (2.0 * PI * rxy2))
+        } else {
+            Vec3::default()
+        };
+        match self {
+            UvMap::Planar => (Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0)),
+            UvMap::Spherical => {
+                let gv = if rxy > TINY {
+                    let k = 1.0 / (PI * p.mag2() * rxy);
+                    Vec3::new(-p[Z] * p[X], -p[Z] * p[Y], rxy2) * k
+                } else {
+                    Vec3::default()
+                };
+                (gu, gv)
+            }
+            UvMap::Cylindrical => (gu, Vec3::new(0.0, 0.0, 1.0)),
+        }
+    }
+
+    /// Tangents dP/du and dP/dv at the point `p` of a
+    /// surface with normal `n` textured by this map, as by
+    /// `uv_tangents()`.
+    pub fn tangents(&self, p: &Point3, n: &Normal3) -> (Vec3, Vec3) {
+        let (gu, gv) = self.gradients(p);
+        uv_tangents(n, &gu, &gv)
+    }
+}
+
+/// Tangents dP/du and dP/dv of a surface with normal `n`
+/// whose texture coordinates have the 3-space gradients `gu`
+/// and `gv`: the vectors in the tangent plane along which
+/// one coordinate changes at unit rate while the other
+/// stays fixed. Both are zero where the coordinates do not
+/// vary independently over the surface.
+pub fn uv_tangents(n: &Normal3, gu: &Vec3, gv: &Vec3) -> (Vec3, Vec3) {
+    // Project the gradients onto the tangent plane, and
+    // find the basis dual to them there.
+    let n = n.unit();
+    let gu = *gu - n * (n * *gu);
+    let gv = *gv - n * (n * *gv);
+    let (a, b, c) = (gu.mag2(), gu * gv, gv.mag2());
+    let det = a * c - b * b;
+    if det <= TINY * a * c {
+        return (Vec3::default(), Vec3::default());
+    }
+    (
+        (gu * c - gv * b) * (1.0 / det),
+        (gv * a - gu * b) * (1.0 / det),
+    )
+}
+
+/// Change in texture coordinates for a small step `dp` along
+/// a surface with tangents `dpdu` and `dpdv`: the
+/// least-squares solution of `dp = du dpdu + dv dpdv`. Zero
+/// where the tangents do not span the surface.
+pub fn uv_change(dp: &Vec3, dpdu: &Vec3, dpdv: &Vec3) -> Vec2 {
+    let (a, b, c) = (dpdu.mag2(), *dpdu * *dpdv, dpdv.mag2());
+    let det = a * c - b * b;
+    if det <= TINY * a * c {
+        return Vec2::default();
+    }
+    let (pu, pv) = (*dp * *dpdu, *dp * *dpdv);
+    Vec2::new((c * pu - b * pv) / det, (a * pv - b * pu) / det)
+}
+
+/// A shape with its texture coordinates replaced by a
+/// projection of the object-space hit point, for shapes
+/// with no useful coordinates of their own (signed distance
+/// fields, say) or to map a texture differently.
+pub struct UvMapped {
+    pub shape: Box<dyn Shape>,
+    pub map: UvMap,
+}
+
+impl UvMapped {
+    pub fn new(shape: Box<dyn Shape>, map: UvMap) -> Self {
+        Self { shape, map }
+    }
+
+    // Replace the texture coordinates of a hit, and the
+    // tangents along them.
+    fn remap<'a>(&self, xform: &Xform, ray: &Ray, mut i: Intersection<'a>) -> Intersection<'a> {
+        if i.t.is_finite() {
+            let p = xform.inverse().apply_point(&ray.at(i.t));
+            i.at = self.map.map(&p);
+            // The object-space normal, up to length.
+            let n = xform.transpose().apply_vector(&i.normal);
+            let (dpdu, dpdv) = self.map.tangents(&p, &n);
+            let (dpdu, dpdv) = (xform.apply_vector(&dpdu), xform.apply_vector(&dpdv));
+            // Carry the normal's change and the tangents in
+            // the coordinates of the part hit over to the new
+            // tangents, by writing them in terms of the old.
+            let (cu, cv) = (
+                uv_change(&dpdu, &i.dpdu, &i.dpdv),
+                uv_change(&dpdv, &i.dpdu, &i.dpdv),
+            );
+            (i.dndu, i.dndv) = (
+                i.dndu * cu[0] + i.dndv * cu[1],
+                i.dndu * cv[0] + i.dndv * cv[1],
+            );
+            (i.object_dpdu, i.object_dpdv) = (
+                i.object_dpdu * cu[0] + i.object_dpdv * cu[1],
+                i.object_dpdu * cv[0] + i.object_dpdv * cv[1],
+            );
+            (i.dpdu, i.dpdv) = (dpdu, dpdv);
+        }
+        i
+    }
+}
+
+impl Shape for UvMapped {
+    fn intersect(&self, xform: &Xform, ray: &Ray) -> Option<Intersection<'_>> {
+        let i = self.shape.intersect(xform, ray)?;
+        Some(self.remap(xform, ray, i))
+    }
+
+    fn intervals(&self, xform: &Xform, ray: &Ray) -> Vec<Span<'_>> {
+        self.shape
+            .intervals(xform, ray)
+            .into_iter()
+            .map(|s| Span {
+                enter: self.remap(xform, ray, s.enter),
+                exit: self.remap(xform, ray, s.exit),
+            })
+            .collect()
+    }
+
+    fn bounds(&self) -> Option<Bounds> {
+        self.shape.bounds()
+    }
+
+    fn light_samples(&self) -> usize {
+        self.shape.light_samples()
+    }
+
+    fn complete(&mut self, xform: &Xform) {
+        self.shape.complete(xform);
+    }
+}
+
+#[test]
+fn test_uv_tangents() {
+    // Stepping along the tangents of a transformed sphere
+    // steps its texture coordinates by the same amounts.
+    let mut s = Sphere::default();
+    let mut xform = Xform::scaling(&Vec3::new(2.0, 1.0, 1.5));
+    xform *= &Xform::rotation_x(0.4);
+    xform *= &Xform::translation(&Vec3::new(0.0, 0.0, 5.0));
+    s.complete(&xform);
+    let ray = Ray::new(Vec3::new(0.3, 0.2, 0.0), Vec3::new(0.0, 0.0, 1.0));
+    let i = s.intersect(&xform, &ray).unwrap();
+    assert!(i.dpdu * i.normal < TINY && i.dpdv * i.normal < TINY);
+
+    let uv = |p: Point3| UvMap::Spherical.map(&xform.inverse().apply_point(&p));
+    let p = ray.at(i.t);
+    let e = 1.0e-6;
+    let du = (uv(p + i.dpdu * e) - i.at) * (1.0 / e);
+    let dv = (uv(p + i.dpdv * e) - i.at) * (1.0 / e);
+    assert!((du - Vec2::new(1.0, 0.0)).mag() < 1.0e-3);
+    assert!((dv - Vec2::new(0.0, 1.0)).mag() < 1.0e-3);
+}
@@ -0,0 +1,241 @@
+//! Real roots of low-degree polynomials, for intersecting
+//! rays with algebraic surfaces.
+
+/// Real roots of a polynomial, in increasing order, without
+/// heap allocation. A repeated root is reported once.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Roots {
+    r: [f64; 4],
+    n: usize,
+}
+
+impl Roots {
+    fn push(&mut self, x: f64) {
+        if x.is_finite() {
+            self.r[self.n] = x;
+            self.n += 1;
+        }
+    }
+
+    // Sort the roots, and merge those that are nearly equal:
+    // a repeated root comes out as a cluster about the square
+    // root of the machine precision across.
+    fn finish(&mut self) {
+        self.r[..self.n].sort_by(f64::total_cmp);
+        let mut n = 0;
+        for i in 0..self.n {
+            let x = self.r[i];
+            if n > 0 && x - self.r[n - 1] <= 1.0e-7 * (1.0 + x.abs()) {
+                continue;
+            }
+            self.r[n] = x;
+            n += 1;
+        }
+        self.n = n;
+    }
+
+    /// The roots as a slice.
+    pub fn as_slice(&self) -> &[f64] {
+        &self.r[..self.n]
+    }
+
+    /// The roots with `dt` added to each: the roots of the
+    /// polynomial shifted right by `dt`.
+    pub fn offset(&self, dt: f64) -> Self {
+        let mut r = *self;
+        for x in r.r[..r.n].iter_mut() {
+            *x += dt;
+        }
+        r
+    }
+}
+
+// Evaluate the polynomial with coefficients `c`, highest
+// degree first, and its derivative at `x`.
+fn eval(c: &[f64], x: f64) -> (f64, f64) {
+    let mut p = 0.0;
+    let mut dp = 0.0;
+    for &k in c {
+        dp = dp * x + p;
+        p = p * x + k;
+    }
+    (p, dp)
+}
+
+// Refine a root of the polynomial with coefficients `c` by
+// a few Newton steps, keeping the original if they go bad.
+fn polish(c: &[f64], x: f64) -> f64 {
+    let mut x = x;
+    for _ in 0..4 {
+        let (p, dp) = eval(c, x);
+        if dp == 0.0 || p == 0.0 {
+            break;
+        }
+        let nx = x - p / dp;
+        if !nx.is_finite() || eval(c, nx).0.abs() > p.abs() {
+            break;
+        }
+        x = nx;
+    }
+    x
+}
+
+/// Real roots of `a x² + b x + c`. Degenerates to the
+/// linear case when `a` is zero.
+pub fn solve_quadratic(a: f64, b: f64, c: f64) -> Roots {
+    let mut roots = Roots::default();
+    if a == 0.0 {
+        if b != 0.0 {
+            roots.push(-c / b);
+        }
+        return roots;
+    }
+    let disc = b * b - 4.0 * a * c;
+    if disc < 0.0 {
+        return roots;
+    }
+    // Avoid cancellation by computing the larger-magnitude
+    // root first and getting the other from the product.
+    let q = -0.5 * (b + b.signum() * disc.sqrt());
+    if q == 0.0 {
+        roots.push(0.0);
+        roots.push(0.0);
+    } else {
+        roots.push(q / a);
+        roots.push(c / q);
+    }
+    roots.finish();
+    roots
+}
+
+/// Real roots of `a x³ + b x² + c x + d`. Degenerates to
+/// the quadratic case when `a` is zero.
+pub fn solve_cubic(a: f64, b: f64, c: f64, d: f64) -> Roots {
+    if a == 0.0 {
+        return solve_quadratic(b, c, d);
+    }
+    let (b, c, d) = (b / a, c / a, d / a);
+
+    // Depress: x = y - b/3 gives y³ + p y + q.
+    let shift = b / 3.0;
+    let p = c - b * shift;
+    let q = d - c * shift + 2.0 * shift * shift * shift;
+
+    let mut roots = Roots::default();
+    let disc = q * q / 4.0 + p * p * p / 27.0;
+    if disc > 0.0 {
+        // One real root, by Cardano.
+        let s = disc.sqrt();
+        let y = (-q / 2.0 + s).cbrt() + (-q / 2.0 - s).cbrt();
+        roots.push(y - shift);
+    } else if p == 0.0 {
+        roots.push(-shift);
+    } else {
+        // Three real roots, by the trigonometric method.
+        let m = 2.0 * (-p / 3.0).sqrt();
+        let theta = (3.0 * q / (p * m)).clamp(-1.0, 1.0).acos() / 3.0;
+        for k in 0..3 {
+            roots.push(m * (theta - 2.0 * std::f64::consts::PI * k as f64 / 3.0).cos() - shift);
+        }
+    }
+    let coeffs = [1.0, b, c, d];
+    for r in roots.r[..roots.n].iter_mut() {
+        *r = polish(&coeffs, *r);
+    }
+    roots.finish();
+    roots
+}
+
+/// Real roots of `a x⁴ + b x³ + c x² + d x + e`, by
+/// Ferrari's method with Newton polishing against the
+/// original polynomial. Degenerates to the cubic case when
+/// `a` is zero.
+pub fn solve_quartic(a: f64, b: f64, c: f64, d: f64, e: f64) -> Roots {
+    if a == 0.0 {
+        return solve_cubic(b, c, d, e);
+    }
+    let (b, c, d, e) = (b / a, c / a, d / a, e / a);
+
+    // Depress: x = y - b/4 gives y⁴ + p y² + q y + r.
+    let shift = b / 4.0;
+    let b2 = b * b;
+    let p = c - 3.0 * b2 / 8.0;
+    let q = d - b * c / 2.0 + b2 * b / 8.0;
+    let r = e - b * d / 4.0 + b2 * c / 16.0 - 3.0 * b2 * b2 / 256.0;
+
+    let mut roots = Roots::default();
+    let scale = 1.0 + p.abs() + r.abs();
+    if q.abs() < 1.0e-12 * scale {
+        // Biquadratic: solve for y².
+        for z in solve_quadratic(1.0, p, r).as_slice() {
+            if *z >= 0.0 {
+                let y = z.sqrt();
+                roots.push(y - shift);
+                roots.push(-y - shift);
+            } else if *z > -1.0e-12 * scale {
+                roots.push(-shift);
+            }
+        }
+    } else {
+        // Resolvent cubic 8m³ + 8p m² + (2p² - 8r) m - q² = 0
+        // has a positive root m, with which the quartic
+        // factors as the difference of two squares.
+        let ms = solve_cubic(8.0, 8.0 * p, 2.0 * p * p - 8.0 * r, -q * q);
+        let m = ms
+            .as_slice()
+            .iter()
+            .cloned()
+            .fold(f64::NEG_INFINITY, f64::max);
+        if m <= 0.0 {
+            return roots;
+        }
+        let s = (2.0 * m).sqrt();
+        for sign in [1.0, -1.0] {
+            let ys = solve_quadratic(1.0, sign * s, p / 2.0 + m - sign * q / (2.0 * s));
+            for y in ys.as_slice() {
+                roots.push(y - shift);
+            }
+        }
+    }
+
+    let coeffs = [1.0, b, c, d, e];
+    for x in roots.r[..roots.n].iter_mut() {
+        *x = polish(&coeffs, *x);
+    }
+    roots.finish();
+    roots
+}
+
+#[test]
+fn test_solve_quartic() {
+    // (x - 1)(x - 2)(x - 3)(x - 4)
+    let roots = solve_quartic(1.0, -10.0, 35.0, -50.0, 24.0);
+    assert_eq!(4, roots.as_slice().len());
+    for (i, r) in roots.as_slice().iter().enumerate() {
+        assert!((r - (i + 1) as f64).abs() < 1.0e-9);
+    }
+
+    // (x² + 1)(x - 2)(x + 0.5)
+    let roots = solve_quartic(2.0, -3.0, 0.0, -3.0, -2.0);
+    assert_eq!(2, roots.as_slice().len());
+    assert!((roots.as_slice()[0] + 0.5).abs() < 1.0e-9);
+    assert!((roots.as_slice()[1] - 2.0).abs() < 1.0e-9);
+
+    // (x - 1)²(x - 3)(x - 4) and (x - 2)²(x + 1)²: each
+    // repeated root once.
+    let roots = solve_quartic(1.0, -9.0, 27.0, -31.0, 12.0);
+    assert_eq!(3, roots.as_slice().len());
+    for (r, x) in roots.as_slice().iter().zip([1.0, 3.0, 4.0]) {
+        assert!((r - x).abs() < 1.0e-6);
+    }
+    let roots = solve_quartic(1.0, -2.0, -3.0, 4.0, 4.0);
+    assert_eq!(2, roots.as_slice().len());
+    for (r, x) in roots.as_slice().iter().zip([-1.0, 2.0]) {
+        assert!((r - x).abs() < 1.0e-6);
+    }
+
+    // (x² + 1)(x² + 4) and (x² + 1)(x² + x + 1) have no
+    // real roots.
+    assert!(solve_quartic(1.0, 0.0, 5.0, 0.0, 4.0).as_slice().is_empty());
+    assert!(solve_quartic(1.0, 1.0, 2.0, 1.0, 1.0).as_slice().is_empty());
+}
@@ -1,5 +1,11 @@
 use crate::*;
 
+/// A checkerboard of unit squares in texture coordinates,
+/// alternating between two textures. When filtered, each
+/// texture is weighted by how much of the footprint its
+/// squares cover. Where the squares differ only in color, a
+/// `Material` with a `Checker` pattern is cheaper, lighting
+/// the surface once.
 pub struct BoardTexture {
     red_texture: Box<dyn Texture>,
     black_texture: Box<dyn Texture>,
@@ -15,7 +21,7 @@ impl BoardTexture {
 }
 
 impl Texture for BoardTexture {
-    fn value(&self, at: &Point, gc: &Point, normal: &Point, m: &Model, depth: usize) -> Color {
+    fn value(&self, at: &Vec2, gc: &Point3, normal: &Normal3, m: &Model, depth: usize) -> Color {
         let x: isize = at[X].floor() as isize;
         let y: isize = at[Y].floor() as isize;
         let texture = if (x & 1) ^ (y & 1) == 1 {
@@ -25,4 +31,28 @@ impl Texture for BoardTexture {
         };
         texture.value(at, gc, normal, m, depth)
     }
+
+    /// Blend the two textures by how much of the footprint
+    /// their squares cover, as for a `Checker` pattern.
+    fn filtered_value(
+        &self,
+        at: &Vec2,
+        footprint: &Footprint,
+        gc: &Point3,
+        normal: &Normal3,
+        m: &Model,
+        depth: usize,
+    ) -> Color {
+        // Red where exactly one index is odd.
+        let red = checker_coverage(at, footprint);
+        let value = |t: &dyn Texture| t.filtered_value(at, footprint, gc, normal, m, depth);
+        if red < TINY {
+            value(self.black_texture.as_ref())
+        } else if red > 1.0 - TINY {
+            value(self.red_texture.as_ref())
+        } else {
+            value(self.red_texture.as_ref()) * red
+                + value(self.black_texture.as_ref()) * (1.0 - red)
+        }
+    }
 }
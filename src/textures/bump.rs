@@ -0,0 +1,142 @@
+use crate::*;
+
+/// A perturbation of the shading normal of a surface,
+/// giving it fine relief without changing its shape.
+pub enum Bump {
+    /// Displace the surface along its normal by the scalar
+    /// value of a pattern times `scale`, in world units.
+    Height {
+        height: Box<dyn Pattern>,
+        scale: f64,
+    },
+    /// A tangent-space normal map: the red, green and blue
+    /// of the pattern, taken from 0 to 1 to -1 to 1, are the
+    /// components of the normal along dP/du, along dP/dv
+    /// and along the surface normal.
+    NormalMap(Box<dyn Pattern>),
+}
+
+impl Bump {
+    pub fn height<P: Pattern + 'static>(height: P, scale: f64) -> Self {
+        Bump::Height {
+            height: Box::new(height),
+            scale,
+        }
+    }
+
+    pub fn normal_map<P: Pattern + 'static>(map: P) -> Self {
+        Bump::NormalMap(Box::new(map))
+    }
+
+    /// The shading normal at texture coordinates `at` of a
+    /// surface with the given footprint and unit normal.
+    pub fn normal(&self, at: &Vec2, footprint: &Footprint, normal: &Normal3) -> Normal3 {
+        match self {
+            Bump::Height { height, scale } => {
+                let Some((du, dv)) = steps(footprint) else {
+                    return *normal;
+                };
+                // Differences of the height a little way
+                // along each tangent.
+                let h = |d: Vec2, dp: Vec3| {
+                    let f = Footprint {
+                        object: footprint.object + dp,
+                        ..*footprint
+                    };
+                    height.scalar(&(*at + d), &f)
+                };
+                let h0 = height.scalar(at, footprint);
+                let dhdu = (h(Vec2::new(du, 0.0), footprint.object_dpdu * du) - h0) / du;
+                let dhdv = (h(Vec2::new(0.0, dv), footprint.object_dpdv * dv) - h0) / dv;
+
+                // Tangents of the displaced surface, ignoring
+                // the turning of the normal.
+                let pu = footprint.dpdu + *normal * (dhdu * scale);
+                let pv = footprint.dpdv + *normal * (dhdv * scale);
+                let n = pu.cross_product(&pv).unit();
+                if n * *normal < 0.0 {
+                    -n
+                } else {
+                    n
+                }
+            }
+            Bump::NormalMap(map) => {
+                let c = map.color(at, footprint);
+                let (t, b) = frame(footprint, normal);
+                let m = |k: usize| 2.0 * c[k] - 1.0;
+                let n = t * m(R) + b * m(G) + *normal * m(B);
+                if n.mag2() > 0.0 {
+                    n.unit()
+                } else {
+                    *normal
+                }
+            }
+        }
+    }
+}
+
+// Steps in u and v for differencing a height pattern: half
+// the footprint, or a small step if the footprint is
+// unknown. None if the surface tangents are unknown.
+fn steps(footprint: &Footprint) -> Option<(f64, f64)> {
+    if footprint.dpdu.mag2() == 0.0 || footprint.dpdv.mag2() == 0.0 {
+        return None;
+    }
+    let step = |a: usize| {
+        let d = 0.5 * (footprint.dx[a].abs() + footprint.dy[a].abs());
+        if d > 0.0 {
+            d
+        } else {
+            0.0005
+        }
+    };
+    Some((step(X), step(Y)))
+}
+
+// Orthonormal tangent frame around the unit normal, with
+// the first axis along dP/du and the second on the side of
+// dP/dv. Where the tangents are unknown any frame is used.
+fn frame(footprint: &Footprint, normal: &Normal3) -> (Vec3, Vec3) {
+    let f = Frame::new(normal, &footprint.dpdu);
+    if f.t * footprint.dpdv < 0.0 {
+        (f.s, -f.t)
+    } else {
+        (f.s, f.t)
+    }
+}
+
+#[test]
+fn test_bump() {
+    // The plane z = 0 with u along x and v along y.
+    let footprint = Footprint {
+        dpdu: Vec3::new(1.0, 0.0, 0.0),
+        dpdv: Vec3::new(0.0, 1.0, 0.0),
+        object_dpdu: Vec3::new(1.0, 0.0, 0.0),
+        object_dpdv: Vec3::new(0.0, 1.0, 0.0),
+        ..Default::default()
+    };
+    let n = Vec3::new(0.0, 0.0, 1.0);
+    let at = Vec2::new(0.3, 0.3);
+
+    // A flat height field and a flat normal map change
+    // nothing.
+    let flat = Bump::height(0.5, 1.0).normal(&at, &footprint, &n);
+    assert!((flat - n).mag() < TINY);
+    let flat = Bump::normal_map(Color::new(0.5, 0.5, 1.0)).normal(&at, &footprint, &n);
+    assert!((flat - n).mag() < TINY);
+
+    // A normal map tips the normal toward +u.
+    let tipped = Bump::normal_map(Color::new(1.0, 0.5, 1.0)).normal(&at, &footprint, &n);
+    assert!((tipped - Vec3::new(1.0, 0.0, 1.0).unit()).mag() < TINY);
+
+    // A height rising with u at unit slope tips the normal
+    // back toward -u.
+    struct Ramp;
+    impl Pattern for Ramp {
+        fn color(&self, at: &Vec2, _footprint: &Footprint) -> Color {
+            Color::new(at[X], at[X], at[X])
+        }
+    }
+    let slope = Bump::height(Ramp, 1.0).normal(&at, &footprint, &n);
+    assert!((slope - Vec3::new(-1.0, 0.0, 1.0).unit()).mag() < 1.0e-6);
+}
@@ -0,0 +1,96 @@
+use crate::*;
+
+/// A glowing surface, such as a lamp or a light panel. It
+/// gives off light in the color of its pattern times its
+/// strength and reflects none. A thing with this texture,
+/// or with a part of a `Group` or `Csg` that has it, is
+/// also a light source: other surfaces sample points on it
+/// for their direct lighting, so it casts soft shadows.
+pub struct Emissive {
+    pub radiance: Box<dyn Pattern>,
+    pub strength: f64,
+    /// Whether light is given off from the back of the
+    /// surface as well as the front, where the normal
+    /// points.
+    pub two_sided: bool,
+    /// Number of points sampled on the thing for each
+    /// surface it lights; after the first bounce only one
+    /// is used.
+    pub samples: usize,
+    /// How the light weakens with distance from the thing
+    /// when it lights other surfaces.
+    pub falloff: Falloff,
+}
+
+impl Emissive {
+    pub fn new<P: Pattern + 'static>(radiance: P) -> Self {
+        Self {
+            radiance: Box::new(radiance),
+            strength: 1.0,
+            two_sided: false,
+            samples: 16,
+            falloff: Falloff::InverseSquare,
+        }
+    }
+
+    pub fn with_strength(mut self, strength: f64) -> Self {
+        self.strength = strength;
+        self
+    }
+
+    pub fn with_two_sided(mut self, two_sided: bool) -> Self {
+        self.two_sided = two_sided;
+        self
+    }
+
+    pub fn with_samples(mut self, samples: usize) -> Self {
+        self.samples = samples;
+        self
+    }
+
+    pub fn with_falloff(mut self, falloff: Falloff) -> Self {
+        self.falloff = falloff;
+        self
+    }
+}
+
+impl Texture for Emissive {
+    /// Without a footprint the object-space point and the
+    /// viewing direction are not known, so the pattern is
+    /// evaluated at the world-space point and the front is
+    /// assumed to be seen.
+    fn value(&self, at: &Vec2, gc: &Point3, normal: &Normal3, _m: &Model, _depth: usize) -> Color {
+        let footprint = Footprint {
+            object: *gc,
+            ..Default::default()
+        };
+        self.emitted(at, &footprint, normal)
+    }
+
+    fn filtered_value(
+        &self,
+        at: &Vec2,
+        footprint: &Footprint,
+        _gc: &Point3,
+        normal: &Normal3,
+        _m: &Model,
+        _depth: usize,
+    ) -> Color {
+        self.emitted(at, footprint, normal)
+    }
+
+    fn emitted(&self, at: &Vec2, footprint: &Footprint, normal: &Normal3) -> Color {
+        if !self.two_sided && footprint.dir * *normal > 0.0 {
+            return Color::new(0.0, 0.0, 0.0);
+        }
+        self.radiance.color(at, footprint) * self.strength
+    }
+
+    fn light_samples(&self) -> usize {
+        self.samples
+    }
+
+    fn light_falloff(&self) -> Falloff {
+        self.falloff
+    }
+}
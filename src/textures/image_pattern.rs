@@ -0,0 +1,140 @@
+use crate::*;
+
+/// How texture coordinates outside 0 to 1 are brought into
+/// an image.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Wrap {
+    /// Tile the image.
+    Repeat,
+    /// Extend the edge pixels.
+    Clamp,
+    /// Tile the image, flipping alternate tiles so that the
+    /// edges match.
+    Mirror,
+}
+
+impl Wrap {
+    /// Bring pixel index `i` into `0..n`.
+    pub fn index(self, i: isize, n: usize) -> usize {
+        let n = n as isize;
+        let i = match self {
+            Wrap::Repeat => i.rem_euclid(n),
+            Wrap::Clamp => i.clamp(0, n - 1),
+            Wrap::Mirror => {
+                let k = i.rem_euclid(2 * n);
+                if k < n {
+                    k
+                } else {
+                    2 * n - 1 - k
+                }
+            }
+        };
+        i as usize
+    }
+}
+
+/// How an image is averaged over a footprint.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Filter {
+    /// Interpolate between the four nearest pixels,
+    /// ignoring the footprint.
+    Bilinear,
+    /// Interpolate between mipmap levels whose texels are
+    /// about the width of the footprint.
+    Trilinear,
+    /// Average over the elliptical footprint, which is
+    /// sharper where the surface is seen at a grazing angle.
+    Ewa,
+}
+
+/// An image mapped onto a surface by its texture
+/// coordinates, with u running left to right and v bottom
+/// to top across the image. The image color is filtered
+/// over the footprint of the ray.
+pub struct ImagePattern {
+    mipmap: MipMap,
+    pub wrap: Wrap,
+    pub filter: Filter,
+    /// Number of copies of the image per unit of texture
+    /// coordinates, in u and v.
+    pub scale: Vec2,
+}
+
+impl ImagePattern {
+    pub fn new(image: ImageBuffer) -> Self {
+        Self {
+            mipmap: MipMap::new(&image),
+            wrap: Wrap::Repeat,
+            filter: Filter::Trilinear,
+            scale: Vec2::new(1.0, 1.0),
+        }
+    }
+
+    // Position in the image of the given texture
+    // coordinates, 0 to 1 left to right and top to bottom.
+    fn st(&self, at: &Vec2) -> Vec2 {
+        Vec2::new(at[X] * self.scale[X], 1.0 - at[Y] * self.scale[Y])
+    }
+
+    /// Image color at the given texture coordinates,
+    /// interpolated between the four nearest pixel centers.
+    pub fn sample(&self, at: &Vec2) -> Color {
+        self.mipmap.bilinear(0, &self.st(at), self.wrap)
+    }
+
+    /// Image color averaged over the given footprint by the
+    /// texture's filter.
+    pub fn sample_filtered(&self, at: &Vec2, footprint: &Footprint) -> Color {
+        let st = self.st(at);
+        let d = |v: &Vec2| Vec2::new(v[X] * self.scale[X], -v[Y] * self.scale[Y]);
+        let (d0, d1) = (d(&footprint.dx), d(&footprint.dy));
+        match self.filter {
+            Filter::Bilinear => self.mipmap.bilinear(0, &st, self.wrap),
+            Filter::Trilinear => {
+                let width = d0.mag().max(d1.mag());
+                self.mipmap.trilinear(&st, width, self.wrap)
+            }
+            Filter::Ewa => self.mipmap.ewa(&st, &d0, &d1, self.wrap),
+        }
+    }
+}
+
+impl Pattern for ImagePattern {
+    fn color(&self, at: &Vec2, footprint: &Footprint) -> Color {
+        self.sample_filtered(at, footprint)
+    }
+}
+
+#[test]
+fn test_image_pattern_sample() {
+    let mut image = ImageBuffer::new(2, 1);
+    image.set(1, 0, [255, 255, 255]);
+    let mut t = ImagePattern::new(image);
+
+    let gray = |t: &ImagePattern, u: f64| t.sample(&Vec2::new(u, 0.5))[R];
+
+    // Pixel centers, and halfway between.
+    assert!(gray(&t, 0.25).abs() < TINY);
+    assert!((gray(&t, 0.75) - 1.0).abs() < TINY);
+    assert!((gray(&t, 0.5) - 0.5).abs() < TINY);
+
+    // Past the edge, by each wrap mode.
+    assert!((gray(&t, 1.0) - 0.5).abs() < TINY);
+    t.wrap = Wrap::Clamp;
+    assert!((gray(&t, 1.0) - 1.0).abs() < TINY);
+    t.wrap = Wrap::Mirror;
+    assert!((gray(&t, 1.1) - 1.0).abs() < TINY);
+    assert!((gray(&t, 1.5) - 0.5).abs() < TINY);
+
+    // A footprint the width of the image sees its average.
+    let footprint = Footprint {
+        dx: Vec2::new(1.0, 0.0),
+        dy: Vec2::new(0.0, 1.0),
+        ..Default::default()
+    };
+    for filter in [Filter::Trilinear, Filter::Ewa] {
+        t.filter = filter;
+        let c = t.sample_filtered(&Vec2::new(0.3, 0.5), &footprint);
+        assert!((c[R] - 0.5).abs() < 1.0e-6);
+    }
+}
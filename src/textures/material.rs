@@ -0,0 +1,326 @@
+use crate::*;
+
+/// Phong lighting parameters of a surface at one point.
+#[derive(Clone, Copy)]
+pub struct Phong {
+    /// Ambient color.
+    pub ka: Color,
+    /// Diffuse color.
+    pub kd: Color,
+    /// Specular highlight color.
+    pub ks: Color,
+    /// Mirror reflection color.
+    pub kr: Color,
+    /// Specular exponent: larger is a tighter highlight.
+    pub ns: f64,
+    /// Roughness of the reflection from 0 to 1: 0 for a
+    /// perfect mirror, more for a blurrier one.
+    pub roughness: f64,
+    /// Number of reflection rays traced for a rough
+    /// reflection.
+    pub samples: usize,
+}
+
+impl Phong {
+    /// Light the surface at `gc` with the model's lights, its
+    /// glowing things and the mirror reflection of the scene,
+    /// as seen along the ray of the footprint, if known, or
+    /// else from the eye. The offset rays of the footprint,
+    /// if any, are reflected along with the reflected ray. A
+    /// rough surface with samples instead averages reflection
+    /// rays spread about the mirror direction.
+    pub fn shade(
+        &self,
+        gc: &Point3,
+        normal: &Normal3,
+        m: &Model,
+        depth: usize,
+        footprint: Option<&Footprint>,
+    ) -> Color {
+        // Start with ambient term.
+        let mut result = self.ka;
+
+        // Unit vector from the viewer toward the target: along
+        // the ray that hit, which need not come from the eye.
+        let pt = match footprint {
+            Some(f) if f.dir.mag2() > 0.0 => f.dir,
+            _ => (*gc - m.eye).unit(),
+        };
+        // Specular direction.
+        let reflect = |v: &Vec3| *v - *normal * ((*v * *normal) * 2.0);
+        let ps = reflect(&pt);
+
+        // Each sample of the lights, and of the glowing things
+        // of the scene, shines like a point light whose color
+        // is its share of the light over π.
+        let samples = m.light_samples(gc, depth);
+        for s in samples.into_iter().chain(m.emitted_light(gc, depth)) {
+            let pli = s.li * (1.0 / PI);
+            // Unit vector toward the light.
+            let lv = s.wi;
+
+            // Diffuse term.
+            let fd = lv * *normal;
+            if fd > TINY {
+                result += self.kd.colorize(&pli) * fd;
+            }
+
+            // Specular diffusion term.
+            let fs = ps * lv;
+            if fs > 0.0 {
+                let fs = (self.ns * fs.ln()).exp();
+                if fs > TINY {
+                    result += self.ks.colorize(&pli) * fs;
+                }
+            }
+        }
+
+        let mirror = self.kr.apply(|c| c == 0.0) != [true; 3];
+
+        // Ray tracing term, skipped for a surface that
+        // reflects nothing. Each offset ray is reflected
+        // about the normal where it met the surface, so that
+        // a curved mirror spreads or gathers the footprint.
+        if !mirror {
+            return result;
+        }
+        let tr = if self.roughness > 0.0 && self.samples > 0 {
+            self.glossy(gc, normal, &pt, m, depth)
+        } else {
+            let ray = match footprint.and_then(|f| f.rays.map(|d| (f, d))) {
+                Some((f, d)) => {
+                    // The normal a texture step `duv` away.
+                    let reflect_at = |v: &Vec3, duv: &Vec2| {
+                        let n = (*normal + f.dndu * duv[0] + f.dndv * duv[1]).unit();
+                        *v - n * ((*v * n) * 2.0)
+                    };
+                    Ray::with_differentials(
+                        *gc,
+                        ps,
+                        (d.x.0, reflect_at(&d.x.1, &f.dx)),
+                        (d.y.0, reflect_at(&d.y.1, &f.dy)),
+                    )
+                }
+                None => Ray::new(*gc, ps),
+            };
+            trace(&ray, m, depth)
+        };
+        result += self.kr.colorize(&tr);
+
+        result
+    }
+
+    // Average of the scene reflected in facets whose normals
+    // are drawn from a GGX distribution of our roughness,
+    // stratified in the angle from the normal. Rays that
+    // would reflect into the surface count as black, which
+    // darkens a rough surface toward grazing. After the
+    // first bounce only one ray is traced, to keep the
+    // number of rays in check.
+    fn glossy(&self, gc: &Point3, normal: &Normal3, pt: &Vec3, m: &Model, depth: usize) -> Color {
+        let facets = Microfacets::new(Distribution::Ggx, self.roughness);
+        let frame = Frame::new(normal, &Vec3::default());
+        let n = if depth <= 1 { self.samples } else { 1 };
+        let mut sum = Color::new(0.0, 0.0, 0.0);
+        for k in 0..n {
+            let wh =
+                frame.to_world(&facets.sample_wh((k as f64 + frandom()) / n as f64, frandom()));
+            let wi = reflect(&-*pt, &wh);
+            if wi * *normal > 0.0 {
+                sum += trace(&Ray::new(*gc, wi), m, depth);
+            }
+        }
+        sum * (1.0 / n as f64)
+    }
+}
+
+/// A Phong surface whose every lighting parameter is given
+/// by a pattern, so that it may vary across the surface.
+/// The shininess and roughness are the scalar values of
+/// their patterns. An optional bump perturbs the normal
+/// used for shading.
+pub struct Material {
+    pub ambient: Box<dyn Pattern>,
+    pub diffuse: Box<dyn Pattern>,
+    pub specular: Box<dyn Pattern>,
+    pub reflectivity: Box<dyn Pattern>,
+    pub shininess: Box<dyn Pattern>,
+    pub roughness: Box<dyn Pattern>,
+    /// Number of reflection rays traced where the surface
+    /// is rough.
+    pub samples: usize,
+    pub bump: Option<Bump>,
+}
+
+impl Material {
+    /// A matte material of the given diffuse pattern, with
+    /// no ambient, highlight or reflection.
+    pub fn new<P: Pattern + 'static>(diffuse: P) -> Self {
+        let kblack = Color::new(0.0, 0.0, 0.0);
+        Self {
+            ambient: Box::new(kblack),
+            diffuse: Box::new(diffuse),
+            specular: Box::new(kblack),
+            reflectivity: Box::new(kblack),
+            shininess: Box::new(1.0),
+            roughness: Box::new(0.0),
+            samples: 0,
+            bump: None,
+        }
+    }
+
+    pub fn with_ambient<P: Pattern + 'static>(mut self, ambient: P) -> Self {
+        self.ambient = Box::new(ambient);
+        self
+    }
+
+    pub fn with_specular<P: Pattern + 'static>(mut self, specular: P) -> Self {
+        self.specular = Box::new(specular);
+        self
+    }
+
+    pub fn with_reflectivity<P: Pattern + 'static>(mut self, reflectivity: P) -> Self {
+        self.reflectivity = Box::new(reflectivity);
+        self
+    }
+
+    pub fn with_shininess<P: Pattern + 'static>(mut self, shininess: P) -> Self {
+        self.shininess = Box::new(shininess);
+        self
+    }
+
+    pub fn with_roughness<P: Pattern + 'static>(mut self, roughness: P) -> Self {
+        self.roughness = Box::new(roughness);
+        self
+    }
+
+    pub fn with_samples(mut self, samples: usize) -> Self {
+        self.samples = samples;
+        self
+    }
+
+    pub fn with_bump(mut self, bump: Bump) -> Self {
+        self.bump = Some(bump);
+        self
+    }
+
+    /// Shading normal at the given texture coordinates and
+    /// footprint, for a surface of the given unit normal.
+    pub fn shading_normal(&self, at: &Vec2, footprint: &Footprint, normal: &Normal3) -> Normal3 {
+        match &self.bump {
+            Some(bump) => bump.normal(at, footprint, normal),
+            None => *normal,
+        }
+    }
+
+    /// Lighting parameters at the given texture coordinates
+    /// and footprint.
+    pub fn phong(&self, at: &Vec2, footprint: &Footprint) -> Phong {
+        Phong {
+            ka: self.ambient.color(at, footprint),
+            kd: self.diffuse.color(at, footprint),
+            ks: self.specular.color(at, footprint),
+            kr: self.reflectivity.color(at, footprint),
+            ns: self.shininess.scalar(at, footprint),
+            roughness: self.roughness.scalar(at, footprint),
+            samples: self.samples,
+        }
+    }
+}
+
+impl Texture for Material {
+    /// Without a footprint the object-space point is not
+    /// known, so patterns are evaluated at the world-space
+    /// point instead, and bumps have no surface tangents to
+    /// follow.
+    fn value(&self, at: &Vec2, gc: &Point3, normal: &Normal3, m: &Model, depth: usize) -> Color {
+        let footprint = Footprint {
+            object: *gc,
+            ..Default::default()
+        };
+        let normal = self.shading_normal(at, &footprint, normal);
+        self.phong(at, &footprint)
+            .shade(gc, &normal, m, depth, None)
+    }
+
+    fn filtered_value(
+        &self,
+        at: &Vec2,
+        footprint: &Footprint,
+        gc: &Point3,
+        normal: &Normal3,
+        m: &Model,
+        depth: usize,
+    ) -> Color {
+        let normal = self.shading_normal(at, footprint, normal);
+        self.phong(at, footprint)
+            .shade(gc, &normal, m, depth, Some(footprint))
+    }
+}
+
+#[test]
+fn test_material_phong() {
+    let kred = Color::new(1.0, 0.0, 0.0);
+    let kblue = Color::new(0.0, 0.0, 1.0);
+    let material = Material::new(Checker::new(kred, kblue))
+        .with_specular(Color::new(0.5, 0.5, 0.5))
+        .with_shininess(Blend::new(10.0, 30.0, 0.25));
+
+    // The checker drives the diffuse color, and the blend
+    // the shininess, while the rest stay constant.
+    let footprint = Footprint::default();
+    let odd = material.phong(&Vec2::new(1.5, 0.5), &footprint);
+    let even = material.phong(&Vec2::new(0.5, 0.5), &footprint);
+    assert!((odd.kd[R] - 1.0).abs() < TINY && odd.kd[B].abs() < TINY);
+    assert!((even.kd[B] - 1.0).abs() < TINY && even.kd[R].abs() < TINY);
+    assert!((odd.ns - 15.0).abs() < TINY);
+    assert!((odd.ks[G] - 0.5).abs() < TINY && odd.kr[G].abs() < TINY);
+}
+
+#[test]
+fn test_phong_emitter_highlight() {
+    // A glowing sphere straight above a mirror at the origin,
+    // with the eye off to one side.
+    let kblack = Color::new(0.0, 0.0, 0.0);
+    let mut lamp = Sphere::default();
+    let xform = Xform::translation(&Vec3::new(0.0, 0.0, 4.0));
+    lamp.complete(&xform);
+    let lamp = Thing {
+        shape: Box::new(lamp),
+        texture: Box::new(Emissive::new(Color::new(2.0, 2.0, 2.0)).with_samples(64)),
+        xform,
+    };
+    let light = PointLight::new(Vec3::new(0.0, 0.0, -10.0), kblack);
+    let m = Model::new(
+        Vec3::new(5.0, 0.0, 1.0),
+        Box::new(light),
+        vec![lamp],
+        kblack,
+    );
+
+    let up = Vec3::new(0.0, 0.0, 1.0);
+    let highlight = |footprint: Option<&Footprint>| {
+        let shade = |ks: f64| {
+            let phong = Phong {
+                ka: kblack,
+                kd: kblack,
+                ks: Color::new(ks, ks, ks),
+                kr: Color::new(0.5, 0.5, 0.5),
+                ns: 10.0,
+                roughness: 0.0,
+                samples: 0,
+            };
+            phong.shade(&Vec3::default(), &up, &m, 1, footprint)[R]
+        };
+        shade(1.0) - shade(0.0)
+    };
+
+    // Seen straight down, the mirror shows a highlight of the
+    // lamp, though the eye would not see one.
+    let down = Footprint {
+        dir: -up,
+        ..Default::default()
+    };
+    assert!(highlight(Some(&down)) > 0.05);
+    assert!(highlight(None).abs() < TINY);
+}
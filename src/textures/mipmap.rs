@@ -0,0 +1,202 @@
+use crate::*;
+
+/// Largest ratio of the major to the minor axis of an EWA
+/// filter ellipse. Thinner ellipses are fattened, trading
+/// some blur for bounded work.
+const MAX_ANISOTROPY: f64 = 8.0;
+
+// One resolution of a mipmap.
+struct Level {
+    xsize: usize,
+    ysize: usize,
+    texels: Vec<Color>,
+}
+
+/// An image with successively halved copies of itself, for
+/// sampling it over areas of any size. Positions are in
+/// texture space: 0 to 1 across the image, left to right
+/// and top to bottom.
+pub struct MipMap {
+    levels: Vec<Level>,
+}
+
+impl MipMap {
+    pub fn new(image: &ImageBuffer) -> Self {
+        assert!(image.xsize > 0 && image.ysize > 0);
+        let texels = image
+            .pixels
+            .iter()
+            .map(|&[r, g, b]| Color::new(r as f64, g as f64, b as f64) * (1.0 / 255.0))
+            .collect();
+        let mut levels = vec![Level {
+            xsize: image.xsize,
+            ysize: image.ysize,
+            texels,
+        }];
+
+        // Halve until a single texel is left, averaging
+        // 2x2 blocks. An odd last row or column is averaged
+        // with itself.
+        while let Some(l) = levels.last().filter(|l| l.xsize > 1 || l.ysize > 1) {
+            let (xsize, ysize) = (l.xsize.div_ceil(2), l.ysize.div_ceil(2));
+            let at =
+                |x: usize, y: usize| l.texels[y.min(l.ysize - 1) * l.xsize + x.min(l.xsize - 1)];
+            let mut texels = Vec::with_capacity(xsize * ysize);
+            for y in 0..ysize {
+                for x in 0..xsize {
+                    let (x, y) = (2 * x, 2 * y);
+                    texels.push((at(x, y) + at(x + 1, y) + at(x, y + 1) + at(x + 1, y + 1)) * 0.25);
+                }
+            }
+            levels.push(Level {
+                xsize,
+                ysize,
+                texels,
+            });
+        }
+        Self { levels }
+    }
+
+    /// Number of levels, the first at full resolution and
+    /// the last a single texel.
+    pub fn len(&self) -> usize {
+        self.levels.len()
+    }
+
+    /// A mipmap always has its full-resolution level;
+    /// present for symmetry with `len()`.
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    fn texel(&self, level: usize, x: isize, y: isize, wrap: Wrap) -> Color {
+        let l = &self.levels[level];
+        l.texels[wrap.index(y, l.ysize) * l.xsize + wrap.index(x, l.xsize)]
+    }
+
+    /// Color at the given position of the given level,
+    /// interpolated between the four nearest texel centers.
+    pub fn bilinear(&self, level: usize, st: &Vec2, wrap: Wrap) -> Color {
+        let level = level.min(self.len() - 1);
+        let l = &self.levels[level];
+        let x = st[X] * l.xsize as f64 - 0.5;
+        let y = st[Y] * l.ysize as f64 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as isize, y0 as isize);
+        let t = |dx, dy| self.texel(level, x0 + dx, y0 + dy, wrap);
+        let top = t(0, 0) * (1.0 - fx) + t(1, 0) * fx;
+        let bottom = t(0, 1) * (1.0 - fx) + t(1, 1) * fx;
+        top * (1.0 - fy) + bottom * fy
+    }
+
+    // Blend of the two levels around the fractional level
+    // `lod` of a lookup.
+    fn between<F: Fn(usize) -> Color>(&self, lod: f64, f: F) -> Color {
+        let top = (self.len() - 1) as f64;
+        let lod = lod.clamp(0.0, top);
+        let l0 = lod.floor();
+        let frac = lod - l0;
+        let c0 = f(l0 as usize);
+        if frac < TINY {
+            c0
+        } else {
+            c0 * (1.0 - frac) + f(l0 as usize + 1) * frac
+        }
+    }
+
+    // Fractional level at which a texel is `width` of the
+    // full image across.
+    fn level_of(&self, width: f64) -> f64 {
+        (self.len() - 1) as f64 + width.max(1.0e-12).log2()
+    }
+
+    /// Color averaged over a square of the given width
+    /// around the given position, by bilinear lookups in
+    /// the two levels whose texels are nearest that size.
+    pub fn trilinear(&self, st: &Vec2, width: f64, wrap: Wrap) -> Color {
+        self.between(self.level_of(width), |l| self.bilinear(l, st, wrap))
+    }
+
+    /// Color averaged with Gaussian weights over the ellipse
+    /// around the given position with the given axes
+    /// (Heckbert's elliptically weighted average).
+    pub fn ewa(&self, st: &Vec2, d0: &Vec2, d1: &Vec2, wrap: Wrap) -> Color {
+        let (mut major, mut minor) = if d0.mag2() >= d1.mag2() {
+            (*d0, *d1)
+        } else {
+            (*d1, *d0)
+        };
+        let major_len = major.mag();
+        let mut minor_len = minor.mag();
+        if major_len < TINY {
+            return self.bilinear(0, st, wrap);
+        }
+        if minor_len * MAX_ANISOTROPY < major_len {
+            // Fatten the minor axis, keeping it perpendicular
+            // to the major one if it has no direction.
+            let scale = major_len / MAX_ANISOTROPY;
+            minor = if minor_len > TINY {
+                minor * (scale / minor_len)
+            } else {
+                Vec2::new(-major[Y], major[X]) * (scale / major_len)
+            };
+            minor_len = scale;
+        }
+        if major_len > 1.0 {
+            // Larger than the whole image: average it all.
+            major *= 1.0 / major_len;
+        }
+        self.between(self.level_of(minor_len), |l| {
+            self.ewa_level(l, st, &major, &minor, wrap)
+        })
+    }
+
+    fn ewa_level(&self, level: usize, st: &Vec2, d0: &Vec2, d1: &Vec2, wrap: Wrap) -> Color {
+        let l = &self.levels[level];
+        let (w, h) = (l.xsize as f64, l.ysize as f64);
+        // Work in texels of this level.
+        let s = st[X] * w - 0.5;
+        let t = st[Y] * h - 0.5;
+        let (ds0, dt0) = (d0[X] * w, d0[Y] * h);
+        let (ds1, dt1) = (d1[X] * w, d1[Y] * h);
+
+        // Implicit ellipse A s² + B s t + C t² = 1, grown by
+        // a texel so that it covers at least one.
+        let mut a = dt0 * dt0 + dt1 * dt1 + 1.0;
+        let mut b = -2.0 * (ds0 * dt0 + ds1 * dt1);
+        let mut c = ds0 * ds0 + ds1 * ds1 + 1.0;
+        let inv_f = 1.0 / (a * c - b * b / 4.0);
+        a *= inv_f;
+        b *= inv_f;
+        c *= inv_f;
+
+        // Bounding box of the ellipse.
+        let det = 4.0 * a * c - b * b;
+        let su = 2.0 * (c / det).sqrt();
+        let tv = 2.0 * (a / det).sqrt();
+        let (s0, s1) = ((s - su).ceil() as isize, (s + su).floor() as isize);
+        let (t0, t1) = ((t - tv).ceil() as isize, (t + tv).floor() as isize);
+
+        const ALPHA: f64 = 2.0;
+        let mut sum = Color::new(0.0, 0.0, 0.0);
+        let mut total = 0.0;
+        for it in t0..=t1 {
+            let tt = it as f64 - t;
+            for is in s0..=s1 {
+                let ss = is as f64 - s;
+                let r2 = a * ss * ss + b * ss * tt + c * tt * tt;
+                if r2 < 1.0 {
+                    let weight = (-ALPHA * r2).exp() - (-ALPHA).exp();
+                    sum += self.texel(level, is, it, wrap) * weight;
+                    total += weight;
+                }
+            }
+        }
+        if total > 0.0 {
+            sum * (1.0 / total)
+        } else {
+            self.bilinear(level, st, wrap)
+        }
+    }
+}
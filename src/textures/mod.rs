@@ -4,12 +4,138 @@ pub use self::solid_texture::*;
 pub mod board_texture;
 pub use self::board_texture::*;
 
+pub mod image_pattern;
+pub use self::image_pattern::*;
+
+pub mod mipmap;
+pub use self::mipmap::*;
+
+pub mod noise_pattern;
+pub use self::noise_pattern::*;
+
+pub mod pattern;
+pub use self::pattern::*;
+
+pub mod material;
+pub use self::material::*;
+
+pub mod bump;
+pub use self::bump::*;
+
+pub mod pbr_material;
+pub use self::pbr_material::*;
+
+pub mod emissive;
+pub use self::emissive::*;
+
 use crate::*;
 
+/// The area of a surface seen by the pixel a ray samples.
+#[derive(Clone, Copy, Default)]
+pub struct Footprint {
+    /// Change in texture coordinates one pixel over in x.
+    pub dx: Vec2,
+    /// Change in texture coordinates one pixel over in y.
+    pub dy: Vec2,
+    /// The offset rays of the incoming ray, with their
+    /// origins moved to where they meet the surface, for
+    /// carrying the footprint on through reflection.
+    pub rays: Option<RayDifferentials>,
+    /// The hit point in the coordinates of the shape hit, as
+    /// in `Intersection`, for solid textures that should move
+    /// with it.
+    pub object: Point3,
+    /// World-space tangents of the surface along the
+    /// texture coordinates, as in `Intersection`.
+    pub dpdu: Vec3,
+    pub dpdv: Vec3,
+    /// Change in the unit normal along the texture
+    /// coordinates, as in `Intersection`.
+    pub dndu: Vec3,
+    pub dndv: Vec3,
+    /// The same tangents in the coordinates of the shape
+    /// hit, for moving `object` along the surface.
+    pub object_dpdu: Vec3,
+    pub object_dpdv: Vec3,
+    /// Unit direction of the ray that hit, or zero if
+    /// unknown.
+    pub dir: Vec3,
+}
+
+/// The appearance of a surface: how it is lit and what it
+/// reflects. A `Material` builds one from `Pattern`s that
+/// say how its lighting parameters vary over the surface.
 pub trait Texture: Send + Sync {
     /// Given the scene model, a texture coordinate (usually just x and y),
     /// a unit vector pointing in the direction of intersection and
     /// an indication of how much recursion depth remains, return
     /// the color of the resulting ray.
-    fn value(&self, at: &Point, gc: &Point, normal: &Point, m: &Model, depth: usize) -> Color;
+    fn value(&self, at: &Vec2, gc: &Point3, normal: &Normal3, m: &Model, depth: usize) -> Color;
+
+    /// As `value()`, but averaged over the given footprint
+    /// to avoid aliasing. By default the footprint is
+    /// ignored.
+    fn filtered_value(
+        &self,
+        at: &Vec2,
+        _footprint: &Footprint,
+        gc: &Point3,
+        normal: &Normal3,
+        m: &Model,
+        depth: usize,
+    ) -> Color {
+        self.value(at, gc, normal, m, depth)
+    }
+
+    /// Light given off by the surface at the given point,
+    /// back along the footprint's ray. Black by default.
+    fn emitted(&self, _at: &Vec2, _footprint: &Footprint, _normal: &Normal3) -> Color {
+        Color::new(0.0, 0.0, 0.0)
+    }
+
+    /// Number of points to sample on a thing with this
+    /// texture when lighting other surfaces with it, or 0
+    /// if it gives off no light. By default 0.
+    fn light_samples(&self) -> usize {
+        0
+    }
+
+    /// How the light given off by a thing with this texture
+    /// weakens with distance when it lights other surfaces.
+    /// By default with the square of the distance, as
+    /// physical lights do.
+    fn light_falloff(&self) -> Falloff {
+        Falloff::InverseSquare
+    }
+}
+
+/// Share one texture among many things.
+impl<T: Texture + ?Sized> Texture for std::sync::Arc<T> {
+    fn value(&self, at: &Vec2, gc: &Point3, normal: &Normal3, m: &Model, depth: usize) -> Color {
+        (**self).value(at, gc, normal, m, depth)
+    }
+
+    fn filtered_value(
+        &self,
+        at: &Vec2,
+        footprint: &Footprint,
+        gc: &Point3,
+        normal: &Normal3,
+        m: &Model,
+        depth: usize,
+    ) -> Color {
+        (**self).filtered_value(at, footprint, gc, normal, m, depth)
+    }
+
+    fn emitted(&self, at: &Vec2, footprint: &Footprint, normal: &Normal3) -> Color {
+        (**self).emitted(at, footprint, normal)
+    }
+
+    fn light_samples(&self) -> usize {
+        (**self).light_samples()
+    }
+
+    fn light_falloff(&self) -> Falloff {
+        (**self).light_falloff()
+    }
 }
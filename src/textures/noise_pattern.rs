@@ -0,0 +1,186 @@
+use crate::*;
+
+/// A smooth run of colors, interpolated linearly between
+/// stops at positions from 0 to 1.
+#[derive(Clone)]
+pub struct ColorRamp {
+    stops: Vec<(f64, Color)>,
+}
+
+impl ColorRamp {
+    /// Ramp through the given stops, which are sorted by
+    /// position. There must be at least one.
+    pub fn new(mut stops: Vec<(f64, Color)>) -> Self {
+        assert!(!stops.is_empty(), "color ramp needs a stop");
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Self { stops }
+    }
+
+    /// Color at position `t`, held at the end colors past
+    /// the first and last stops.
+    pub fn at(&self, t: f64) -> Color {
+        let first = self.stops[0];
+        if t <= first.0 {
+            return first.1;
+        }
+        for w in self.stops.windows(2) {
+            let ((t0, c0), (t1, c1)) = (w[0], w[1]);
+            if t <= t1 {
+                let f = if t1 > t0 { (t - t0) / (t1 - t0) } else { 1.0 };
+                return c0 * (1.0 - f) + c1 * f;
+            }
+        }
+        self.stops[self.stops.len() - 1].1
+    }
+}
+
+/// A scalar field over 3-space built from solid noise,
+/// with values from 0 to 1.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Noise {
+    Perlin,
+    Simplex,
+    /// Distance to the nearest cellular feature point.
+    Worley,
+    Fbm {
+        octaves: usize,
+    },
+    Turbulence {
+        octaves: usize,
+    },
+    /// Veins along x, bent by turbulence.
+    Marble,
+    /// Rings around the z axis, warped by noise.
+    Wood,
+    /// Cell walls with fine speckle.
+    Granite,
+    /// Soft billows of fBm.
+    Clouds,
+}
+
+impl Noise {
+    /// Value of the field at `p`, from 0 to 1.
+    pub fn value(&self, p: &Point3) -> f64 {
+        let signed = |n: f64| 0.5 + 0.5 * n;
+        let t = match *self {
+            Noise::Perlin => signed(perlin(p)),
+            Noise::Simplex => signed(simplex(p)),
+            Noise::Worley => worley(p).0,
+            Noise::Fbm { octaves } => signed(fbm(p, octaves)),
+            Noise::Turbulence { octaves } => 2.0 * turbulence(p, octaves),
+            Noise::Marble => signed((p[X] * 4.0 + 6.0 * turbulence(p, 6)).sin()),
+            Noise::Wood => {
+                let r = (p[X] * p[X] + p[Y] * p[Y]).sqrt() * 8.0;
+                let g = r + 0.8 * fbm(&(*p * 2.0), 3);
+                g - g.floor()
+            }
+            Noise::Granite => {
+                let (f1, f2) = worley(&(*p * 6.0));
+                0.3 + (f2 - f1) + 0.8 * fbm(&(*p * 12.0), 4)
+            }
+            Noise::Clouds => signed(1.5 * fbm(p, 6)),
+        };
+        t.clamp(0.0, 1.0)
+    }
+}
+
+/// A solid pattern: a noise field, evaluated at the hit
+/// point in the coordinates of the thing hit so that it
+/// moves with the thing. Its color is given by a ramp, and
+/// its scalar value is the field itself.
+pub struct NoisePattern {
+    pub noise: Noise,
+    /// Number of pattern features per unit of object space.
+    pub scale: f64,
+    pub ramp: ColorRamp,
+}
+
+impl NoisePattern {
+    pub fn new(noise: Noise, ramp: ColorRamp) -> Self {
+        Self {
+            noise,
+            scale: 1.0,
+            ramp,
+        }
+    }
+
+    /// White marble with dark gray veins.
+    pub fn marble() -> Self {
+        let ramp = ColorRamp::new(vec![
+            (0.0, Color::new(0.25, 0.25, 0.28)),
+            (0.3, Color::new(0.75, 0.75, 0.78)),
+            (1.0, Color::new(0.95, 0.95, 0.93)),
+        ]);
+        Self::new(Noise::Marble, ramp)
+    }
+
+    /// Light and dark brown growth rings.
+    pub fn wood() -> Self {
+        let ramp = ColorRamp::new(vec![
+            (0.0, Color::new(0.75, 0.52, 0.3)),
+            (0.7, Color::new(0.6, 0.38, 0.2)),
+            (1.0, Color::new(0.4, 0.24, 0.12)),
+        ]);
+        Self::new(Noise::Wood, ramp)
+    }
+
+    /// Pink and gray speckled stone.
+    pub fn granite() -> Self {
+        let ramp = ColorRamp::new(vec![
+            (0.0, Color::new(0.15, 0.13, 0.13)),
+            (0.4, Color::new(0.55, 0.5, 0.5)),
+            (0.8, Color::new(0.8, 0.6, 0.58)),
+            (1.0, Color::new(0.9, 0.88, 0.86)),
+        ]);
+        Self::new(Noise::Granite, ramp)
+    }
+
+    /// White clouds in a blue sky.
+    pub fn clouds() -> Self {
+        let ramp = ColorRamp::new(vec![
+            (0.45, Color::new(0.3, 0.5, 0.9)),
+            (0.75, Color::new(1.0, 1.0, 1.0)),
+        ]);
+        Self::new(Noise::Clouds, ramp)
+    }
+
+    /// Value of the field at the object-space point `p`.
+    pub fn value(&self, p: &Point3) -> f64 {
+        self.noise.value(&(*p * self.scale))
+    }
+}
+
+impl Pattern for NoisePattern {
+    fn color(&self, _at: &Vec2, footprint: &Footprint) -> Color {
+        self.ramp.at(self.value(&footprint.object))
+    }
+
+    fn scalar(&self, _at: &Vec2, footprint: &Footprint) -> f64 {
+        self.value(&footprint.object)
+    }
+}
+
+#[test]
+fn test_noise_pattern() {
+    let ramp = ColorRamp::new(vec![
+        (1.0, Color::new(1.0, 1.0, 1.0)),
+        (0.0, Color::new(0.0, 0.0, 0.0)),
+    ]);
+    assert!((ramp.at(0.25)[G] - 0.25).abs() < TINY);
+    assert!((ramp.at(-1.0)[G]).abs() < TINY);
+    assert!((ramp.at(2.0)[G] - 1.0).abs() < TINY);
+
+    // The pattern depends only on the scaled object point.
+    let mut n = NoisePattern::new(Noise::Perlin, ramp);
+    let at = |p: Point3| Footprint {
+        object: p,
+        ..Default::default()
+    };
+    let c = n.color(&Vec2::default(), &at(Vec3::new(1.0, 2.0, 3.0)));
+    assert!((c[R] - 0.5).abs() < TINY);
+    n.scale = 2.0;
+    let p = Vec3::new(0.3, 0.1, 0.7);
+    let v = n.scalar(&Vec2::new(5.0, 5.0), &at(p));
+    assert!((v - Noise::Perlin.value(&(p * 2.0))).abs() < TINY);
+    assert!((n.color(&Vec2::default(), &at(p))[G] - v).abs() < TINY);
+}
@@ -0,0 +1,198 @@
+use std::sync::Arc;
+
+use crate::*;
+
+/// A color or scalar field over a surface, of its texture
+/// coordinates or of the object-space hit point in the
+/// footprint. Patterns say nothing about lighting: a
+/// `Material` uses them to vary its lighting parameters.
+pub trait Pattern: Send + Sync {
+    /// Color of the pattern at texture coordinates `at`,
+    /// averaged over the footprint where the pattern is
+    /// able.
+    fn color(&self, at: &Vec2, footprint: &Footprint) -> Color;
+
+    /// Value of the pattern as a scalar. By default this is
+    /// the luminance of its color.
+    fn scalar(&self, at: &Vec2, footprint: &Footprint) -> f64 {
+        let c = self.color(at, footprint);
+        0.2126 * c[R] + 0.7152 * c[G] + 0.0722 * c[B]
+    }
+}
+
+/// A constant color.
+impl Pattern for Color {
+    fn color(&self, _at: &Vec2, _footprint: &Footprint) -> Color {
+        *self
+    }
+}
+
+/// A constant scalar, whose color is the gray of that
+/// value.
+impl Pattern for f64 {
+    fn color(&self, _at: &Vec2, _footprint: &Footprint) -> Color {
+        Color::new(*self, *self, *self)
+    }
+
+    fn scalar(&self, _at: &Vec2, _footprint: &Footprint) -> f64 {
+        *self
+    }
+}
+
+/// Share one pattern among many materials.
+impl<T: Pattern + ?Sized> Pattern for Arc<T> {
+    fn color(&self, at: &Vec2, footprint: &Footprint) -> Color {
+        (**self).color(at, footprint)
+    }
+
+    fn scalar(&self, at: &Vec2, footprint: &Footprint) -> f64 {
+        (**self).scalar(at, footprint)
+    }
+}
+
+/// Fraction of the footprint around `at` lying in the odd
+/// squares of a checkerboard of unit squares in texture
+/// coordinates: those where exactly one of the u and v
+/// indices is odd. The board is box-filtered over the
+/// bounding box of the footprint, in closed form.
+pub fn checker_coverage(at: &Vec2, footprint: &Footprint) -> f64 {
+    // Fraction of `c ± d` in squares of odd index.
+    let odd = |c: f64, d: f64| {
+        if d < TINY {
+            return c.floor().rem_euclid(2.0);
+        }
+        // Integral from 0 of whether the square is odd.
+        let integral = |x: f64| {
+            let h = (x / 2.0).floor();
+            h + 2.0 * (x / 2.0 - h - 0.5).max(0.0)
+        };
+        (integral(c + d) - integral(c - d)) / (2.0 * d)
+    };
+    let width = |a: usize| footprint.dx[a].abs().max(footprint.dy[a].abs());
+    let sx = odd(at[X], width(X));
+    let sy = odd(at[Y], width(Y));
+    sx + sy - 2.0 * sx * sy
+}
+
+// Mix `f(false)` and `f(true)` by `t`, evaluating only the
+// ones that show.
+fn mix<T, F>(t: f64, f: F) -> T
+where
+    T: std::ops::Mul<f64, Output = T> + std::ops::Add<Output = T>,
+    F: Fn(bool) -> T,
+{
+    if t < TINY {
+        f(false)
+    } else if t > 1.0 - TINY {
+        f(true)
+    } else {
+        f(false) * (1.0 - t) + f(true) * t
+    }
+}
+
+/// A checkerboard of unit squares in texture coordinates,
+/// alternating between two patterns, filtered as by
+/// `checker_coverage()`.
+pub struct Checker {
+    /// Pattern of the squares where exactly one index is
+    /// odd.
+    pub odd: Box<dyn Pattern>,
+    /// Pattern of the other squares.
+    pub even: Box<dyn Pattern>,
+}
+
+impl Checker {
+    pub fn new<P: Pattern + 'static, Q: Pattern + 'static>(odd: P, even: Q) -> Self {
+        Self {
+            odd: Box::new(odd),
+            even: Box::new(even),
+        }
+    }
+
+    // The odd or even pattern.
+    fn side(&self, odd: bool) -> &dyn Pattern {
+        if odd {
+            self.odd.as_ref()
+        } else {
+            self.even.as_ref()
+        }
+    }
+}
+
+impl Pattern for Checker {
+    fn color(&self, at: &Vec2, footprint: &Footprint) -> Color {
+        let t = checker_coverage(at, footprint);
+        mix(t, |odd| self.side(odd).color(at, footprint))
+    }
+
+    fn scalar(&self, at: &Vec2, footprint: &Footprint) -> f64 {
+        let t = checker_coverage(at, footprint);
+        mix(t, |odd| self.side(odd).scalar(at, footprint))
+    }
+}
+
+/// A blend of two patterns, weighted by the scalar value of
+/// a third, the mask, clamped to 0 to 1: where the mask is
+/// 0 the first pattern shows, and where it is 1 the second.
+pub struct Blend {
+    pub a: Box<dyn Pattern>,
+    pub b: Box<dyn Pattern>,
+    pub mask: Box<dyn Pattern>,
+}
+
+impl Blend {
+    pub fn new<P, Q, M>(a: P, b: Q, mask: M) -> Self
+    where
+        P: Pattern + 'static,
+        Q: Pattern + 'static,
+        M: Pattern + 'static,
+    {
+        Self {
+            a: Box::new(a),
+            b: Box::new(b),
+            mask: Box::new(mask),
+        }
+    }
+
+    // The first or second pattern.
+    fn side(&self, second: bool) -> &dyn Pattern {
+        if second {
+            self.b.as_ref()
+        } else {
+            self.a.as_ref()
+        }
+    }
+}
+
+impl Pattern for Blend {
+    fn color(&self, at: &Vec2, footprint: &Footprint) -> Color {
+        let t = self.mask.scalar(at, footprint).clamp(0.0, 1.0);
+        mix(t, |b| self.side(b).color(at, footprint))
+    }
+
+    fn scalar(&self, at: &Vec2, footprint: &Footprint) -> f64 {
+        let t = self.mask.scalar(at, footprint).clamp(0.0, 1.0);
+        mix(t, |b| self.side(b).scalar(at, footprint))
+    }
+}
+
+#[test]
+fn test_patterns() {
+    let kred = Color::new(1.0, 0.0, 0.0);
+    let fp = Footprint::default();
+
+    // A blend nested in a checker.
+    let board = Checker::new(Blend::new(0.0, 1.0, 0.25), kred);
+    assert!((board.color(&Vec2::new(0.5, 0.5), &fp)[R] - 1.0).abs() < TINY);
+    assert!((board.scalar(&Vec2::new(1.5, 0.5), &fp) - 0.25).abs() < TINY);
+    assert!((board.scalar(&Vec2::new(1.5, 1.5), &fp) - 0.2126).abs() < TINY);
+
+    // A footprint covering many squares sees the average.
+    let wide = Footprint {
+        dx: Vec2::new(10.0, 0.0),
+        dy: Vec2::new(0.0, 10.0),
+        ..Default::default()
+    };
+    let c = Checker::new(1.0, 0.0).scalar(&Vec2::new(0.3, 0.6), &wide);
+    assert!((c - 0.5).abs() < 1.0e-6);
+}
@@ -0,0 +1,250 @@
+use crate::*;
+
+/// How a `PbrMaterial` scatters light.
+#[derive(Clone, Copy)]
+pub enum Scattering {
+    /// Lambertian diffuse, in the material color.
+    Lambert,
+    /// Oren-Nayar rough diffuse, in the material color, with
+    /// the roughness as the standard deviation of the facet
+    /// slopes in radians.
+    OrenNayar,
+    /// A metal of the given complex index of refraction,
+    /// tinted by the material color.
+    Conductor {
+        eta: Color,
+        k: Color,
+        distribution: Distribution,
+    },
+    /// A transparent dielectric of the given index of
+    /// refraction, tinted by the material color.
+    Dielectric {
+        eta: f64,
+        distribution: Distribution,
+    },
+    /// The principled metallic/roughness model, with the
+    /// material color as base color and the given dielectric
+    /// specular level.
+    Principled { specular: f64 },
+}
+
+impl Scattering {
+    /// One of the common metals.
+    pub fn metal(metal: Metal, distribution: Distribution) -> Self {
+        let (eta, k) = metal.ior();
+        Scattering::Conductor {
+            eta,
+            k,
+            distribution,
+        }
+    }
+}
+
+/// A physically based surface: a BSDF whose parameters are
+/// given by patterns. In the Whitted tracer the lights and
+/// glowing things are sampled directly, perfectly specular
+/// directions are followed exactly, and the light from the
+/// rest of the scene by way of rough or diffuse scattering
+/// is estimated by importance sampling the BSDF `samples`
+/// times, or ignored if that is 0.
+pub struct PbrMaterial {
+    pub scattering: Scattering,
+    pub color: Box<dyn Pattern>,
+    /// Perceptual roughness from 0 to 1 for the microfacet
+    /// models; facet slope deviation for Oren-Nayar.
+    pub roughness: Box<dyn Pattern>,
+    /// Metalness from 0 to 1 for the principled model.
+    pub metallic: Box<dyn Pattern>,
+    pub bump: Option<Bump>,
+    /// Number of directions sampled at a first hit; after
+    /// the first bounce at most one is used, to keep the
+    /// number of rays in check.
+    pub samples: usize,
+}
+
+impl PbrMaterial {
+    pub fn new(scattering: Scattering) -> Self {
+        Self {
+            scattering,
+            color: Box::new(Color::new(0.8, 0.8, 0.8)),
+            roughness: Box::new(0.5),
+            metallic: Box::new(0.0),
+            bump: None,
+            samples: 0,
+        }
+    }
+
+    pub fn with_color<P: Pattern + 'static>(mut self, color: P) -> Self {
+        self.color = Box::new(color);
+        self
+    }
+
+    pub fn with_roughness<P: Pattern + 'static>(mut self, roughness: P) -> Self {
+        self.roughness = Box::new(roughness);
+        self
+    }
+
+    pub fn with_metallic<P: Pattern + 'static>(mut self, metallic: P) -> Self {
+        self.metallic = Box::new(metallic);
+        self
+    }
+
+    pub fn with_bump(mut self, bump: Bump) -> Self {
+        self.bump = Some(bump);
+        self
+    }
+
+    pub fn with_samples(mut self, samples: usize) -> Self {
+        self.samples = samples;
+        self
+    }
+
+    /// The BSDF at the given texture coordinates and
+    /// footprint.
+    pub fn bsdf(&self, at: &Vec2, footprint: &Footprint) -> Box<dyn Bsdf> {
+        let color = self.color.color(at, footprint);
+        let roughness = self.roughness.scalar(at, footprint);
+        match self.scattering {
+            Scattering::Lambert => Box::new(Lambert { albedo: color }),
+            Scattering::OrenNayar => Box::new(OrenNayar::new(color, roughness)),
+            Scattering::Conductor {
+                eta,
+                k,
+                distribution,
+            } => {
+                let mut c = Conductor::new(eta, k, Microfacets::new(distribution, roughness));
+                c.tint = color;
+                Box::new(c)
+            }
+            Scattering::Dielectric { eta, distribution } => {
+                let mut d = Dielectric::new(eta, Microfacets::new(distribution, roughness));
+                d.tint = color;
+                Box::new(d)
+            }
+            Scattering::Principled { specular } => {
+                let metallic = self.metallic.scalar(at, footprint);
+                Box::new(Principled::new(color, metallic, roughness, specular))
+            }
+        }
+    }
+
+    // Light leaving the surface toward the viewer.
+    fn shade(
+        &self,
+        at: &Vec2,
+        footprint: &Footprint,
+        gc: &Point3,
+        normal: &Normal3,
+        m: &Model,
+        depth: usize,
+    ) -> Color {
+        let normal = match &self.bump {
+            Some(bump) => bump.normal(at, footprint, normal),
+            None => *normal,
+        };
+        let frame = Frame::new(&normal, &footprint.dpdu);
+        let view = if footprint.dir.mag2() > 0.0 {
+            -footprint.dir
+        } else {
+            (m.eye - *gc).unit()
+        };
+        let wo = frame.to_local(&view);
+        let bsdf = self.bsdf(at, footprint);
+
+        // Direct light.
+        let mut result = Color::new(0.0, 0.0, 0.0);
+        for s in m
+            .light_samples(gc, depth)
+            .into_iter()
+            .chain(m.emitted_light(gc, depth))
+        {
+            let wi = frame.to_local(&s.wi);
+            result += bsdf.eval(&wo, &wi).colorize(&s.li) * wi[Z].abs();
+        }
+
+        let incoming = |wi: &Vec3| trace(&Ray::new(*gc, frame.to_world(wi)), m, depth);
+        for (wi, c) in bsdf.specular(&wo) {
+            result += c.colorize(&incoming(&wi));
+        }
+
+        // Stratify the samples along one axis. Glowing
+        // things were sampled above, so the sampled
+        // directions leave them out.
+        let n = if depth <= 1 {
+            self.samples
+        } else {
+            self.samples.min(1)
+        };
+        for k in 0..n {
+            let u = Vec3::new(frandom(), (k as f64 + frandom()) / n as f64, frandom());
+            match bsdf.sample(&wo, &u) {
+                Some(s) if !s.specular && s.pdf > 0.0 => {
+                    let ray = Ray::new(*gc, frame.to_world(&s.wi));
+                    result +=
+                        s.weight().colorize(&trace_indirect(&ray, m, depth)) * (1.0 / n as f64);
+                }
+                _ => (),
+            }
+        }
+        result
+    }
+}
+
+impl Texture for PbrMaterial {
+    /// Without a footprint the object-space point is not
+    /// known, so patterns are evaluated at the world-space
+    /// point instead.
+    fn value(&self, at: &Vec2, gc: &Point3, normal: &Normal3, m: &Model, depth: usize) -> Color {
+        let footprint = Footprint {
+            object: *gc,
+            ..Default::default()
+        };
+        self.shade(at, &footprint, gc, normal, m, depth)
+    }
+
+    fn filtered_value(
+        &self,
+        at: &Vec2,
+        footprint: &Footprint,
+        gc: &Point3,
+        normal: &Normal3,
+        m: &Model,
+        depth: usize,
+    ) -> Color {
+        self.shade(at, footprint, gc, normal, m, depth)
+    }
+}
+
+#[test]
+fn test_pbr_shading() {
+    // Lit head on, a white Lambertian surface shows the
+    // light's color times its albedo.
+    let light = PointLight::new(Vec3::new(0.0, 0.0, 10.0), Color::new(1.0, 1.0, 1.0));
+    let bg = Color::new(0.2, 0.4, 0.6);
+    let m = Model::new(Vec3::new(0.0, 0.0, 5.0), Box::new(light), Vec::new(), bg);
+    let lambert = PbrMaterial::new(Scattering::Lambert).with_color(Color::new(0.5, 0.5, 0.5));
+    let normal = Vec3::new(0.0, 0.0, 1.0);
+    let c = lambert.value(&Vec2::default(), &Vec3::default(), &normal, &m, 1);
+    assert!((0..3).all(|k| (c[k] - 0.5).abs() < TINY));
+
+    // Clear glass loses no light: looking into a glass ball,
+    // whatever is reflected at each surface and whatever is
+    // refracted through it is background. Were the far side
+    // missed, the light would stay divided by the square of
+    // the index of refraction, and less than half would show.
+    let glass = PbrMaterial::new(Scattering::Dielectric {
+        eta: 1.5,
+        distribution: Distribution::Ggx,
+    })
+    .with_color(Color::new(1.0, 1.0, 1.0))
+    .with_roughness(0.0);
+    let ball = Thing {
+        shape: Box::new(Sphere::default()),
+        texture: Box::new(glass),
+        xform: Xform::identity(),
+    };
+    let light = PointLight::new(Vec3::new(0.0, 10.0, 0.0), Color::new(1.0, 1.0, 1.0));
+    let m = Model::new(Vec3::new(0.0, 0.0, 5.0), Box::new(light), vec![ball], bg);
+    let c = trace(&Ray::new(m.eye, Vec3::new(0.0, 0.0, -1.0)), &m, 0);
+    assert!((0..3).all(|k| (c[k] - bg[k]).abs() < TINY));
+}
@@ -6,51 +6,98 @@ pub struct SolidTexture {
     kd: Color,
     ks: Color,
     ns: f64,
+    roughness: f64,
+    samples: usize,
 }
 
 impl SolidTexture {
     pub fn new(ka: Color, kd: Color, ks: Color, ns: f64) -> Self {
-        Self { ka, kd, ks, ns }
+        Self {
+            ka,
+            kd,
+            ks,
+            ns,
+            roughness: 0.0,
+            samples: 0,
+        }
     }
-}
 
-impl Texture for SolidTexture {
-    fn value(&self, _at: &Point, gc: &Point, normal: &Point, m: &Model, depth: usize) -> Color {
-        // Start with ambient term.
-        let mut result = self.ka;
-
-        let pl = m.the_light.at();
-        let pli = m.the_light.i();
-        let pe = m.eye.clone();
-        // Unit vector toward the light.
-        let lv = (pl - gc.clone()).unit();
-
-        // Diffuse term.
-        let fd = lv.clone() * normal.clone();
-        if fd > TINY {
-            result += self.kd.colorize(&pli) * fd;
+    /// The same texture with its reflection blurred by the
+    /// given roughness from 0 to 1, averaged over the given
+    /// number of reflection rays, as for brushed metal or a
+    /// satin finish.
+    pub fn glossy(&self, roughness: f64, samples: usize) -> Self {
+        Self {
+            roughness,
+            samples,
+            ..*self
+        }
+    }
+
+    /// The same texture with its ambient and diffuse colors
+    /// multiplied by the given color, as for a surface whose
+    /// color varies from place to place.
+    pub fn tinted(&self, c: &Color) -> Self {
+        Self {
+            ka: self.ka.colorize(c),
+            kd: self.kd.colorize(c),
+            ..*self
         }
+    }
+}
 
-        // Specular Terms.
-        // Unit vector from the eye toward the target.
-        let pt = (gc.clone() - pe).unit();
-        // Specular direction.
-        let ps = pt.clone() - normal.clone() * ((pt * normal.clone()) * 2.0);
-
-        // Specular diffusion term.
-        let fs = ps.clone() * lv;
-        if fs > 0.0 {
-            let fs = (self.ns * fs.ln()).exp();
-            if fs > TINY {
-                result += self.ks.colorize(&pli) * fs;
-            }
+impl SolidTexture {
+    /// Lighting parameters of the texture, which reflects
+    /// the scene in its specular color.
+    pub fn phong(&self) -> Phong {
+        Phong {
+            ka: self.ka,
+            kd: self.kd,
+            ks: self.ks,
+            kr: self.ks,
+            ns: self.ns,
+            roughness: self.roughness,
+            samples: self.samples,
         }
+    }
+}
 
-        // Ray tracing term.
-        let ray = Ray::new(gc.clone(), ps);
-        let tr = trace(&ray, m, depth);
-        result += self.ks.colorize(&tr);
+impl Texture for SolidTexture {
+    fn value(&self, _at: &Vec2, gc: &Point3, normal: &Normal3, m: &Model, depth: usize) -> Color {
+        self.phong().shade(gc, normal, m, depth, None)
+    }
 
-        result
+    fn filtered_value(
+        &self,
+        _at: &Vec2,
+        footprint: &Footprint,
+        gc: &Point3,
+        normal: &Normal3,
+        m: &Model,
+        depth: usize,
+    ) -> Color {
+        self.phong().shade(gc, normal, m, depth, Some(footprint))
     }
 }
+
+#[test]
+fn test_glossy_reflection() {
+    // An empty scene, lit from below so that only the
+    // reflection of the background shows.
+    let light = PointLight::new(Vec3::new(0.0, 0.0, -10.0), Color::new(1.0, 1.0, 1.0));
+    let bg = Color::new(0.2, 0.4, 0.6);
+    let m = Model::new(Vec3::new(0.0, 0.0, 5.0), Box::new(light), Vec::new(), bg);
+    let kblack = Color::new(0.0, 0.0, 0.0);
+    let mirror = SolidTexture::new(kblack, kblack, Color::new(0.5, 0.5, 0.5), 20.0);
+    let at = Vec2::default();
+    let gc = Vec3::default();
+    let normal = Vec3::new(0.0, 0.0, 1.0);
+    let c = mirror.value(&at, &gc, &normal, &m, 1);
+    assert!((c[B] - 0.3).abs() < TINY);
+
+    // Seen head on, almost every ray of a moderately rough
+    // reflection leaves the surface.
+    let c = mirror.glossy(0.2, 32).value(&at, &gc, &normal, &m, 1);
+    assert!(c[B] <= 0.3 + TINY && c[B] > 0.27);
+    assert!((c[R] * 3.0 - c[B]).abs() < TINY);
+}
@@ -12,6 +12,13 @@ pub struct Thing {
 }
 
 impl Thing {
+    /// Number of points to sample on the thing when lighting
+    /// other surfaces with it, or 0 if no part of it gives
+    /// off light.
+    pub fn light_samples(&self) -> usize {
+        self.texture.light_samples().max(self.shape.light_samples())
+    }
+
     /// XXX For now, hard-code the things in the scene. We
     /// will eventually fix this.
     pub fn generate() -> Vec<Thing> {
@@ -21,10 +28,10 @@ impl Thing {
             100.0 * TINY * (frandom() - 0.5)
         }
 
-        let ll = Point::new([-4.0, -4.0]);
-        let ul = Point::new([-4.0, 4.0]);
-        let ur = Point::new([4.0, 4.0]);
-        let lr = Point::new([4.0, -4.0]);
+        let ll = Vec2::new(-4.0, -4.0);
+        let ul = Vec2::new(-4.0, 4.0);
+        let ur = Vec2::new(4.0, 4.0);
+        let lr = Vec2::new(4.0, -4.0);
 
         let kyellow = Color::new(1.0, 0.9, 0.7);
         let kblue = Color::new(0.3, 0.3, 1.0);
@@ -40,9 +47,12 @@ impl Thing {
         let mut things = vec![];
 
         let mut p = Poly::new([ll, ul, ur, lr]);
-        let mut xform = Xform::rotation_y(-PI / 2.0);
-        xform *= &Xform::scaling(&Point::new([1.0, D / 8.0, D / 8.0]));
-        let xboard = Point::new([-joggle(), joggle(), zb + joggle()]);
+        // Turn the board over so that its face, +z, ends up
+        // facing the light.
+        let mut xform = Xform::scaling(&Vec3::new(1.0, 1.0, -1.0));
+        xform *= &Xform::rotation_y(-PI / 2.0);
+        xform *= &Xform::scaling(&Vec3::new(1.0, D / 8.0, D / 8.0));
+        let xboard = Vec3::new(-joggle(), joggle(), zb + joggle());
         xform *= &Xform::translation(&xboard);
         p.complete(&xform);
         let board = Thing {
@@ -54,8 +64,8 @@ impl Thing {
 
         let mut s = Sphere::default();
         let mut xform = Xform::identity();
-        xform *= &Xform::scaling(&Point::new([1.5, 1.5, 1.5]));
-        let x = Point::new([1.5 + joggle(), joggle(), zb + joggle()]);
+        xform *= &Xform::scaling(&Vec3::new(1.5, 1.5, 1.5));
+        let x = Vec3::new(1.5 + joggle(), joggle(), zb + joggle());
         xform *= &Xform::translation(&x);
         s.complete(&xform);
         let sgreen = Thing {
@@ -66,7 +76,7 @@ impl Thing {
         things.push(sgreen);
 
         let mut s = Sphere::default();
-        let x = Point::new([1.0 + joggle(), -D / 3.0 + joggle(), zb - D / 5.0 + joggle()]);
+        let x = Vec3::new(1.0 + joggle(), -D / 3.0 + joggle(), zb - D / 5.0 + joggle());
         let xform = Xform::translation(&x);
         s.complete(&xform);
         let sblue = Thing {
@@ -77,7 +87,7 @@ impl Thing {
         things.push(sblue);
 
         let mut s = Sphere::default();
-        let x = Point::new([1.0 + joggle(), D / 3.0 + joggle(), zb - D / 3.0 + joggle()]);
+        let x = Vec3::new(1.0 + joggle(), D / 3.0 + joggle(), zb - D / 3.0 + joggle());
         let xform = Xform::translation(&x);
         s.complete(&xform);
         let syellow = Thing {
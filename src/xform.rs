@@ -2,13 +2,114 @@ use std::ops::{Mul, MulAssign};
 
 use crate::*;
 
-// Convenience type for 4x4 matrices.
-type XFMatrix = [[f64; 4]; 4];
+/// Convenience type for 4x4 matrices.
+pub type XFMatrix = [[f64; 4]; 4];
+
+// Determinant of the upper-left 3x3 of a matrix.
+fn det3(m: &XFMatrix) -> f64 {
+    m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+        - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+        + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+}
+
+/// Errors in constructing a transformation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum XformError {
+    /// The supplied matrix has no inverse.
+    Singular,
+}
+
+impl std::fmt::Display for XformError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            XformError::Singular => write!(f, "singular transformation matrix"),
+        }
+    }
+}
+
+impl std::error::Error for XformError {}
+
+/// Transpose of a matrix.
+pub fn transpose_matrix(m: &XFMatrix) -> XFMatrix {
+    let mut t = XFMatrix::default();
+    for (r, row) in m.iter().enumerate() {
+        for (c, v) in row.iter().enumerate() {
+            t[c][r] = *v;
+        }
+    }
+    t
+}
+
+/// Inverse of a general matrix by Gauss-Jordan elimination
+/// with partial pivoting, or `None` if the matrix is
+/// singular or has elements that are not finite. Pivots
+/// are compared against the largest element of the matrix
+/// so that uniformly tiny or huge matrices are handled
+/// sensibly.
+pub fn invert_matrix(m: &XFMatrix) -> Option<XFMatrix> {
+    if m.iter().flatten().any(|v| !v.is_finite()) {
+        return None;
+    }
+    let big = m
+        .iter()
+        .flat_map(|row| row.iter())
+        .fold(0.0f64, |a, v| a.max(v.abs()));
+    if big == 0.0 {
+        return None;
+    }
+
+    let mut a = *m;
+    let mut inv = Xform::identity().m;
+    for c in 0..4 {
+        // Swap the largest remaining pivot into place.
+        let p = (c..4)
+            .max_by(|&i, &j| a[i][c].abs().total_cmp(&a[j][c].abs()))
+            .unwrap();
+        if a[p][c].abs() <= 1.0e-12 * big {
+            return None;
+        }
+        a.swap(c, p);
+        inv.swap(c, p);
+
+        let d = 1.0 / a[c][c];
+        for j in 0..4 {
+            a[c][j] *= d;
+            inv[c][j] *= d;
+        }
+        for r in 0..4 {
+            if r == c {
+                continue;
+            }
+            let f = a[r][c];
+            for j in 0..4 {
+                a[r][j] -= f * a[c][j];
+                inv[r][j] -= f * inv[c][j];
+            }
+        }
+    }
+    Some(inv)
+}
+
+/// Components of an affine transformation without shear,
+/// as produced by `Xform::decompose()`.
+#[derive(Clone)]
+pub struct Decomposition {
+    /// Offset, applied last.
+    pub translation: Vec3,
+    /// Rotation, applied after scaling.
+    pub rotation: Quaternion,
+    /// Per-axis scale, applied first.
+    pub scale: Vec3,
+}
 
 /// A transformation has a forward matrix and an inverse
 /// matrix, maintained in parallel for efficiency and ease
 /// of implementation.
-#[derive(Clone, Default)]
+///
+/// Transformations compose in the order they are written:
+/// `a * &b` and `a *= &b` both give the transformation that
+/// applies `a` first, then `b`.
+#[derive(Clone, Copy, Default)]
 pub struct Xform {
     /// Forward transformation matrix.
     pub m: XFMatrix,
@@ -95,8 +196,7 @@ impl Xform {
     }
 
     /// Translation transformation by given offset.
-    pub fn translation(trans: &Point) -> Self {
-        assert!(trans.len() == 3);
+    pub fn translation(trans: &Vec3) -> Self {
         let mut x = Xform::identity();
         x.m[0][3] = trans[0];
         x.m[1][3] = trans[1];
@@ -108,8 +208,7 @@ impl Xform {
     }
 
     /// Scaling transformation by given scale.
-    pub fn scaling(scale: &Point) -> Self {
-        assert!(scale.len() == 3);
+    pub fn scaling(scale: &Vec3) -> Self {
         let mut x = Xform::default();
         x.m[0][0] = scale[0];
         x.m[1][1] = scale[1];
@@ -122,6 +221,189 @@ impl Xform {
         x
     }
 
+    /// Transformation given by an arbitrary affine or
+    /// projective matrix. The inverse is computed, so fails
+    /// if the matrix is singular.
+    pub fn from_matrix(m: XFMatrix) -> Result<Self, XformError> {
+        let mi = invert_matrix(&m).ok_or(XformError::Singular)?;
+        Ok(Xform { m, mi })
+    }
+
+    /// Shear transformation. Each coordinate is offset by
+    /// the given multiples of the other two: `xy` is the
+    /// amount of y added to x, and so on. Panics if the
+    /// shear collapses space.
+    pub fn shearing(xy: f64, xz: f64, yx: f64, yz: f64, zx: f64, zy: f64) -> Self {
+        let m = [
+            [1.0, xy, xz, 0.0],
+            [yx, 1.0, yz, 0.0],
+            [zx, zy, 1.0, 0.0],
+            [0.0, 0.0, 0.0, 1.0],
+        ];
+        Xform::from_matrix(m).expect("degenerate shear")
+    }
+
+    /// Transformation placing an object at `eye` with its
+    /// +z axis pointing toward `target` and its +y axis as
+    /// close to `up` as possible. Panics if `up` is parallel
+    /// to the viewing direction.
+    pub fn look_at(eye: &Point3, target: &Point3, up: &Vec3) -> Self {
+        let f = (*target - *eye).unit();
+        let mut r = up.cross_product(&f);
+        assert!(r.mag() > TINY, "look_at up vector parallel to view");
+        r.unitize();
+        let u = f.cross_product(&r);
+
+        let mut x = Xform::identity();
+        for (c, v) in [&r, &u, &f].into_iter().enumerate() {
+            for i in 0..3 {
+                x.m[i][c] = v[i];
+                x.mi[c][i] = v[i];
+            }
+            x.mi[c][3] = -v.dot(eye);
+        }
+        for i in 0..3 {
+            x.m[i][3] = eye[i];
+        }
+        x
+    }
+
+    /// Transpose both the forward and inverse matrices.
+    pub fn transpose(&self) -> Self {
+        Xform {
+            m: transpose_matrix(&self.m),
+            mi: transpose_matrix(&self.mi),
+        }
+    }
+
+    /// The inverse transpose of the transformation, which
+    /// is the correct transformation for surface normals.
+    pub fn normal_xform(&self) -> Self {
+        self.inverse().transpose()
+    }
+
+    /// Rotation transformation around the given axis by
+    /// given angle in radians.
+    pub fn rotation(axis: &Vec3, angle: f64) -> Self {
+        Xform::from_quaternion(&Quaternion::from_axis_angle(axis, angle))
+    }
+
+    /// Rotation transformation given by a unit quaternion.
+    pub fn from_quaternion(q: &Quaternion) -> Self {
+        Xform {
+            m: q.to_matrix(),
+            mi: q.conjugate().to_matrix(),
+        }
+    }
+
+    /// Transformation that scales, then rotates, then
+    /// translates. This is the inverse of `decompose()`.
+    pub fn compose(d: &Decomposition) -> Self {
+        let mut x = Xform::scaling(&d.scale);
+        x *= &Xform::from_quaternion(&d.rotation);
+        x *= &Xform::translation(&d.translation);
+        x
+    }
+
+    /// Split an affine transformation into scale, rotation
+    /// and translation. Shear is not representable and will
+    /// be silently folded into the rotation and scale. A
+    /// reflection is represented as a negative x scale.
+    pub fn decompose(&self) -> Decomposition {
+        let m = &self.m;
+        let translation = Vec3::new(m[0][3], m[1][3], m[2][3]);
+
+        let col = |c: usize| Vec3::new(m[0][c], m[1][c], m[2][c]);
+        let cols = [col(0), col(1), col(2)];
+        let mut scale = Vec3::new(cols[0].mag(), cols[1].mag(), cols[2].mag());
+        if det3(m) < 0.0 {
+            scale[X] = -scale[X];
+        }
+
+        let mut r = Xform::identity().m;
+        for (c, v) in cols.iter().enumerate() {
+            for (row, rr) in r.iter_mut().take(3).enumerate() {
+                rr[c] = v[row] / scale[c];
+            }
+        }
+        let rotation = Quaternion::from_matrix(&r);
+
+        Decomposition {
+            translation,
+            rotation,
+            scale,
+        }
+    }
+
+    /// Interpolate between two affine transformations by
+    /// decomposing them, linearly interpolating translation
+    /// and scale and spherically interpolating rotation.
+    /// `t` of 0 gives `self`, `t` of 1 gives `x`.
+    pub fn interpolate(&self, x: &Xform, t: f64) -> Self {
+        let d0 = self.decompose();
+        let d1 = x.decompose();
+        let lerp = |p0: Vec3, p1: Vec3| p0 + (p1 - p0) * t;
+        Xform::compose(&Decomposition {
+            translation: lerp(d0.translation, d1.translation),
+            rotation: d0.rotation.slerp(&d1.rotation, t),
+            scale: lerp(d0.scale, d1.scale),
+        })
+    }
+
+    /// Apply the transformation to a location. A projective
+    /// transformation will have its result homogenized.
+    pub fn apply_point(&self, p: &Point3) -> Point3 {
+        let m = &self.m;
+        let mut r = Vec3::default();
+        for i in 0..3 {
+            r[i] = m[i][0] * p[X] + m[i][1] * p[Y] + m[i][2] * p[Z] + m[i][3];
+        }
+        let w = m[3][0] * p[X] + m[3][1] * p[Y] + m[3][2] * p[Z] + m[3][3];
+        if w != 1.0 {
+            r *= 1.0 / w;
+        }
+        r
+    }
+
+    /// Apply the transformation to a direction: translation
+    /// is ignored.
+    pub fn apply_vector(&self, v: &Vec3) -> Vec3 {
+        let m = &self.m;
+        let mut r = Vec3::default();
+        for i in 0..3 {
+            r[i] = m[i][0] * v[X] + m[i][1] * v[Y] + m[i][2] * v[Z];
+        }
+        r
+    }
+
+    /// Transform a surface normal by the inverse transpose
+    /// of the transformation, ignoring translation, and
+    /// unitize the result.
+    pub fn apply_normal(&self, n: &Normal3) -> Normal3 {
+        let mi = &self.mi;
+        let mut r = Vec3::default();
+        for i in 0..3 {
+            r[i] = mi[0][i] * n[X] + mi[1][i] * n[Y] + mi[2][i] * n[Z];
+        }
+        r.unit()
+    }
+
+    /// Rate of change of `apply_normal(n)` as `n`, which
+    /// need not be unit, changes at the rate `dn`.
+    pub fn apply_normal_change(&self, n: &Normal3, dn: &Vec3) -> Vec3 {
+        let mi = &self.mi;
+        let mut a = Vec3::default();
+        let mut da = Vec3::default();
+        for i in 0..3 {
+            a[i] = mi[0][i] * n[X] + mi[1][i] * n[Y] + mi[2][i] * n[Z];
+            da[i] = mi[0][i] * dn[X] + mi[1][i] * dn[Y] + mi[2][i] * dn[Z];
+        }
+        // Differentiate a / |a|.
+        let len = a.mag();
+        let u = a * (1.0 / len);
+        (da - u * (u * da)) * (1.0 / len)
+    }
+
     /// Invert the transformation by exchanging the
     /// forward and inverse matrices.
     pub fn invert(&mut self) {
@@ -131,20 +413,19 @@ impl Xform {
     /// Produce the inverse of a transformation with
     /// the forward and inverse matrices interchanged.
     pub fn inverse(&self) -> Self {
-        let mut tmp = self.clone();
+        let mut tmp = *self;
         tmp.invert();
         tmp
     }
 }
 
-impl Mul<&Point> for Xform {
-    type Output = Point;
+impl Mul<Vec4> for &Xform {
+    type Output = Vec4;
 
-    /// Apply the transformation to the given Point
-    /// by matrix-vector multiplication.
-    fn mul(self, rhs: &Point) -> Point {
-        assert!(rhs.len() == 4);
-        let mut t = Point::new([0.0; 4]);
+    /// Apply the transformation to the given homogeneous
+    /// vector by matrix-vector multiplication.
+    fn mul(self, rhs: Vec4) -> Vec4 {
+        let mut t = Vec4::default();
         for r in 0..4 {
             for c in 0..4 {
                 t[r] += self.m[r][c] * rhs[c];
@@ -154,61 +435,105 @@ impl Mul<&Point> for Xform {
     }
 }
 
-impl Mul<&Point> for &Xform {
-    type Output = Point;
+impl Mul<Vec4> for Xform {
+    type Output = Vec4;
 
-    /// Apply the transformation to the given Point
-    /// by matrix-vector multiplication.
-    fn mul(self, rhs: &Point) -> Point {
-        assert!(rhs.len() == 4);
-        let mut t = Point::new([0.0; 4]);
-        for r in 0..4 {
-            for c in 0..4 {
-                t[r] += self.m[r][c] * rhs[c];
-            }
-        }
-        t
+    /// Apply the transformation to the given homogeneous
+    /// vector by matrix-vector multiplication.
+    fn mul(self, rhs: Vec4) -> Vec4 {
+        &self * rhs
     }
 }
 
 impl Mul<&Xform> for Xform {
     type Output = Xform;
 
-    /// Compose two transformations by matrix multiplication
-    /// of the forward transformation matrix and reversed
-    /// matrix multiplication of the inverse transformation
-    /// matrix using `*` notation.
-    fn mul(self, rhs: &Xform) -> Xform {
-        let mut t = Xform::default();
-        for i in 0..4 {
-            for j in 0..4 {
-                for k in 0..4 {
-                    t.m[i][j] += self.m[i][k] * rhs.m[k][j];
-                    t.mi[i][j] += rhs.mi[i][k] * self.mi[k][j];
-                }
-            }
-        }
-        t
+    /// Compose two transformations using `*` notation:
+    /// `a * &b` applies `a`, then `b`, just as `a *= &b`.
+    fn mul(mut self, rhs: &Xform) -> Xform {
+        self *= rhs;
+        self
     }
 }
 
 impl MulAssign<&Xform> for Xform {
     /// Compose the transformation with a given
-    /// transformation by matrix multiplication of the
-    /// forward transformation matrix and reversed matrix
-    /// multiplication of the inverse transformation matrix
-    /// using `*=` notation.
+    /// transformation to be applied after it using `*=`
+    /// notation: the forward matrix is multiplied by `rhs` on
+    /// the left, and the inverse matrix on the right.
     fn mul_assign(&mut self, rhs: &Xform) {
-        let tmp = self.clone();
+        let tmp = *self;
         for i in 0..4 {
             for j in 0..4 {
-                self.m[i][j] = tmp.m[i][0] * rhs.m[0][j];
+                self.m[i][j] = rhs.m[i][0] * tmp.m[0][j];
                 self.mi[i][j] = tmp.mi[i][0] * rhs.mi[0][j];
                 for k in 1..4 {
-                    self.m[i][j] += tmp.m[i][k] * rhs.m[k][j];
+                    self.m[i][j] += rhs.m[i][k] * tmp.m[k][j];
                     self.mi[i][j] += tmp.mi[i][k] * rhs.mi[k][j];
                 }
             }
         }
     }
 }
+
+#[test]
+fn test_xform_decompose() {
+    let d = Decomposition {
+        translation: Vec3::new(1.0, -2.0, 3.0),
+        rotation: Quaternion::from_axis_angle(&Vec3::new(1.0, 1.0, 0.0), 0.7),
+        scale: Vec3::new(2.0, 0.5, 1.5),
+    };
+    let x = Xform::compose(&d);
+    let e = x.decompose();
+    for i in 0..3 {
+        assert!((d.translation[i] - e.translation[i]).abs() < TINY);
+        assert!((d.scale[i] - e.scale[i]).abs() < TINY);
+    }
+    assert!((d.rotation.dot(&e.rotation).abs() - 1.0).abs() < TINY);
+
+    let y = Xform::identity().interpolate(&x, 1.0);
+    for r in 0..4 {
+        for c in 0..4 {
+            assert!((x.m[r][c] - y.m[r][c]).abs() < TINY);
+        }
+    }
+}
+
+#[test]
+fn test_xform_from_matrix() {
+    let m = [
+        [2.0, 1.0, 0.0, 3.0],
+        [0.0, 0.0, 4.0, -1.0],
+        [1.0, 3.0, 1.0, 0.5],
+        [0.0, 0.0, 0.0, 1.0],
+    ];
+    let x = Xform::from_matrix(m).unwrap();
+    for i in 0..4 {
+        for j in 0..4 {
+            let p: f64 = (0..4).map(|k| x.m[i][k] * x.mi[k][j]).sum();
+            let e = if i == j { 1.0 } else { 0.0 };
+            assert!((p - e).abs() < TINY);
+        }
+    }
+
+    let mut m = m;
+    m[1] = m[0];
+    assert_eq!(Some(XformError::Singular), Xform::from_matrix(m).err());
+    m[1][1] = f64::NAN;
+    assert_eq!(Some(XformError::Singular), Xform::from_matrix(m).err());
+}
+
+#[test]
+fn test_xform_compose_order() {
+    let a = Xform::scaling(&Vec3::new(2.0, 3.0, 4.0));
+    let b = Xform::translation(&Vec3::new(1.0, -1.0, 0.5));
+    let ab = a * &b;
+    let mut c = a;
+    c *= &b;
+    let p = Vec3::new(0.5, 1.0, -2.0);
+    let q = b.apply_point(&a.apply_point(&p));
+    for x in [ab, c] {
+        assert!((x.apply_point(&p) - q).mag() < TINY);
+        assert!((x.inverse().apply_point(&q) - p).mag() < TINY);
+    }
+}
@@ -0,0 +1,159 @@
+//! Golden-image regression tests. Each reference scene is
+//! rendered small with a fixed PRNG seed and compared
+//! against the image checked in under `tests/golden/`.
+//!
+//! On failure a diff image is written to
+//! `target/golden-diff/`: mismatched pixels are red, the
+//! rest are a dimmed copy of the rendered image.
+//!
+//! After an intended rendering change, regenerate the
+//! golden images with
+//!
+//!     UPDATE_GOLDEN=1 cargo test --test golden
+
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter};
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard};
+
+use rt::*;
+
+/// Largest per-pixel perceptual difference that is not
+/// counted as a mismatch. Differences are in roughly the
+/// same units as 8-bit channel values.
+const PIXEL_TOLERANCE: f64 = 6.0;
+
+/// Largest fraction of mismatched pixels allowed.
+const MAX_MISMATCH: f64 = 0.002;
+
+// The PRNG is global, so scenes must be built and
+// rendered one at a time to be reproducible.
+static PRNG_LOCK: Mutex<()> = Mutex::new(());
+
+fn lock_prng() -> MutexGuard<'static, ()> {
+    PRNG_LOCK.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+// Perceptual distance between two 8-bit colors, using the
+// "redmean" weighted Euclidean approximation, scaled so
+// that a difference of `d` in every channel scores `d`.
+fn distance(a: [u8; 3], b: [u8; 3]) -> f64 {
+    let rm = (a[0] as f64 + b[0] as f64) / 2.0;
+    let d: Vec<f64> = (0..3).map(|i| a[i] as f64 - b[i] as f64).collect();
+    let wr = 2.0 + rm / 256.0;
+    let wg = 4.0;
+    let wb = 2.0 + (255.0 - rm) / 256.0;
+    ((wr * d[0] * d[0] + wg * d[1] * d[1] + wb * d[2] * d[2]) / 9.0).sqrt()
+}
+
+fn golden_path(name: &str) -> PathBuf {
+    [
+        env!("CARGO_MANIFEST_DIR"),
+        "tests",
+        "golden",
+        &format!("{}.ppm", name),
+    ]
+    .iter()
+    .collect()
+}
+
+fn diff_path(name: &str) -> PathBuf {
+    let dir: PathBuf = [env!("CARGO_MANIFEST_DIR"), "target", "golden-diff"]
+        .iter()
+        .collect();
+    fs::create_dir_all(&dir).unwrap();
+    dir.join(format!("{}.diff.ppm", name))
+}
+
+fn check(name: &str, m: &Model, w: usize, h: usize, antialias: Option<usize>) {
+    seed(None);
+    let mut im = ImageBuffer::new(w, h);
+    render(&mut im, m, w, h, true, antialias);
+
+    let golden = golden_path(name);
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        im.write_ppm(BufWriter::new(File::create(&golden).unwrap()))
+            .unwrap();
+        return;
+    }
+
+    let f = File::open(&golden).unwrap_or_else(|e| panic!("{}: {}", golden.display(), e));
+    let expected = ImageBuffer::read_ppm(BufReader::new(f)).unwrap();
+    assert_eq!((w, h), (expected.xsize, expected.ysize), "{}: size", name);
+
+    let mut diff = ImageBuffer::new(w, h);
+    let mut mismatches = 0;
+    let mut worst: f64 = 0.0;
+    for y in 0..h {
+        for x in 0..w {
+            let d = distance(im.get(x, y), expected.get(x, y));
+            worst = worst.max(d);
+            if d > PIXEL_TOLERANCE {
+                mismatches += 1;
+                diff.set(x, y, [255, 0, 0]);
+            } else {
+                diff.set(x, y, im.get(x, y).map(|c| c / 3));
+            }
+        }
+    }
+
+    let frac = mismatches as f64 / (w * h) as f64;
+    if frac > MAX_MISMATCH {
+        let path = diff_path(name);
+        diff.write_ppm(BufWriter::new(File::create(&path).unwrap()))
+            .unwrap();
+        panic!(
+            "{}: {} of {} pixels differ (worst {:.1}); diff in {}",
+            name,
+            mismatches,
+            w * h,
+            worst,
+            path.display(),
+        );
+    }
+}
+
+fn demo_model() -> Model {
+    seed(None);
+    Model::generate(Thing::generate())
+}
+
+// A single sphere with a checkerboard texture, lit from
+// the default light.
+fn sphere_model() -> Model {
+    let kwhite = Color::new(1.0, 1.0, 1.0);
+    let kred = Color::new(1.0, 0.2, 0.2);
+    let kblack = Color::new(0.0, 0.0, 0.0);
+    let twhite = SolidTexture::new(kblack, kwhite * 0.8, kwhite * 0.1, 20.0);
+    let tred = SolidTexture::new(kblack, kred * 0.8, kwhite * 0.1, 20.0);
+    let texture = BoardTexture::new(Box::new(twhite), Box::new(tred));
+
+    let mut s = Sphere::default();
+    let mut xform = Xform::scaling(&Vec3::new(2.0, 2.0, 2.0));
+    xform *= &Xform::translation(&Vec3::new(0.0, 0.0, D * A.cos()));
+    s.complete(&xform);
+    let thing = Thing {
+        shape: Box::new(s),
+        texture: Box::new(texture),
+        xform,
+    };
+    Model::generate(vec![thing])
+}
+
+#[test]
+fn golden_demo() {
+    let _prng = lock_prng();
+    check("demo", &demo_model(), 80, 60, None);
+}
+
+#[test]
+fn golden_demo_antialias() {
+    let _prng = lock_prng();
+    check("demo_antialias", &demo_model(), 48, 36, Some(4));
+}
+
+#[test]
+fn golden_sphere() {
+    let _prng = lock_prng();
+    check("sphere", &sphere_model(), 48, 48, None);
+}